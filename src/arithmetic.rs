@@ -0,0 +1,40 @@
+use crate::ast::ProductOperator;
+
+/// The result of `*`, `/`, `%` or `~/` on two numbers. Dividing by zero with `/` gives an
+/// infinity or NaN as in IEEE 754, but `%` and `~/` have no result for a divisor of zero.
+pub fn product(operator: &ProductOperator, left: f64, right: f64) -> Result<f64, String> {
+    match operator {
+        ProductOperator::Star => Ok(left * right),
+        ProductOperator::Slash => Ok(left / right),
+        ProductOperator::Percent | ProductOperator::TildeSlash if right == 0.0 => {
+            Err("Division by zero.".to_string())
+        }
+        ProductOperator::Percent => Ok(left % right),
+        ProductOperator::TildeSlash => Ok((left / right).trunc()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::ProductOperator;
+
+    use super::product;
+
+    #[test]
+    fn only_slash_divides_by_zero() {
+        assert_eq!(
+            product(&ProductOperator::Slash, -1.0, 0.0),
+            Ok(f64::NEG_INFINITY)
+        );
+        assert_eq!(product(&ProductOperator::Percent, -7.0, 2.0), Ok(-1.0));
+        assert_eq!(product(&ProductOperator::TildeSlash, -7.0, 2.0), Ok(-3.0));
+        assert_eq!(
+            product(&ProductOperator::Percent, 5.0, 0.0),
+            Err("Division by zero.".to_string())
+        );
+        assert_eq!(
+            product(&ProductOperator::TildeSlash, 5.0, -0.0),
+            Err("Division by zero.".to_string())
+        );
+    }
+}
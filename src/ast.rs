@@ -1,22 +1,172 @@
-#[derive(Debug, PartialEq)]
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::symbol::Symbol;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Expression(Node, i32),
+    Print(Node, i32),
+    /// A variable declaration, with the name of its type if it is annotated with one.
+    Var(Symbol, Option<Symbol>, Option<Node>, i32),
+    Block(Vec<Statement>),
+    If(Node, Box<Statement>, Option<Box<Statement>>),
+    While(Node, Box<Statement>),
+    /// The initializer, condition and increment clauses of a `for` loop, each of which may be
+    /// omitted, and its body.
+    For(Option<Box<Statement>>, Option<Node>, Option<Node>, Box<Statement>),
+    Function(Rc<Function>),
+    Class(Class),
+    Return(Option<Node>, i32),
+    /// An import of the module at a path, binding either the module or some of what it exports.
+    Import(Symbol, Imports, i32),
+    /// A variable, function or class declaration that other modules can import.
+    Export(Box<Statement>),
+    /// A block, with what handles an error thrown out of it and what runs after it either way,
+    /// at least one of which it has.
+    Try(Box<Statement>, Option<Catch>, Option<Box<Statement>>),
+    Throw(Node, i32),
+    /// A loop with a label, which `break` and `continue` in the loops nested in it can name.
+    Labelled(Symbol, Box<Statement>),
+    /// A `break`, out of the loop with the label if it has one and otherwise the innermost loop.
+    Break(Option<Symbol>, i32),
+    /// A `continue`, on to the next iteration of the loop with the label if it has one and
+    /// otherwise the innermost loop.
+    Continue(Option<Symbol>, i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Imports {
+    /// The module itself, under a name, like `import "a.lox" as a;`.
+    Module(Symbol),
+    /// Exports of the module, under their own names, like `import { b, c } from "a.lox";`.
+    Names(Vec<Symbol>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: Symbol,
+    pub parameters: Vec<Symbol>,
+    /// The name of the type each parameter is annotated with, if any.
+    pub parameter_types: Vec<Option<Symbol>>,
+    pub return_type: Option<Symbol>,
+    pub body: Vec<Statement>,
+    pub line_number: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class {
+    pub name: Symbol,
+    /// The variable naming the class inherited from, if any.
+    pub superclass: Option<Node>,
+    pub methods: Vec<Rc<Function>>,
+    pub line_number: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catch {
+    /// The variable the thrown value is bound to while the body runs.
+    pub name: Symbol,
+    pub body: Box<Statement>,
+    pub line_number: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     Expression(Box<Node>),
+    /// An expression in parentheses.
+    Grouping(Box<Node>),
     Series(Box<Node>, Box<Node>),
+    Assign(Symbol, Box<Node>, Depth, i32),
+    /// An assignment that combines a variable, property or element with a value, like `a += b`,
+    /// whose target is a `Variable`, `Get` or `Index` node.
+    CompoundAssign(AssignOperator, Box<Node>, Box<Node>, i32),
+    /// An increment or decrement of a variable, property or element, like `++a` or `a--`, which
+    /// gives the new value when the operator comes first and the old value when it comes last.
+    Increment(IncrementOperator, Fixity, Box<Node>, i32),
+    /// A choice between two values by a condition, like `a ? b : c`.
+    Conditional(Box<Node>, Box<Node>, Box<Node>),
+    Logical(LogicalOperator, Box<Node>, Box<Node>),
     Equality(EqualityOperator, Box<Node>, Box<Node>),
+    /// A bitwise `&`, `|` or `^` of two integers.
+    Bitwise(BitwiseOperator, Box<Node>, Box<Node>),
     Comparison(ComparisonOperator, Box<Node>, Box<Node>),
+    /// An integer with its bits shifted left or right, like `a << b`.
+    Shift(ShiftOperator, Box<Node>, Box<Node>),
     Sum(SumOperator, Box<Node>, Box<Node>),
     Product(ProductOperator, Box<Node>, Box<Node>),
+    /// A number raised to the power of another, like `a ** b`.
+    Power(Box<Node>, Box<Node>),
     Unary(UnaryOperator, Box<Node>),
+    Call(Box<Node>, Vec<Node>, i32),
+    /// A property of an object, like `a.b`.
+    Get(Box<Node>, Symbol, i32),
+    /// An assignment to a property of an object, like `a.b = c`.
+    Set(Box<Node>, Symbol, Box<Node>, i32),
+    /// A list literal, like `[a, b]`.
+    List(Vec<Node>),
+    /// A map literal, like `{a: b}`.
+    Map(Vec<(Node, Node)>, i32),
+    /// An element of a list or the value of a key in a map, like `a[b]`.
+    Index(Box<Node>, Box<Node>, i32),
+    /// An assignment to an element of a list or a key in a map, like `a[b] = c`.
+    SetIndex(Box<Node>, Box<Node>, Box<Node>, i32),
+    /// A copy of part of a list, like `a[b:c]`, where either bound may be omitted.
+    Slice(Box<Node>, Option<Box<Node>>, Option<Box<Node>>, i32),
+    This(Depth, i32),
+    /// A method of the superclass, like `super.b`.
+    Super(Symbol, Depth, i32),
+    Variable(Symbol, Depth, i32),
     Primary(Literal),
 }
 
-#[derive(Debug, PartialEq)]
+/// How many scopes out from its use a variable was declared, which the resolver records. A global
+/// counts as declared just outside every scope around its use, and a variable that was never
+/// resolved is looked for in each enclosing scope in turn.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Depth(Cell<Option<usize>>);
+
+impl Depth {
+    pub fn get(&self) -> Option<usize> {
+        self.0.get()
+    }
+
+    pub fn set(&self, depth: usize) {
+        self.0.set(Some(depth));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogicalOperator {
+    And,
+    Or,
+}
+
+impl LogicalOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogicalOperator::And => "and",
+            LogicalOperator::Or => "or",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum EqualityOperator {
     Equal,
     NotEqual,
 }
 
-#[derive(Debug, PartialEq)]
+impl EqualityOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EqualityOperator::Equal => "==",
+            EqualityOperator::NotEqual => "!=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ComparisonOperator {
     Greater,
     GreaterEqual,
@@ -24,29 +174,164 @@ pub enum ComparisonOperator {
     LessEqual,
 }
 
-#[derive(Debug, PartialEq)]
+impl ComparisonOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComparisonOperator::Greater => ">",
+            ComparisonOperator::GreaterEqual => ">=",
+            ComparisonOperator::Less => "<",
+            ComparisonOperator::LessEqual => "<=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitwiseOperator {
+    Ampersand,
+    Pipe,
+    Caret,
+}
+
+impl BitwiseOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BitwiseOperator::Ampersand => "&",
+            BitwiseOperator::Pipe => "|",
+            BitwiseOperator::Caret => "^",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShiftOperator {
+    LessLess,
+    /// A shift right that keeps the sign.
+    GreaterGreater,
+}
+
+impl ShiftOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShiftOperator::LessLess => "<<",
+            ShiftOperator::GreaterGreater => ">>",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum SumOperator {
     Plus,
     Minus,
 }
 
-#[derive(Debug, PartialEq)]
+impl SumOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SumOperator::Plus => "+",
+            SumOperator::Minus => "-",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ProductOperator {
     Star,
     Slash,
+    Percent,
+    /// Division rounded toward zero.
+    TildeSlash,
+}
+
+impl ProductOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProductOperator::Star => "*",
+            ProductOperator::Slash => "/",
+            ProductOperator::Percent => "%",
+            ProductOperator::TildeSlash => "~/",
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum UnaryOperator {
     Bang,
     Minus,
+    Tilde,
+}
+
+impl UnaryOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnaryOperator::Bang => "!",
+            UnaryOperator::Minus => "-",
+            UnaryOperator::Tilde => "~",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssignOperator {
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    StarStarEqual,
+    TildeSlashEqual,
+    AmpersandEqual,
+    PipeEqual,
+    CaretEqual,
+    LessLessEqual,
+    GreaterGreaterEqual,
+}
+
+impl AssignOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssignOperator::PlusEqual => "+=",
+            AssignOperator::MinusEqual => "-=",
+            AssignOperator::StarEqual => "*=",
+            AssignOperator::SlashEqual => "/=",
+            AssignOperator::PercentEqual => "%=",
+            AssignOperator::StarStarEqual => "**=",
+            AssignOperator::TildeSlashEqual => "~/=",
+            AssignOperator::AmpersandEqual => "&=",
+            AssignOperator::PipeEqual => "|=",
+            AssignOperator::CaretEqual => "^=",
+            AssignOperator::LessLessEqual => "<<=",
+            AssignOperator::GreaterGreaterEqual => ">>=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IncrementOperator {
+    PlusPlus,
+    MinusMinus,
+}
+
+impl IncrementOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IncrementOperator::PlusPlus => "++",
+            IncrementOperator::MinusMinus => "--",
+        }
+    }
+}
+
+/// Whether an operator comes before its operand or after it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fixity {
+    Prefix,
+    Postfix,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     Nil,
     True,
     False,
-    String(String),
+    String(Symbol),
     Number(f64),
 }
@@ -0,0 +1,91 @@
+use crate::ast::{BitwiseOperator, ShiftOperator};
+
+/// The result of `&`, `|` or `^` on two numbers that are integers.
+pub fn bitwise(operator: &BitwiseOperator, left: f64, right: f64) -> Result<f64, String> {
+    let (left, right) = (integer(left)?, integer(right)?);
+    Ok(match operator {
+        BitwiseOperator::Ampersand => left & right,
+        BitwiseOperator::Pipe => left | right,
+        BitwiseOperator::Caret => left ^ right,
+    } as f64)
+}
+
+/// The result of `<<` or `>>` on two numbers that are integers. Bits shifted past either end are
+/// dropped, and `>>` keeps the sign.
+pub fn shift(operator: &ShiftOperator, left: f64, right: f64) -> Result<f64, String> {
+    let (left, right) = (integer(left)?, integer(right)?);
+    let amount = u32::try_from(right)
+        .ok()
+        .filter(|amount| *amount < i64::BITS)
+        .ok_or_else(|| "Shift amount must be between 0 and 63.".to_string())?;
+    Ok(match operator {
+        ShiftOperator::LessLess => left << amount,
+        ShiftOperator::GreaterGreater => left >> amount,
+    } as f64)
+}
+
+/// The result of `~` on a number that is an integer.
+pub fn not(operand: f64) -> Result<f64, String> {
+    integer(operand)
+        .map(|operand| !operand as f64)
+        .map_err(|_| "Operand must be an integer.".to_string())
+}
+
+// The number as an `i64` if it is one exactly. `i64::MAX as f64` rounds up to 2^63, which is
+// out of range, so the upper bound is exclusive.
+fn integer(number: f64) -> Result<i64, String> {
+    if number.fract() == 0.0 && number >= i64::MIN as f64 && number < i64::MAX as f64 {
+        Ok(number as i64)
+    } else {
+        Err("Operands must be integers.".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{BitwiseOperator, ShiftOperator};
+
+    use super::{bitwise, not, shift};
+
+    #[test]
+    fn only_integers_in_range_have_bits() {
+        assert_eq!(bitwise(&BitwiseOperator::Caret, -1.0, 5.0), Ok(-6.0));
+        assert_eq!(
+            not(-9_223_372_036_854_775_808.0),
+            Ok(9_223_372_036_854_775_807.0)
+        );
+        assert_eq!(
+            bitwise(&BitwiseOperator::Pipe, 9_223_372_036_854_775_808.0, 0.0),
+            Err("Operands must be integers.".to_string())
+        );
+        assert_eq!(
+            bitwise(&BitwiseOperator::Ampersand, 1.5, 1.0),
+            Err("Operands must be integers.".to_string())
+        );
+        assert_eq!(
+            not(f64::NAN),
+            Err("Operand must be an integer.".to_string())
+        );
+        assert_eq!(
+            not(f64::INFINITY),
+            Err("Operand must be an integer.".to_string())
+        );
+    }
+
+    #[test]
+    fn shifts_are_limited_to_the_width_of_an_integer() {
+        assert_eq!(
+            shift(&ShiftOperator::LessLess, 1.0, 62.0),
+            Ok(4_611_686_018_427_387_904.0)
+        );
+        assert_eq!(shift(&ShiftOperator::GreaterGreater, -8.0, 2.0), Ok(-2.0));
+        assert_eq!(
+            shift(&ShiftOperator::LessLess, 1.0, 64.0),
+            Err("Shift amount must be between 0 and 63.".to_string())
+        );
+        assert_eq!(
+            shift(&ShiftOperator::GreaterGreater, 1.0, -1.0),
+            Err("Shift amount must be between 0 and 63.".to_string())
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::SyntaxKind;
+use crate::token::TokenType;
+
+/// A token of the tree, with its exact text. Green tokens don't know where they are, so equal
+/// tokens can be shared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenToken {
+    token_type: TokenType,
+    text: Rc<str>,
+}
+
+impl GreenToken {
+    pub fn new(token_type: TokenType, text: &str) -> GreenToken {
+        GreenToken {
+            token_type,
+            text: Rc::from(text),
+        }
+    }
+
+    pub fn token_type(&self) -> TokenType {
+        self.token_type
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// An immutable node of the tree, which only knows its kind, its children and the length of
+/// its text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    text_len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let text_len = children.iter().map(GreenElement::text_len).sum();
+        GreenNode {
+            kind,
+            text_len,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{}", node)?,
+                GreenElement::Token(token) => write!(f, "{}", token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn text_len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.text_len(),
+            GreenElement::Token(token) => token.text().len(),
+        }
+    }
+}
+
+/// Builds a green tree from the top down as a parser goes through its tokens.
+#[derive(Default)]
+pub struct GreenNodeBuilder {
+    // The nodes that have been started but not finished, and where their children start.
+    parents: Vec<(SyntaxKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+/// A position in the builder from which a node can be started after its first child was built,
+/// such as the left operand of a binary expression.
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint(usize);
+
+impl GreenNodeBuilder {
+    pub fn new() -> GreenNodeBuilder {
+        GreenNodeBuilder {
+            parents: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn start_node(&mut self, kind: SyntaxKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    /// Starts a node that takes as its children everything built since the checkpoint.
+    pub fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.parents.push((kind, checkpoint.0));
+    }
+
+    pub fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("no node to finish");
+        let children = self.children.split_off(first_child);
+        self.children
+            .push(GreenElement::Node(Rc::new(GreenNode::new(kind, children))));
+    }
+
+    pub fn token(&mut self, token_type: TokenType, text: &str) {
+        self.children
+            .push(GreenElement::Token(Rc::new(GreenToken::new(
+                token_type, text,
+            ))));
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+
+    /// Returns the root, which must be the only node built and must have been finished.
+    pub fn finish(mut self) -> GreenNode {
+        assert!(self.parents.is_empty(), "unfinished nodes");
+        match self.children.pop() {
+            Some(GreenElement::Node(root)) if self.children.is_empty() => {
+                Rc::try_unwrap(root).unwrap_or_else(|root| (*root).clone())
+            }
+            _ => panic!("the tree must have a single root node"),
+        }
+    }
+}
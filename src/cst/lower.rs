@@ -0,0 +1,568 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use super::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+use crate::ast::{
+    AssignOperator, BitwiseOperator, Catch, Class, ComparisonOperator, Depth, EqualityOperator,
+    Fixity, Function, Imports, IncrementOperator, Literal, LogicalOperator, Node, ProductOperator,
+    ShiftOperator, Statement, SumOperator, UnaryOperator,
+};
+use crate::symbol::Symbol;
+use crate::token::TokenType;
+
+/// Derives the statements of a program from its syntax tree, which must be free of errors.
+pub fn program(root: &SyntaxNode) -> Vec<Statement> {
+    let lowerer = Lowerer::new(root);
+    root.children()
+        .map(|statement| lowerer.statement(&statement))
+        .collect()
+}
+
+/// Derives the statements of a program like `program`, along with the text range of every statement,
+/// method and node in the order they were built: each one after its children, which are in source
+/// order. The `Expression` wrapping a clause has the same range as the clause.
+pub fn program_with_spans(root: &SyntaxNode) -> (Vec<Statement>, Vec<Range<usize>>) {
+    let lowerer = Lowerer::new(root);
+    let statements = root
+        .children()
+        .map(|statement| lowerer.statement(&statement))
+        .collect();
+    (statements, lowerer.spans.into_inner())
+}
+
+/// Derives the expression from the syntax tree of a program consisting of a single expression.
+pub fn expression(root: &SyntaxNode) -> Node {
+    let lowerer = Lowerer::new(root);
+    lowerer.expression(&nth_child(root, 0))
+}
+
+struct Lowerer {
+    // The byte offset at which each line of the source starts.
+    line_starts: Vec<usize>,
+    spans: RefCell<Vec<Range<usize>>>,
+}
+
+impl Lowerer {
+    fn new(root: &SyntaxNode) -> Lowerer {
+        let line_starts = std::iter::once(0)
+            .chain(
+                root.text()
+                    .match_indices('\n')
+                    .map(|(offset, _)| offset + 1),
+            )
+            .collect();
+        Lowerer {
+            line_starts,
+            spans: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn line_number(&self, token: &SyntaxToken) -> i32 {
+        let offset = token.text_range().start;
+        self.line_starts.partition_point(|&start| start <= offset) as i32
+    }
+
+    fn statement(&self, node: &SyntaxNode) -> Statement {
+        let statement = match node.kind() {
+            SyntaxKind::VarDeclaration => {
+                let name = token(node, TokenType::Identifier);
+                let initializer = node
+                    .children()
+                    .find(|child| child.kind() != SyntaxKind::TypeAnnotation)
+                    .map(|value| self.expression(&value));
+                Statement::Var(
+                    Symbol::intern(name.text()),
+                    type_annotation(node),
+                    initializer,
+                    self.line_number(&name),
+                )
+            }
+            SyntaxKind::FunctionDeclaration => Statement::Function(Rc::new(self.function(node))),
+            SyntaxKind::ClassDeclaration => {
+                let name = token(node, TokenType::Identifier);
+                let superclass = node
+                    .children()
+                    .find(|child| child.kind() == SyntaxKind::Variable)
+                    .map(|superclass| self.node(&superclass));
+                let methods = node
+                    .children()
+                    .filter(|child| child.kind() == SyntaxKind::Method)
+                    .map(|method| {
+                        let function = Rc::new(self.function(&method));
+                        self.spans.borrow_mut().push(method.text_range());
+                        function
+                    })
+                    .collect();
+                Statement::Class(Class {
+                    name: Symbol::intern(name.text()),
+                    superclass,
+                    methods,
+                    line_number: self.line_number(&name),
+                })
+            }
+            SyntaxKind::ImportDeclaration => self.import(node),
+            SyntaxKind::ExportDeclaration => {
+                Statement::Export(Box::new(self.statement(&nth_child(node, 0))))
+            }
+            SyntaxKind::Block => Statement::Block(self.statements(node)),
+            SyntaxKind::ExpressionStatement => Statement::Expression(
+                self.expression(&nth_child(node, 0)),
+                self.first_line_number(node),
+            ),
+            SyntaxKind::PrintStatement => Statement::Print(
+                self.expression(&nth_child(node, 0)),
+                self.first_line_number(node),
+            ),
+            SyntaxKind::ReturnStatement => Statement::Return(
+                node.children().next().map(|value| self.expression(&value)),
+                self.first_line_number(node),
+            ),
+            SyntaxKind::IfStatement => Statement::If(
+                self.expression(&nth_child(node, 0)),
+                Box::new(self.statement(&nth_child(node, 1))),
+                node.children()
+                    .nth(2)
+                    .map(|else_branch| Box::new(self.statement(&else_branch))),
+            ),
+            SyntaxKind::WhileStatement => Statement::While(
+                self.expression(&nth_child(node, 0)),
+                Box::new(self.statement(&nth_child(node, 1))),
+            ),
+            SyntaxKind::ForStatement => self.for_statement(node),
+            SyntaxKind::TryStatement => self.try_statement(node),
+            SyntaxKind::ThrowStatement => Statement::Throw(
+                self.expression(&nth_child(node, 0)),
+                self.first_line_number(node),
+            ),
+            SyntaxKind::LabelledStatement => Statement::Labelled(
+                Symbol::intern(token(node, TokenType::Identifier).text()),
+                Box::new(self.statement(&nth_child(node, 0))),
+            ),
+            SyntaxKind::BreakStatement => {
+                Statement::Break(label(node), self.first_line_number(node))
+            }
+            SyntaxKind::ContinueStatement => {
+                Statement::Continue(label(node), self.first_line_number(node))
+            }
+            kind => unreachable!("{:?} is not a statement", kind),
+        };
+        self.spans.borrow_mut().push(node.text_range());
+        statement
+    }
+
+    // Lowers a function declaration or a method, which differ only in the `fun` keyword. Each
+    // parameter's annotation follows it in the parameter list, and the return type's follows the
+    // list.
+    fn function(&self, node: &SyntaxNode) -> Function {
+        let name = token(node, TokenType::Identifier);
+        let mut parameters = Vec::new();
+        let mut parameter_types = Vec::new();
+        for element in nth_child(node, 0).children_with_tokens() {
+            match element {
+                SyntaxElement::Token(token) if token.token_type() == TokenType::Identifier => {
+                    parameters.push(Symbol::intern(token.text()));
+                    parameter_types.push(None);
+                }
+                SyntaxElement::Node(annotation) => {
+                    let name = token(&annotation, TokenType::Identifier);
+                    if let Some(parameter_type) = parameter_types.last_mut() {
+                        *parameter_type = Some(Symbol::intern(name.text()));
+                    }
+                }
+                SyntaxElement::Token(_) => {}
+            }
+        }
+        let body = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::Block)
+            .expect("a function has a body");
+        Function {
+            name: Symbol::intern(name.text()),
+            parameters,
+            parameter_types,
+            return_type: type_annotation(node),
+            body: self.statements(&body),
+            line_number: self.line_number(&name),
+        }
+    }
+
+    // Names between braces are imported from the module, and otherwise the only name is the one
+    // the module is bound to.
+    fn import(&self, node: &SyntaxNode) -> Statement {
+        let path = token(node, TokenType::String);
+        let mut names = node
+            .tokens()
+            .filter(|token| token.token_type() == TokenType::Identifier)
+            .map(|name| Symbol::intern(name.text()));
+        let imports = if node
+            .tokens()
+            .any(|token| token.token_type() == TokenType::LeftBrace)
+        {
+            Imports::Names(names.collect())
+        } else {
+            Imports::Module(names.next().expect("an import names its module"))
+        };
+        Statement::Import(
+            Symbol::intern(&path.text()[1..path.text().len() - 1]),
+            imports,
+            self.first_line_number(node),
+        )
+    }
+
+    // Only a catch clause names a variable, so the blocks after the body are told apart by whether
+    // there is one.
+    fn try_statement(&self, node: &SyntaxNode) -> Statement {
+        let mut blocks = node.children();
+        let body = Box::new(self.statement(&blocks.next().expect("a try has a body")));
+        let catch = node
+            .tokens()
+            .find(|token| token.token_type() == TokenType::Identifier)
+            .map(|name| Catch {
+                name: Symbol::intern(name.text()),
+                body: Box::new(self.statement(&blocks.next().expect("a catch has a body"))),
+                line_number: self.line_number(&name),
+            });
+        let finally = blocks.next().map(|block| Box::new(self.statement(&block)));
+        Statement::Try(body, catch, finally)
+    }
+
+    fn statements(&self, block: &SyntaxNode) -> Vec<Statement> {
+        block
+            .children()
+            .map(|statement| self.statement(&statement))
+            .collect()
+    }
+
+    // The clauses are told apart by the semicolons between them. The initializer's semicolon is
+    // part of its statement, so it ends the first clause too.
+    fn for_statement(&self, node: &SyntaxNode) -> Statement {
+        let mut initializer = None;
+        let mut condition = None;
+        let mut increment = None;
+        let mut body = None;
+        let mut clause = 0;
+        let mut after_clauses = false;
+        for element in node.children_with_tokens() {
+            match element {
+                SyntaxElement::Token(token) => match token.token_type() {
+                    TokenType::Semicolon => clause += 1,
+                    TokenType::RightParen => after_clauses = true,
+                    _ => {}
+                },
+                SyntaxElement::Node(child) if after_clauses => {
+                    body = Some(self.statement(&child));
+                }
+                SyntaxElement::Node(child) => match clause {
+                    0 => {
+                        initializer = Some(Box::new(self.statement(&child)));
+                        clause += 1;
+                    }
+                    1 => condition = Some(self.expression(&child)),
+                    _ => increment = Some(self.expression(&child)),
+                },
+            }
+        }
+        Statement::For(
+            initializer,
+            condition,
+            increment,
+            Box::new(body.expect("a for loop has a body")),
+        )
+    }
+
+    // Wraps the expression of a statement or clause, as the parser marks them.
+    fn expression(&self, node: &SyntaxNode) -> Node {
+        let expression = Node::Expression(Box::new(self.node(node)));
+        self.spans.borrow_mut().push(node.text_range());
+        expression
+    }
+
+    fn node(&self, node: &SyntaxNode) -> Node {
+        let lowered = match node.kind() {
+            SyntaxKind::Series => Node::Series(
+                Box::new(self.node(&nth_child(node, 0))),
+                Box::new(self.node(&nth_child(node, 1))),
+            ),
+            SyntaxKind::Assign => {
+                let target = token(&nth_child(node, 0), TokenType::Identifier);
+                Node::Assign(
+                    Symbol::intern(target.text()),
+                    Box::new(self.node(&nth_child(node, 1))),
+                    Depth::default(),
+                    self.line_number(&target),
+                )
+            }
+            SyntaxKind::CompoundAssign => {
+                let operator = node.tokens().next().expect("an assignment has an operator");
+                let assign_operator = match operator.token_type() {
+                    TokenType::PlusEqual => AssignOperator::PlusEqual,
+                    TokenType::MinusEqual => AssignOperator::MinusEqual,
+                    TokenType::StarEqual => AssignOperator::StarEqual,
+                    TokenType::SlashEqual => AssignOperator::SlashEqual,
+                    TokenType::PercentEqual => AssignOperator::PercentEqual,
+                    TokenType::StarStarEqual => AssignOperator::StarStarEqual,
+                    TokenType::TildeSlashEqual => AssignOperator::TildeSlashEqual,
+                    TokenType::AmpersandEqual => AssignOperator::AmpersandEqual,
+                    TokenType::PipeEqual => AssignOperator::PipeEqual,
+                    TokenType::CaretEqual => AssignOperator::CaretEqual,
+                    TokenType::LessLessEqual => AssignOperator::LessLessEqual,
+                    _ => AssignOperator::GreaterGreaterEqual,
+                };
+                Node::CompoundAssign(
+                    assign_operator,
+                    self.left(node),
+                    self.right(node),
+                    self.line_number(&operator),
+                )
+            }
+            // The operator comes before the operand's node when it is a prefix.
+            SyntaxKind::Increment => {
+                let operator = node.tokens().next().expect("an increment has an operator");
+                let operand = nth_child(node, 0);
+                let fixity = if operator.text_range().start < operand.text_range().start {
+                    Fixity::Prefix
+                } else {
+                    Fixity::Postfix
+                };
+                let increment_operator = match operator.token_type() {
+                    TokenType::PlusPlus => IncrementOperator::PlusPlus,
+                    _ => IncrementOperator::MinusMinus,
+                };
+                Node::Increment(
+                    increment_operator,
+                    fixity,
+                    Box::new(self.node(&operand)),
+                    self.line_number(&operator),
+                )
+            }
+            SyntaxKind::Conditional => Node::Conditional(
+                self.left(node),
+                self.right(node),
+                Box::new(self.node(&nth_child(node, 2))),
+            ),
+            SyntaxKind::Logical => {
+                let operator = match operator(node) {
+                    TokenType::And => LogicalOperator::And,
+                    _ => LogicalOperator::Or,
+                };
+                Node::Logical(operator, self.left(node), self.right(node))
+            }
+            SyntaxKind::Binary => {
+                let (left, right) = (self.left(node), self.right(node));
+                match operator(node) {
+                    TokenType::Ampersand => Node::Bitwise(BitwiseOperator::Ampersand, left, right),
+                    TokenType::Pipe => Node::Bitwise(BitwiseOperator::Pipe, left, right),
+                    TokenType::Caret => Node::Bitwise(BitwiseOperator::Caret, left, right),
+                    TokenType::EqualEqual => Node::Equality(EqualityOperator::Equal, left, right),
+                    TokenType::BangEqual => Node::Equality(EqualityOperator::NotEqual, left, right),
+                    TokenType::Greater => {
+                        Node::Comparison(ComparisonOperator::Greater, left, right)
+                    }
+                    TokenType::GreaterEqual => {
+                        Node::Comparison(ComparisonOperator::GreaterEqual, left, right)
+                    }
+                    TokenType::Less => Node::Comparison(ComparisonOperator::Less, left, right),
+                    TokenType::LessEqual => {
+                        Node::Comparison(ComparisonOperator::LessEqual, left, right)
+                    }
+                    TokenType::LessLess => Node::Shift(ShiftOperator::LessLess, left, right),
+                    TokenType::GreaterGreater => {
+                        Node::Shift(ShiftOperator::GreaterGreater, left, right)
+                    }
+                    TokenType::Plus => Node::Sum(SumOperator::Plus, left, right),
+                    TokenType::Minus => Node::Sum(SumOperator::Minus, left, right),
+                    TokenType::Star => Node::Product(ProductOperator::Star, left, right),
+                    TokenType::Slash => Node::Product(ProductOperator::Slash, left, right),
+                    TokenType::Percent => Node::Product(ProductOperator::Percent, left, right),
+                    TokenType::TildeSlash => {
+                        Node::Product(ProductOperator::TildeSlash, left, right)
+                    }
+                    TokenType::StarStar => Node::Power(left, right),
+                    token_type => unreachable!("{:?} is not a binary operator", token_type),
+                }
+            }
+            SyntaxKind::Unary => {
+                let operator = match operator(node) {
+                    TokenType::Bang => UnaryOperator::Bang,
+                    TokenType::Tilde => UnaryOperator::Tilde,
+                    _ => UnaryOperator::Minus,
+                };
+                Node::Unary(operator, Box::new(self.node(&nth_child(node, 0))))
+            }
+            SyntaxKind::Call => {
+                let arguments = nth_child(node, 1);
+                let left_paren = token(&arguments, TokenType::LeftParen);
+                Node::Call(
+                    Box::new(self.node(&nth_child(node, 0))),
+                    arguments
+                        .children()
+                        .map(|argument| self.node(&argument))
+                        .collect(),
+                    self.line_number(&left_paren),
+                )
+            }
+            SyntaxKind::Get => {
+                let name = token(node, TokenType::Identifier);
+                Node::Get(
+                    Box::new(self.node(&nth_child(node, 0))),
+                    Symbol::intern(name.text()),
+                    self.line_number(&name),
+                )
+            }
+            // The target is a `Get` node, whose object is the object of the assignment.
+            SyntaxKind::Set => {
+                let target = nth_child(node, 0);
+                let name = token(&target, TokenType::Identifier);
+                Node::Set(
+                    Box::new(self.node(&nth_child(&target, 0))),
+                    Symbol::intern(name.text()),
+                    Box::new(self.node(&nth_child(node, 1))),
+                    self.line_number(&name),
+                )
+            }
+            SyntaxKind::Index => {
+                let left_bracket = token(node, TokenType::LeftBracket);
+                Node::Index(
+                    self.left(node),
+                    self.right(node),
+                    self.line_number(&left_bracket),
+                )
+            }
+            // The target is an `Index` node, whose object and index are those of the assignment.
+            SyntaxKind::SetIndex => {
+                let target = nth_child(node, 0);
+                let left_bracket = token(&target, TokenType::LeftBracket);
+                Node::SetIndex(
+                    self.left(&target),
+                    self.right(&target),
+                    Box::new(self.node(&nth_child(node, 1))),
+                    self.line_number(&left_bracket),
+                )
+            }
+            SyntaxKind::Slice => self.slice(node),
+            SyntaxKind::List => {
+                Node::List(node.children().map(|element| self.node(&element)).collect())
+            }
+            SyntaxKind::Map => {
+                let left_brace = token(node, TokenType::LeftBrace);
+                let children: Vec<SyntaxNode> = node.children().collect();
+                Node::Map(
+                    children
+                        .chunks(2)
+                        .map(|entry| (self.node(&entry[0]), self.node(&entry[1])))
+                        .collect(),
+                    self.line_number(&left_brace),
+                )
+            }
+            SyntaxKind::This => Node::This(Depth::default(), self.first_line_number(node)),
+            SyntaxKind::Super => {
+                let method = token(node, TokenType::Identifier);
+                Node::Super(
+                    Symbol::intern(method.text()),
+                    Depth::default(),
+                    self.first_line_number(node),
+                )
+            }
+            SyntaxKind::Grouping => Node::Grouping(Box::new(self.node(&nth_child(node, 0)))),
+            SyntaxKind::Variable => {
+                let name = token(node, TokenType::Identifier);
+                Node::Variable(
+                    Symbol::intern(name.text()),
+                    Depth::default(),
+                    self.line_number(&name),
+                )
+            }
+            SyntaxKind::Literal => {
+                let literal = node.tokens().next().expect("a literal has a token");
+                let literal = match literal.token_type() {
+                    TokenType::Nil => Literal::Nil,
+                    TokenType::True => Literal::True,
+                    TokenType::False => Literal::False,
+                    TokenType::Number => Literal::Number(
+                        literal
+                            .text()
+                            .parse()
+                            .expect("the parser only accepts valid numbers"),
+                    ),
+                    _ => {
+                        let text = literal.text();
+                        Literal::String(Symbol::intern(&text[1..text.len() - 1]))
+                    }
+                };
+                Node::Primary(literal)
+            }
+            kind => unreachable!("{:?} is not an expression", kind),
+        };
+        self.spans.borrow_mut().push(node.text_range());
+        lowered
+    }
+
+    // The bounds of a slice are told apart by which side of the colon they are on.
+    fn slice(&self, node: &SyntaxNode) -> Node {
+        let left_bracket = token(node, TokenType::LeftBracket);
+        let object = self.left(node);
+        let mut start = None;
+        let mut end = None;
+        let mut after_colon = false;
+        for element in node.children_with_tokens().skip(1) {
+            match element {
+                SyntaxElement::Token(token) => {
+                    after_colon |= token.token_type() == TokenType::Colon
+                }
+                SyntaxElement::Node(bound) if after_colon => {
+                    end = Some(Box::new(self.node(&bound)))
+                }
+                SyntaxElement::Node(bound) => start = Some(Box::new(self.node(&bound))),
+            }
+        }
+        Node::Slice(object, start, end, self.line_number(&left_bracket))
+    }
+
+    fn left(&self, node: &SyntaxNode) -> Box<Node> {
+        Box::new(self.node(&nth_child(node, 0)))
+    }
+
+    fn right(&self, node: &SyntaxNode) -> Box<Node> {
+        Box::new(self.node(&nth_child(node, 1)))
+    }
+
+    fn first_line_number(&self, node: &SyntaxNode) -> i32 {
+        let first_token = node.first_token().expect("a node has tokens");
+        self.line_number(&first_token)
+    }
+}
+
+fn nth_child(node: &SyntaxNode, index: usize) -> SyntaxNode {
+    node.children()
+        .nth(index)
+        .unwrap_or_else(|| panic!("{:?} is missing child {}", node.kind(), index))
+}
+
+// The name of the type in the annotation directly under a node, if it has one.
+fn type_annotation(node: &SyntaxNode) -> Option<Symbol> {
+    node.children()
+        .find(|child| child.kind() == SyntaxKind::TypeAnnotation)
+        .map(|annotation| Symbol::intern(token(&annotation, TokenType::Identifier).text()))
+}
+
+// The label a `break` or `continue` names, if it names one.
+fn label(node: &SyntaxNode) -> Option<Symbol> {
+    node.tokens()
+        .find(|token| token.token_type() == TokenType::Identifier)
+        .map(|label| Symbol::intern(label.text()))
+}
+
+fn token(node: &SyntaxNode, token_type: TokenType) -> SyntaxToken {
+    node.tokens()
+        .find(|token| token.token_type() == token_type)
+        .unwrap_or_else(|| panic!("{:?} is missing a {:?} token", node.kind(), token_type))
+}
+
+// The operator of a unary or binary expression is its only token outside its operands.
+fn operator(node: &SyntaxNode) -> TokenType {
+    node.tokens()
+        .next()
+        .expect("an operation has an operator")
+        .token_type()
+}
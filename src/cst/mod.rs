@@ -0,0 +1,148 @@
+pub mod green;
+pub mod lower;
+pub mod red;
+
+use crate::parser::recursive_descent::RecursiveDescentParser;
+use crate::parser::Parse;
+use crate::scanner::Scanner;
+
+pub use green::{Checkpoint, GreenElement, GreenNode, GreenNodeBuilder, GreenToken};
+pub use red::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+/// The kinds of the nodes of the tree. Tokens are told apart by their `TokenType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    // Declarations and Statements
+    Program,
+    VarDeclaration,
+    FunctionDeclaration,
+    ClassDeclaration,
+    /// An import, whose identifiers are either the names imported or the name of the module.
+    ImportDeclaration,
+    /// An export, whose child is the declaration exported.
+    ExportDeclaration,
+    Method,
+    ParameterList,
+    /// A `:` and the name of a type, following a variable, parameter or parameter list.
+    TypeAnnotation,
+    Block,
+    ExpressionStatement,
+    PrintStatement,
+    IfStatement,
+    WhileStatement,
+    ForStatement,
+    ReturnStatement,
+    /// A `try` statement, whose children are its blocks, with the identifier of its `catch` clause
+    /// among its tokens if it has one.
+    TryStatement,
+    ThrowStatement,
+    /// A label and the loop it labels, which is its child.
+    LabelledStatement,
+    BreakStatement,
+    ContinueStatement,
+
+    // Expressions
+    Series,
+    Assign,
+    /// An assignment with an operator like `+=`, whose first child is its target.
+    CompoundAssign,
+    /// A `++` or `--` before or after its operand.
+    Increment,
+    Conditional,
+    Logical,
+    Binary,
+    Unary,
+    Call,
+    ArgumentList,
+    Get,
+    Set,
+    Index,
+    SetIndex,
+    Slice,
+    This,
+    Super,
+    Grouping,
+    List,
+    /// A map literal, whose children alternate between keys and their values.
+    Map,
+    Variable,
+    Literal,
+}
+
+/// Parses a program into its lossless syntax tree, which keeps every token including whitespace
+/// and comments, so that printing it gives back the source byte for byte.
+pub fn parse(source_code: &str) -> Result<SyntaxNode, crate::Error> {
+    let tokens = Scanner::with_trivia()
+        .scan(source_code)
+        .map_err(crate::Error::Scan)?;
+    RecursiveDescentParser::new()
+        .parse_syntax(&tokens)
+        .map_err(|mut parse_errors| crate::Error::Parse(parse_errors.remove(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::scanner::Scanner;
+
+    use super::lower;
+
+    const PROGRAMS: [&str; 7] = [
+        "",
+        "  // Only a comment\n\n",
+        "var a = (1 + 2) * -3; // trailing\n\n\nprint a,\ta;\n",
+        "fun f(x, y) {\n  // café\n  return x(y)(\"é\");\n}\nfor (var i = 0; i < 3; i = i + 1) print f;\n",
+        "var a : Number;\nfun g(b: String, c) : Bool { return b; }\n",
+        "var xs = [ 1, [ ], xs [ -1 ] ];\nxs[0] = xs[ : 2 ] , xs[1:], xs[:];\n",
+        "var m = { \"a\" : 1, 2: { } };\n{ m[\"a\"] = {nil:true}; }\n",
+    ];
+
+    #[test]
+    fn trees_give_back_the_source() {
+        for program in PROGRAMS {
+            let root = super::parse(program).unwrap();
+
+            assert_eq!(root.to_string(), program);
+            assert_eq!(root.text_range(), 0..program.len());
+        }
+    }
+
+    #[test]
+    fn lowering_gives_the_same_statements_with_or_without_trivia() {
+        for program in PROGRAMS {
+            let root = super::parse(program).unwrap();
+            let tokens = Scanner::new().scan(program).unwrap();
+            let statements = RecursiveDescentParser::new()
+                .parse_program(&tokens)
+                .unwrap();
+
+            assert_eq!(lower::program(&root), statements);
+        }
+    }
+
+    #[test]
+    fn trees_can_be_printed() {
+        let root = super::parse("print -a; // b\n").unwrap();
+        let expected_output = r#"Program@0..15
+  PrintStatement@0..9
+    Print@0..5 "print"
+    Whitespace@5..6 " "
+    Unary@6..8
+      Minus@6..7 "-"
+      Variable@7..8
+        Identifier@7..8 "a"
+    Semicolon@8..9 ";"
+  Whitespace@9..10 " "
+  Comment@10..14 "// b"
+  Whitespace@14..15 "\n"
+"#;
+
+        assert_eq!(format!("{:?}", root), expected_output);
+    }
+
+    #[test]
+    fn programs_with_errors_have_no_tree() {
+        assert!(super::parse("print (1;").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::symbol::Symbol;
+use crate::value::Value;
+
+/// The variables bound in one scope, linked to the scope that encloses it.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<Symbol, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new(enclosing: Option<Rc<RefCell<Environment>>>) -> Environment {
+        Environment {
+            values: HashMap::new(),
+            enclosing,
+        }
+    }
+
+    pub fn define(&mut self, name: Symbol, value: Value) {
+        self.values.insert(name, value);
+    }
+
+    /// The names bound directly in this scope.
+    pub fn names(&self) -> impl Iterator<Item = &Symbol> {
+        self.values.keys()
+    }
+
+    /// The variables bound directly in this scope, in no particular order.
+    pub fn bindings(&self) -> Vec<(Symbol, Value)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Looks a variable up in this scope and then in each enclosing scope.
+    pub fn get(&self, name: &Symbol) -> Option<Value> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .enclosing
+                .as_ref()
+                .and_then(|enclosing| enclosing.borrow().get(name)),
+        }
+    }
+
+    /// Looks a variable up starting from the scope `depth` scopes out from this one.
+    pub fn get_at(&self, depth: usize, name: &Symbol) -> Option<Value> {
+        match (depth, &self.enclosing) {
+            (0, _) => self.get(name),
+            (_, Some(enclosing)) => enclosing.borrow().get_at(depth - 1, name),
+            (_, None) => None,
+        }
+    }
+
+    /// Assigns to a variable starting from the scope `depth` scopes out from this one, returning
+    /// whether there was one.
+    pub fn assign_at(&mut self, depth: usize, name: &Symbol, value: Value) -> bool {
+        match (depth, &self.enclosing) {
+            (0, _) => self.assign(name, value),
+            (_, Some(enclosing)) => enclosing.borrow_mut().assign_at(depth - 1, name, value),
+            (_, None) => false,
+        }
+    }
+
+    /// Assigns to the innermost existing variable with this name, returning whether there was
+    /// one.
+    pub fn assign(&mut self, name: &Symbol, value: Value) -> bool {
+        if let Some(slot) = self.values.get_mut(name) {
+            *slot = value;
+            return true;
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => false,
+        }
+    }
+}
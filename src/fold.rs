@@ -0,0 +1,285 @@
+use std::rc::Rc;
+
+use crate::ast::{Catch, Class, Function, Node, Statement};
+
+/// Rewrites the AST by taking it apart and building it back up. By default each method folds the
+/// children of what it's given and rebuilds it unchanged, so implementations override the methods
+/// for what they rewrite and call the matching `walk_` function for everything else.
+pub trait Folder {
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        walk_statement(self, statement)
+    }
+
+    fn fold_function(&mut self, function: Function) -> Function {
+        walk_function(self, function)
+    }
+
+    fn fold_node(&mut self, node: Node) -> Node {
+        walk_node(self, node)
+    }
+}
+
+/// Folds every statement of a program.
+pub fn fold_program<F: Folder + ?Sized>(
+    folder: &mut F,
+    statements: Vec<Statement>,
+) -> Vec<Statement> {
+    statements
+        .into_iter()
+        .map(|statement| folder.fold_statement(statement))
+        .collect()
+}
+
+pub fn walk_statement<F: Folder + ?Sized>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::Expression(expression, line_number) => {
+            Statement::Expression(folder.fold_node(expression), line_number)
+        }
+        Statement::Print(expression, line_number) => {
+            Statement::Print(folder.fold_node(expression), line_number)
+        }
+        Statement::Var(name, annotation, initializer, line_number) => Statement::Var(
+            name,
+            annotation,
+            initializer.map(|initializer| folder.fold_node(initializer)),
+            line_number,
+        ),
+        Statement::Block(statements) => Statement::Block(fold_program(folder, statements)),
+        Statement::If(condition, then_branch, else_branch) => Statement::If(
+            folder.fold_node(condition),
+            fold_boxed_statement(folder, then_branch),
+            else_branch.map(|else_branch| fold_boxed_statement(folder, else_branch)),
+        ),
+        Statement::While(condition, body) => Statement::While(
+            folder.fold_node(condition),
+            fold_boxed_statement(folder, body),
+        ),
+        Statement::For(initializer, condition, increment, body) => Statement::For(
+            initializer.map(|initializer| fold_boxed_statement(folder, initializer)),
+            condition.map(|condition| folder.fold_node(condition)),
+            increment.map(|increment| folder.fold_node(increment)),
+            fold_boxed_statement(folder, body),
+        ),
+        // A function that is shared is copied before it is folded, leaving the other owners with
+        // the original.
+        Statement::Function(function) => {
+            let function = Rc::unwrap_or_clone(function);
+            Statement::Function(Rc::new(folder.fold_function(function)))
+        }
+        Statement::Class(class) => Statement::Class(Class {
+            superclass: class
+                .superclass
+                .map(|superclass| folder.fold_node(superclass)),
+            methods: class
+                .methods
+                .into_iter()
+                .map(|method| Rc::new(folder.fold_function(Rc::unwrap_or_clone(method))))
+                .collect(),
+            ..class
+        }),
+        Statement::Return(value, line_number) => {
+            Statement::Return(value.map(|value| folder.fold_node(value)), line_number)
+        }
+        Statement::Import(..) => statement,
+        Statement::Export(declaration) => {
+            Statement::Export(fold_boxed_statement(folder, declaration))
+        }
+        Statement::Try(body, catch, finally) => Statement::Try(
+            fold_boxed_statement(folder, body),
+            catch.map(|catch| Catch {
+                body: fold_boxed_statement(folder, catch.body),
+                ..catch
+            }),
+            finally.map(|finally| fold_boxed_statement(folder, finally)),
+        ),
+        Statement::Throw(value, line_number) => {
+            Statement::Throw(folder.fold_node(value), line_number)
+        }
+        Statement::Labelled(label, body) => {
+            Statement::Labelled(label, fold_boxed_statement(folder, body))
+        }
+        Statement::Break(..) | Statement::Continue(..) => statement,
+    }
+}
+
+pub fn walk_function<F: Folder + ?Sized>(folder: &mut F, function: Function) -> Function {
+    Function {
+        body: fold_program(folder, function.body),
+        ..function
+    }
+}
+
+pub fn walk_node<F: Folder + ?Sized>(folder: &mut F, node: Node) -> Node {
+    match node {
+        Node::Expression(expression) => Node::Expression(fold_boxed_node(folder, expression)),
+        Node::Grouping(expression) => Node::Grouping(fold_boxed_node(folder, expression)),
+        Node::Series(left, right) => Node::Series(
+            fold_boxed_node(folder, left),
+            fold_boxed_node(folder, right),
+        ),
+        Node::Assign(name, value, depth, line_number) => {
+            Node::Assign(name, fold_boxed_node(folder, value), depth, line_number)
+        }
+        Node::CompoundAssign(operator, target, value, line_number) => Node::CompoundAssign(
+            operator,
+            fold_boxed_node(folder, target),
+            fold_boxed_node(folder, value),
+            line_number,
+        ),
+        Node::Increment(operator, fixity, operand, line_number) => Node::Increment(
+            operator,
+            fixity,
+            fold_boxed_node(folder, operand),
+            line_number,
+        ),
+        Node::Conditional(condition, then_value, else_value) => Node::Conditional(
+            fold_boxed_node(folder, condition),
+            fold_boxed_node(folder, then_value),
+            fold_boxed_node(folder, else_value),
+        ),
+        Node::Logical(operator, left, right) => Node::Logical(
+            operator,
+            fold_boxed_node(folder, left),
+            fold_boxed_node(folder, right),
+        ),
+        Node::Equality(operator, left, right) => Node::Equality(
+            operator,
+            fold_boxed_node(folder, left),
+            fold_boxed_node(folder, right),
+        ),
+        Node::Bitwise(operator, left, right) => Node::Bitwise(
+            operator,
+            fold_boxed_node(folder, left),
+            fold_boxed_node(folder, right),
+        ),
+        Node::Comparison(operator, left, right) => Node::Comparison(
+            operator,
+            fold_boxed_node(folder, left),
+            fold_boxed_node(folder, right),
+        ),
+        Node::Shift(operator, left, right) => Node::Shift(
+            operator,
+            fold_boxed_node(folder, left),
+            fold_boxed_node(folder, right),
+        ),
+        Node::Sum(operator, left, right) => Node::Sum(
+            operator,
+            fold_boxed_node(folder, left),
+            fold_boxed_node(folder, right),
+        ),
+        Node::Product(operator, left, right) => Node::Product(
+            operator,
+            fold_boxed_node(folder, left),
+            fold_boxed_node(folder, right),
+        ),
+        Node::Power(base, exponent) => Node::Power(
+            fold_boxed_node(folder, base),
+            fold_boxed_node(folder, exponent),
+        ),
+        Node::Unary(operator, operand) => Node::Unary(operator, fold_boxed_node(folder, operand)),
+        Node::Call(callee, arguments, line_number) => Node::Call(
+            fold_boxed_node(folder, callee),
+            arguments
+                .into_iter()
+                .map(|argument| folder.fold_node(argument))
+                .collect(),
+            line_number,
+        ),
+        Node::Get(object, name, line_number) => {
+            Node::Get(fold_boxed_node(folder, object), name, line_number)
+        }
+        Node::Set(object, name, value, line_number) => Node::Set(
+            fold_boxed_node(folder, object),
+            name,
+            fold_boxed_node(folder, value),
+            line_number,
+        ),
+        Node::List(elements) => Node::List(
+            elements
+                .into_iter()
+                .map(|element| folder.fold_node(element))
+                .collect(),
+        ),
+        Node::Map(entries, line_number) => Node::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (folder.fold_node(key), folder.fold_node(value)))
+                .collect(),
+            line_number,
+        ),
+        Node::Index(object, index, line_number) => Node::Index(
+            fold_boxed_node(folder, object),
+            fold_boxed_node(folder, index),
+            line_number,
+        ),
+        Node::SetIndex(object, index, value, line_number) => Node::SetIndex(
+            fold_boxed_node(folder, object),
+            fold_boxed_node(folder, index),
+            fold_boxed_node(folder, value),
+            line_number,
+        ),
+        Node::Slice(object, start, end, line_number) => Node::Slice(
+            fold_boxed_node(folder, object),
+            start.map(|start| fold_boxed_node(folder, start)),
+            end.map(|end| fold_boxed_node(folder, end)),
+            line_number,
+        ),
+        Node::This(..) | Node::Super(..) | Node::Variable(..) | Node::Primary(_) => node,
+    }
+}
+
+// Folds a boxed child in place, reusing its allocation.
+fn fold_boxed_node<F: Folder + ?Sized>(folder: &mut F, mut node: Box<Node>) -> Box<Node> {
+    *node = folder.fold_node(*node);
+    node
+}
+
+fn fold_boxed_statement<F: Folder + ?Sized>(
+    folder: &mut F,
+    mut statement: Box<Statement>,
+) -> Box<Statement> {
+    *statement = folder.fold_statement(*statement);
+    statement
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Literal, Node};
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::printer::AstPrinter;
+    use crate::scanner::Scanner;
+    use crate::symbol::Symbol;
+
+    use super::{fold_program, walk_node, Folder};
+
+    // Replaces every variable named `x` with the number 0.
+    struct ZeroX;
+
+    impl Folder for ZeroX {
+        fn fold_node(&mut self, node: Node) -> Node {
+            match node {
+                Node::Variable(name, ..) if name == Symbol::intern("x") => {
+                    Node::Primary(Literal::Number(0.0))
+                }
+                node => walk_node(self, node),
+            }
+        }
+    }
+
+    #[test]
+    fn folders_reach_every_node() {
+        let tokens = Scanner::new()
+            .scan("fun f(x) { for (var i = x; i < -x; x = x + 1) if (x) return f(x); } var y = (x, x);")
+            .unwrap();
+        let statements = RecursiveDescentParser::new()
+            .parse_program(&tokens)
+            .unwrap();
+        let expected_output = "(fun f (x) (for (var i 0) (< i (- 0)) (= x (+ 0 1)) (if 0 (return (call f 0)))))\n(var y (group (, 0 0)))\n";
+
+        assert_eq!(
+            AstPrinter::print_program(&fold_program(&mut ZeroX, statements)),
+            expected_output
+        );
+    }
+}
@@ -0,0 +1,986 @@
+use crate::ast::{
+    Class, Fixity, Function, Imports, IncrementOperator, Literal, Node, Statement, UnaryOperator,
+};
+use crate::parser::recursive_descent::RecursiveDescentParser;
+use crate::parser::Parse;
+use crate::scanner::Scanner;
+use crate::symbol::Symbol;
+use crate::token::{Token, TokenType};
+
+const INDENT: &str = "    ";
+const MAX_WIDTH: usize = 80;
+
+/// Formats a program in the canonical style: four space indentation, one statement per line,
+/// single spaces around binary operators, and expressions and parameter lists that don't fit in 80
+/// columns broken up: the arguments of calls and the elements of lists and maps one per line, and
+/// chains of operators after each operator. Comments are kept, and blank lines between statements
+/// are collapsed to at most one.
+pub fn format(source_code: &str) -> Result<String, crate::Error> {
+    let mut tokens = Vec::new();
+    let mut comments = Vec::new();
+    for token in Scanner::with_trivia()
+        .scan(source_code)
+        .map_err(crate::Error::Scan)?
+    {
+        if token.token_type == TokenType::Comment {
+            comments.push(Comment {
+                before_token: tokens.len(),
+                line_number: token.line_number,
+                text: token.lexeme.trim_end(),
+            });
+        } else if token.token_type != TokenType::Whitespace {
+            tokens.push(token);
+        }
+    }
+
+    let statements = RecursiveDescentParser::new()
+        .parse_program(&tokens)
+        .map_err(|mut parse_errors| crate::Error::Parse(parse_errors.remove(0)))?;
+
+    let mut formatter = Formatter {
+        tokens: &tokens,
+        comments,
+        next_token: 0,
+        next_comment: 0,
+        indent: 0,
+        continuation: 0,
+        at_block_start: false,
+        line: String::new(),
+        output: String::new(),
+    };
+    for statement in &statements {
+        formatter.statement(statement);
+    }
+    formatter.start_line(false);
+    Ok(formatter.output)
+}
+
+struct Comment<'a> {
+    // The number of tokens before the comment, which places it in the token stream.
+    before_token: usize,
+    line_number: i32,
+    text: &'a str,
+}
+
+// Prints the AST while keeping track of the source token each piece of it came from, which tells
+// where the comments go and where the source had blank lines.
+struct Formatter<'a> {
+    tokens: &'a [Token<'a>],
+    comments: Vec<Comment<'a>>,
+    next_token: usize,
+    next_comment: usize,
+    indent: usize,
+    // How many levels deeper than `indent` the wrapped lines of the current line are.
+    continuation: usize,
+    at_block_start: bool,
+    line: String,
+    output: String,
+}
+
+impl Formatter<'_> {
+    fn statement(&mut self, statement: &Statement) {
+        self.start_line(true);
+        match statement {
+            Statement::Block(statements) => {
+                self.block(statements);
+                self.end_line();
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.token("if");
+                self.write(" ");
+                self.condition(condition);
+                self.write(" ");
+                let Some(else_branch) = else_branch else {
+                    self.statement(then_branch);
+                    return;
+                };
+                if let Statement::Block(statements) = then_branch.as_ref() {
+                    self.block(statements);
+                    self.continue_after_block();
+                } else {
+                    self.statement(then_branch);
+                    self.start_line(false);
+                }
+                self.token("else");
+                self.write(" ");
+                self.statement(else_branch);
+            }
+            Statement::While(condition, body) => {
+                self.token("while");
+                self.write(" ");
+                self.condition(condition);
+                self.write(" ");
+                self.statement(body);
+            }
+            Statement::For(initializer, condition, increment, body) => {
+                self.token("for");
+                self.write(" ");
+                self.token("(");
+                match initializer {
+                    Some(initializer) => self.simple_statement(initializer),
+                    None => self.token(";"),
+                }
+                if let Some(condition) = condition {
+                    self.write(" ");
+                    self.root_expression(condition);
+                }
+                self.token(";");
+                if let Some(increment) = increment {
+                    self.write(" ");
+                    self.root_expression(increment);
+                }
+                self.token(")");
+                self.write(" ");
+                self.statement(body);
+            }
+            Statement::Function(function) => {
+                self.token("fun");
+                self.write(" ");
+                self.function(function);
+                self.end_line();
+            }
+            Statement::Class(class) => {
+                self.class(class);
+                self.end_line();
+            }
+            Statement::Export(declaration) => {
+                self.token("export");
+                self.write(" ");
+                self.statement(declaration);
+            }
+            Statement::Try(body, catch, finally) => {
+                self.token("try");
+                self.write(" ");
+                self.clause(body);
+                if let Some(catch) = catch {
+                    self.continue_after_block();
+                    self.token("catch");
+                    self.write(" ");
+                    self.token("(");
+                    self.token(catch.name.as_str());
+                    self.token(")");
+                    self.write(" ");
+                    self.clause(&catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.continue_after_block();
+                    self.token("finally");
+                    self.write(" ");
+                    self.clause(finally);
+                }
+                self.end_line();
+            }
+            Statement::Labelled(label, body) => {
+                self.token(label.as_str());
+                self.token(":");
+                self.write(" ");
+                self.statement(body);
+            }
+            Statement::Expression(..)
+            | Statement::Print(..)
+            | Statement::Var(..)
+            | Statement::Return(..)
+            | Statement::Throw(..)
+            | Statement::Break(..)
+            | Statement::Continue(..)
+            | Statement::Import(..) => {
+                self.simple_statement(statement);
+                self.end_line();
+            }
+        }
+    }
+
+    // Writes a statement that ends in a semicolon, without ending the line.
+    fn simple_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(node, _) => self.root_expression(node),
+            Statement::Print(node, _) => {
+                self.token("print");
+                self.write(" ");
+                self.root_expression(node);
+            }
+            Statement::Var(name, annotation, initializer, _) => {
+                self.token("var");
+                self.write(" ");
+                self.token(name.as_str());
+                self.type_annotation(annotation.as_ref());
+                if let Some(initializer) = initializer {
+                    self.write(" ");
+                    self.token("=");
+                    self.write(" ");
+                    self.root_expression(initializer);
+                }
+            }
+            Statement::Return(value, _) => {
+                self.token("return");
+                if let Some(value) = value {
+                    self.write(" ");
+                    self.root_expression(value);
+                }
+            }
+            Statement::Throw(value, _) => {
+                self.token("throw");
+                self.write(" ");
+                self.root_expression(value);
+            }
+            Statement::Break(label, _) | Statement::Continue(label, _) => {
+                self.token(match statement {
+                    Statement::Break(..) => "break",
+                    _ => "continue",
+                });
+                if let Some(label) = label {
+                    self.write(" ");
+                    self.token(label.as_str());
+                }
+            }
+            Statement::Import(_, imports, _) => {
+                self.token("import");
+                self.write(" ");
+                match imports {
+                    Imports::Module(name) => {
+                        self.literal_token();
+                        self.write(" ");
+                        self.token("as");
+                        self.write(" ");
+                        self.token(name.as_str());
+                    }
+                    Imports::Names(names) => {
+                        self.token("{");
+                        for (index, name) in names.iter().enumerate() {
+                            if index > 0 {
+                                self.token(",");
+                                self.write(" ");
+                            }
+                            self.token(name.as_str());
+                        }
+                        self.token("}");
+                        self.write(" ");
+                        self.token("from");
+                        self.write(" ");
+                        self.literal_token();
+                    }
+                }
+            }
+            _ => unreachable!("only statements ending in a semicolon are written inline"),
+        }
+        self.token(";");
+    }
+
+    // Writes a block up to its closing brace, leaving the line open for an `else`.
+    fn block(&mut self, statements: &[Statement]) {
+        // Comments between a keyword and its brace would otherwise be moved above a line that may
+        // start with the closing brace of an earlier block.
+        if self.comment_before_next_token() {
+            self.line.truncate(self.line.trim_end().len());
+            self.end_line();
+            self.start_line(false);
+        }
+        self.token("{");
+        if statements.is_empty() && !self.comment_before_next_token() {
+            self.token("}");
+            return;
+        }
+
+        self.end_line();
+        self.indent += 1;
+        self.at_block_start = true;
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.start_line(false);
+        self.indent -= 1;
+        self.token("}");
+    }
+
+    // Separates the closing brace of a block from the keyword that continues its statement, which
+    // go on one line unless there are comments between them.
+    fn continue_after_block(&mut self) {
+        if self.comment_before_next_token() {
+            self.end_line();
+            self.start_line(false);
+        } else {
+            self.write(" ");
+        }
+    }
+
+    // Writes the block of a try statement or of one of its clauses, which are always blocks.
+    fn clause(&mut self, block: &Statement) {
+        match block {
+            Statement::Block(statements) => self.block(statements),
+            _ => unreachable!("the clauses of a try statement are blocks"),
+        }
+    }
+
+    // Writes a function from its name on, which is all of a method.
+    fn function(&mut self, function: &Function) {
+        self.token(function.name.as_str());
+
+        let checkpoint = self.checkpoint();
+        self.token("(");
+        for (index, parameter) in function.parameters.iter().enumerate() {
+            if index > 0 {
+                self.token(",");
+                self.write(" ");
+            }
+            self.token(parameter.as_str());
+            self.type_annotation(function.parameter_types[index].as_ref());
+        }
+        self.token(")");
+        self.type_annotation(function.return_type.as_ref());
+        if self.column() + " {".len() > MAX_WIDTH && !function.parameters.is_empty() {
+            self.rollback(checkpoint);
+            self.token("(");
+            for (index, parameter) in function.parameters.iter().enumerate() {
+                self.write_newline(1);
+                self.token(parameter.as_str());
+                self.type_annotation(function.parameter_types[index].as_ref());
+                if index + 1 < function.parameters.len() {
+                    self.token(",");
+                }
+            }
+            self.write_newline(0);
+            self.token(")");
+            self.type_annotation(function.return_type.as_ref());
+        }
+
+        self.write(" ");
+        self.block(&function.body);
+    }
+
+    fn type_annotation(&mut self, annotation: Option<&Symbol>) {
+        if let Some(annotation) = annotation {
+            self.token(":");
+            self.write(" ");
+            self.token(annotation.as_str());
+        }
+    }
+
+    // Writes a class up to its closing brace, with each method on lines of its own.
+    fn class(&mut self, class: &Class) {
+        self.token("class");
+        self.write(" ");
+        self.token(class.name.as_str());
+        if let Some(superclass) = &class.superclass {
+            self.write(" ");
+            self.token("<");
+            self.write(" ");
+            self.node(superclass, false);
+        }
+        self.write(" ");
+        self.token("{");
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.before_token <= self.next_token);
+        if class.methods.is_empty() && !has_comments {
+            self.token("}");
+            return;
+        }
+
+        self.end_line();
+        self.indent += 1;
+        self.at_block_start = true;
+        for method in &class.methods {
+            self.start_line(true);
+            self.function(method);
+            self.end_line();
+        }
+        self.start_line(false);
+        self.indent -= 1;
+        self.token("}");
+    }
+
+    fn condition(&mut self, condition: &Node) {
+        self.token("(");
+        self.root_expression(condition);
+        self.token(")");
+    }
+
+    // The parser wraps the expression of every statement and clause in a `Node::Expression`.
+    fn root_expression(&mut self, node: &Node) {
+        match node {
+            Node::Expression(expression) => self.expression(expression),
+            _ => self.expression(node),
+        }
+    }
+
+    // Writes an expression on the current line if it fits, and otherwise breaks up its calls,
+    // lists, maps and operator chains, each of whose parts then gets its own chance to fit.
+    fn expression(&mut self, node: &Node) {
+        let checkpoint = self.checkpoint();
+        self.node(node, false);
+        if self.column() > MAX_WIDTH && can_break(node) {
+            self.rollback(checkpoint);
+            self.node(node, true);
+        }
+    }
+
+    fn node(&mut self, node: &Node, break_lines: bool) {
+        match node {
+            Node::Expression(expression) => self.node(expression, break_lines),
+            Node::Grouping(expression) => {
+                self.token("(");
+                self.node(expression, break_lines);
+                self.token(")");
+            }
+            Node::Series(left, right) => {
+                self.node(left, break_lines);
+                self.token(",");
+                self.write(" ");
+                self.node(right, break_lines);
+            }
+            Node::Assign(name, value, ..) => {
+                self.token(name.as_str());
+                self.write(" ");
+                self.token("=");
+                self.write(" ");
+                self.node(value, break_lines);
+            }
+            Node::CompoundAssign(operator, target, value, _) => {
+                self.binary(target, operator.as_str(), value, break_lines);
+            }
+            Node::Increment(operator, Fixity::Prefix, operand, _) => {
+                self.token(operator.as_str());
+                self.node(operand, break_lines);
+            }
+            Node::Increment(operator, Fixity::Postfix, operand, _) => {
+                self.node(operand, break_lines);
+                self.token(operator.as_str());
+            }
+            Node::Conditional(condition, then_value, else_value) => {
+                self.node(condition, break_lines);
+                self.write(" ");
+                self.token("?");
+                self.write(" ");
+                self.node(then_value, break_lines);
+                self.write(" ");
+                self.token(":");
+                self.write(" ");
+                self.node(else_value, break_lines);
+            }
+            Node::Logical(..)
+            | Node::Bitwise(..)
+            | Node::Equality(..)
+            | Node::Comparison(..)
+            | Node::Shift(..)
+            | Node::Sum(..)
+            | Node::Product(..)
+            | Node::Power(..)
+                if break_lines =>
+            {
+                self.broken_chain(node);
+            }
+            Node::Logical(operator, left, right) => {
+                self.binary(left, operator.as_str(), right, break_lines);
+            }
+            Node::Bitwise(operator, left, right) => {
+                self.binary(left, operator.as_str(), right, break_lines);
+            }
+            Node::Equality(operator, left, right) => {
+                self.binary(left, operator.as_str(), right, break_lines);
+            }
+            Node::Comparison(operator, left, right) => {
+                self.binary(left, operator.as_str(), right, break_lines);
+            }
+            Node::Shift(operator, left, right) => {
+                self.binary(left, operator.as_str(), right, break_lines);
+            }
+            Node::Sum(operator, left, right) => {
+                self.binary(left, operator.as_str(), right, break_lines);
+            }
+            Node::Product(operator, left, right) => {
+                self.binary(left, operator.as_str(), right, break_lines);
+            }
+            Node::Power(base, exponent) => {
+                self.binary(base, "**", exponent, break_lines);
+            }
+            Node::Unary(operator, operand) => {
+                self.token(operator.as_str());
+                // `- -a` and `- --a` must not run together into `--a` and `---a`.
+                if let Node::Unary(UnaryOperator::Minus, _)
+                | Node::Increment(IncrementOperator::MinusMinus, Fixity::Prefix, _, _) =
+                    operand.as_ref()
+                {
+                    self.write(" ");
+                }
+                self.node(operand, break_lines);
+            }
+            Node::Call(callee, arguments, _) => {
+                self.node(callee, break_lines);
+                self.token("(");
+                if break_lines && !arguments.is_empty() {
+                    self.broken_elements(arguments, Self::expression);
+                } else {
+                    for (index, argument) in arguments.iter().enumerate() {
+                        if index > 0 {
+                            self.token(",");
+                            self.write(" ");
+                        }
+                        self.node(argument, false);
+                    }
+                }
+                self.token(")");
+            }
+            Node::Get(object, name, _) => {
+                self.node(object, break_lines);
+                self.token(".");
+                self.token(name.as_str());
+            }
+            Node::Set(object, name, value, _) => {
+                self.node(object, break_lines);
+                self.token(".");
+                self.token(name.as_str());
+                self.write(" ");
+                self.token("=");
+                self.write(" ");
+                self.node(value, break_lines);
+            }
+            Node::List(elements) => {
+                self.token("[");
+                if break_lines && !elements.is_empty() {
+                    self.broken_elements(elements, Self::expression);
+                } else {
+                    for (index, element) in elements.iter().enumerate() {
+                        if index > 0 {
+                            self.token(",");
+                            self.write(" ");
+                        }
+                        self.node(element, false);
+                    }
+                }
+                self.token("]");
+            }
+            Node::Map(entries, _) => {
+                self.token("{");
+                if break_lines && !entries.is_empty() {
+                    self.broken_elements(entries, Self::entry);
+                } else {
+                    for (index, entry) in entries.iter().enumerate() {
+                        if index > 0 {
+                            self.token(",");
+                            self.write(" ");
+                        }
+                        self.entry(entry);
+                    }
+                }
+                self.token("}");
+            }
+            Node::Index(object, index, _) => {
+                self.node(object, break_lines);
+                self.token("[");
+                self.node(index, break_lines);
+                self.token("]");
+            }
+            Node::SetIndex(object, index, value, _) => {
+                self.node(object, break_lines);
+                self.token("[");
+                self.node(index, break_lines);
+                self.token("]");
+                self.write(" ");
+                self.token("=");
+                self.write(" ");
+                self.node(value, break_lines);
+            }
+            Node::Slice(object, start, end, _) => {
+                self.node(object, break_lines);
+                self.token("[");
+                if let Some(start) = start {
+                    self.node(start, break_lines);
+                }
+                self.token(":");
+                if let Some(end) = end {
+                    self.node(end, break_lines);
+                }
+                self.token("]");
+            }
+            Node::This(..) => self.token("this"),
+            Node::Super(method, ..) => {
+                self.token("super");
+                self.token(".");
+                self.token(method.as_str());
+            }
+            Node::Variable(name, ..) => self.token(name.as_str()),
+            Node::Primary(literal) => match literal {
+                Literal::Nil => self.token("nil"),
+                Literal::True => self.token("true"),
+                Literal::False => self.token("false"),
+                Literal::String(_) | Literal::Number(_) => self.literal_token(),
+            },
+        }
+    }
+
+    fn binary(&mut self, left: &Node, operator: &str, right: &Node, break_lines: bool) {
+        self.node(left, break_lines);
+        self.write(" ");
+        self.token(operator);
+        self.write(" ");
+        self.node(right, break_lines);
+    }
+
+    // Writes a chain of operators of the same precedence, like `a + b - c`, with a line break after
+    // each operator.
+    fn broken_chain(&mut self, node: &Node) {
+        let (left, operator, right) =
+            binary_operands(node).expect("only binary operators are chained");
+        if binary_operands(left).is_some() && same_precedence(left, node) {
+            self.broken_chain(left);
+        } else {
+            self.expression(left);
+        }
+        self.write(" ");
+        self.token(operator);
+        self.write_newline(1);
+        self.continuation += 1;
+        self.expression(right);
+        self.continuation -= 1;
+    }
+
+    // Writes the arguments of a call or the elements of a list or map one per line, a level
+    // deeper than the brackets around them.
+    fn broken_elements<T>(&mut self, elements: &[T], mut element: impl FnMut(&mut Self, &T)) {
+        for (index, item) in elements.iter().enumerate() {
+            self.write_newline(1);
+            self.continuation += 1;
+            element(self, item);
+            self.continuation -= 1;
+            if index + 1 < elements.len() {
+                self.token(",");
+            }
+        }
+        self.write_newline(0);
+    }
+
+    fn entry(&mut self, (key, value): &(Node, Node)) {
+        self.node(key, false);
+        self.token(":");
+        self.write(" ");
+        self.expression(value);
+    }
+
+    // Writes the next source token, which the AST says is `text`.
+    fn token(&mut self, text: &str) {
+        debug_assert_eq!(self.tokens[self.next_token].lexeme, text);
+        self.next_token += 1;
+        self.line.push_str(text);
+    }
+
+    // Writes the next source token, a number or string, as it was written in the source.
+    fn literal_token(&mut self) {
+        let token = &self.tokens[self.next_token];
+        self.next_token += 1;
+        match token.token_type {
+            TokenType::String => {
+                self.line.push('"');
+                self.line.push_str(token.lexeme);
+                self.line.push('"');
+            }
+            _ => self.line.push_str(token.lexeme),
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        self.line.push_str(text);
+    }
+
+    // Continues the current line on a new one, `extra` levels deeper than the lines continued so
+    // far.
+    fn write_newline(&mut self, extra: usize) {
+        self.line.push('\n');
+        let depth = self.indent + self.continuation + extra;
+        self.line.push_str(&INDENT.repeat(depth));
+    }
+
+    fn column(&self) -> usize {
+        match self.line.rfind('\n') {
+            Some(index) => self.line[index + 1..].chars().count(),
+            None => self.indent * INDENT.len() + self.line.chars().count(),
+        }
+    }
+
+    fn checkpoint(&self) -> (usize, usize) {
+        (self.line.len(), self.next_token)
+    }
+
+    fn rollback(&mut self, (line_length, next_token): (usize, usize)) {
+        self.line.truncate(line_length);
+        self.next_token = next_token;
+    }
+
+    // Before a line starting with the next token, writes the comments that precede it on lines
+    // of their own, keeping a blank line wherever the source had one or more.
+    fn start_line(&mut self, allow_blank_line: bool) {
+        if !self.line.is_empty() {
+            return;
+        }
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.before_token > self.next_token {
+                break;
+            }
+            let (line_number, text) = (comment.line_number, comment.text);
+            self.blank_line_before(line_number);
+            self.push_line(text);
+            self.next_comment += 1;
+        }
+        if allow_blank_line {
+            if let Some(token) = self.tokens.get(self.next_token) {
+                self.blank_line_before(token.line_number);
+            }
+        }
+        self.at_block_start = false;
+    }
+
+    // Whether a comment comes right before the next token, rather than before one already written,
+    // which would be moved above the current line.
+    fn comment_before_next_token(&self) -> bool {
+        self.comments[self.next_comment..]
+            .iter()
+            .take_while(|comment| comment.before_token <= self.next_token)
+            .any(|comment| comment.before_token == self.next_token)
+    }
+
+    fn blank_line_before(&mut self, line_number: i32) {
+        let previous_line_number = self.tokens[..self.next_token]
+            .last()
+            .map(|token| token.line_number)
+            .max(
+                self.next_comment
+                    .checked_sub(1)
+                    .map(|index| self.comments[index].line_number),
+            );
+        if let Some(previous_line_number) = previous_line_number {
+            if line_number > previous_line_number + 1 && !self.at_block_start {
+                self.output.push('\n');
+            }
+        }
+    }
+
+    // Writes the current line, followed by a comment from the end of its last source line.
+    // Comments from within a statement that now fits on one line are moved above it.
+    fn end_line(&mut self) {
+        let last_line_number = self.tokens[self.next_token - 1].line_number;
+        let mut trailing_comment = None;
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.before_token < self.next_token {
+                let indentation = INDENT.repeat(self.indent);
+                self.output.push_str(&indentation);
+                self.output.push_str(comment.text);
+                self.output.push('\n');
+            } else if comment.before_token == self.next_token
+                && comment.line_number == last_line_number
+            {
+                trailing_comment = Some(comment.text);
+            } else {
+                break;
+            }
+            self.next_comment += 1;
+        }
+
+        let mut line = std::mem::take(&mut self.line);
+        if let Some(comment) = trailing_comment {
+            line.push(' ');
+            line.push_str(comment);
+        }
+        self.push_line(&line);
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.output.push_str(&INDENT.repeat(self.indent));
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+}
+
+// Whether an expression has calls with arguments, lists or maps with elements, or operator chains
+// that it could be broken up at.
+fn can_break(node: &Node) -> bool {
+    match node {
+        Node::Call(_, arguments, _) if !arguments.is_empty() => true,
+        Node::Call(callee, _, _) => can_break(callee),
+        Node::List(elements) => !elements.is_empty(),
+        Node::Map(entries, _) => !entries.is_empty(),
+        Node::Expression(node)
+        | Node::Grouping(node)
+        | Node::Assign(_, node, ..)
+        | Node::Unary(_, node)
+        | Node::Increment(_, _, node, _)
+        | Node::Get(node, _, _) => can_break(node),
+        Node::Series(left, right)
+        | Node::Set(left, _, right, _)
+        | Node::CompoundAssign(_, left, right, _)
+        | Node::Index(left, right, _) => can_break(left) || can_break(right),
+        Node::Conditional(condition, then_value, else_value) => {
+            can_break(condition) || can_break(then_value) || can_break(else_value)
+        }
+        Node::SetIndex(object, index, value, _) => {
+            can_break(object) || can_break(index) || can_break(value)
+        }
+        Node::Slice(object, start, end, _) => {
+            can_break(object) || start.iter().chain(end).any(|bound| can_break(bound))
+        }
+        Node::This(..) | Node::Super(..) | Node::Variable(..) | Node::Primary(_) => false,
+        Node::Logical(..)
+        | Node::Bitwise(..)
+        | Node::Equality(..)
+        | Node::Comparison(..)
+        | Node::Shift(..)
+        | Node::Sum(..)
+        | Node::Product(..)
+        | Node::Power(..) => true,
+    }
+}
+
+fn binary_operands(node: &Node) -> Option<(&Node, &'static str, &Node)> {
+    match node {
+        Node::Logical(operator, left, right) => Some((left, operator.as_str(), right)),
+        Node::Bitwise(operator, left, right) => Some((left, operator.as_str(), right)),
+        Node::Equality(operator, left, right) => Some((left, operator.as_str(), right)),
+        Node::Comparison(operator, left, right) => Some((left, operator.as_str(), right)),
+        Node::Shift(operator, left, right) => Some((left, operator.as_str(), right)),
+        Node::Sum(operator, left, right) => Some((left, operator.as_str(), right)),
+        Node::Product(operator, left, right) => Some((left, operator.as_str(), right)),
+        Node::Power(base, exponent) => Some((base, "**", exponent)),
+        _ => None,
+    }
+}
+
+// Whether two binary operators bind equally tightly. `and` binds tighter than `or`, and `&`, `^`
+// and `|` each have their own precedence, as in C.
+fn same_precedence(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        (Node::Logical(a, ..), Node::Logical(b, ..)) => a == b,
+        (Node::Bitwise(a, ..), Node::Bitwise(b, ..)) => a == b,
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formatter::format;
+
+    struct TestCase {
+        input: &'static str,
+        expected_output: &'static str,
+    }
+
+    #[test]
+    fn programs_are_formatted_canonically() {
+        let test_cases = vec![
+            TestCase {
+                input: "var a=1;print a+2*(3-a);",
+                expected_output: "var a = 1;\nprint a + 2 * (3 - a);\n",
+            },
+            TestCase {
+                input: "fun add(a,b){return a+b;}\n\n\n\nprint add(1,-  -2.50);",
+                expected_output: "fun add(a, b) {\n    return a + b;\n}\n\nprint add(1, - -2.50);\n",
+            },
+            TestCase {
+                input: "if(a)print 1;else if(b){print 2;}else{}",
+                expected_output: "if (a) print 1;\nelse if (b) {\n    print 2;\n} else {}\n",
+            },
+            TestCase {
+                input: "for(var i=0;i<3;i=i+1)\nprint i;\nfor(;;){}\nwhile(!done){\n\nstep();\n\n}",
+                expected_output: "for (var i = 0; i < 3; i = i + 1) print i;\nfor (;;) {}\nwhile (!done) {\n    step();\n}\n",
+            },
+            TestCase {
+                input: "// Header.\n\nvar a = 1; // One.\n{\n  // Inside.\n  print a;\n  // Last.\n}\nprint a +\n  // Between.\n  1;\n// End.\n",
+                expected_output: "// Header.\n\nvar a = 1; // One.\n{\n    // Inside.\n    print a;\n    // Last.\n}\n// Between.\nprint a + 1;\n// End.\n",
+            },
+            TestCase {
+                input: "fun f(a, // A.\n  b) {}\nif (a) { print 1; } // After if.\n// Before else.\nelse { print 2; }",
+                expected_output: "// A.\nfun f(a, b) {}\nif (a) {\n    print 1;\n} // After if.\n// Before else.\nelse {\n    print 2;\n}\n",
+            },
+            TestCase {
+                input: "print some_function(\"a long argument\", another_function(\"another long argument\", 123));",
+                expected_output: "print some_function(\n    \"a long argument\",\n    another_function(\"another long argument\", 123)\n);\n",
+            },
+            TestCase {
+                input: "var numbers = [1000000, 2000000, 3000000, 4000000, 5000000, 6000000, 7000000, [8000000]];",
+                expected_output: "var numbers = [\n    1000000,\n    2000000,\n    3000000,\n    4000000,\n    5000000,\n    6000000,\n    7000000,\n    [8000000]\n];\n",
+            },
+            TestCase {
+                input: "var settings = {\"width\": 80, \"indent\": 4, \"style\": \"canonical\", \"comments\": \"kept\"};",
+                expected_output: "var settings = {\n    \"width\": 80,\n    \"indent\": 4,\n    \"style\": \"canonical\",\n    \"comments\": \"kept\"\n};\n",
+            },
+            TestCase {
+                input: "if (first_condition_is_true and second_condition_is_true or third_condition_is_true) {}",
+                expected_output: "if (first_condition_is_true and second_condition_is_true or\n    third_condition_is_true) {}\n",
+            },
+            TestCase {
+                input: "var total = first_long_operand_name + second_long_operand_name - third_long_operand * 2;",
+                expected_output: "var total = first_long_operand_name +\n    second_long_operand_name -\n    third_long_operand * 2;\n",
+            },
+            TestCase {
+                input: "class A<B{init(x){this.x=x;}\n\n\nget(){return super.get(this.x).y;}}class C{}",
+                expected_output: "class A < B {\n    init(x) {\n        this.x = x;\n    }\n\n    get() {\n        return super.get(this.x).y;\n    }\n}\nclass C {}\n",
+            },
+            TestCase {
+                input: "fun a_function_with_a_long_name(first_parameter, second_parameter, third_parameter) {}",
+                expected_output: "fun a_function_with_a_long_name(\n    first_parameter,\n    second_parameter,\n    third_parameter\n) {}\n",
+            },
+            TestCase {
+                input: "var a:Number=1;fun f(b :String,c):Bool{}",
+                expected_output: "var a: Number = 1;\nfun f(b: String, c): Bool {}\n",
+            },
+            TestCase {
+                input: "var xs=[1,[2 ,3],[]];xs[0]=xs[ -1 ];print xs[1:],xs[:2],xs[ : ];",
+                expected_output: "var xs = [1, [2, 3], []];\nxs[0] = xs[-1];\nprint xs[1:], xs[:2], xs[:];\n",
+            },
+            TestCase {
+                input: "var m={\"a\" :1,2:{ }};{m[\"a\"]={nil:[true]};}({}).has(1);",
+                expected_output: "var m = {\"a\": 1, 2: {}};\n{\n    m[\"a\"] = {nil: [true]};\n}\n({}).has(1);\n",
+            },
+            TestCase {
+                input: "import\"a.lox\"as a;import{b ,c}from \"c.lox\";\nexport   fun d(){}export var e=1;",
+                expected_output: "import \"a.lox\" as a;\nimport {b, c} from \"c.lox\";\nexport fun d() {}\nexport var e = 1;\n",
+            },
+            TestCase {
+                input: "try{throw  \"a\";}catch(e){print e;}\ntry {} finally {}",
+                expected_output: "try {\n    throw \"a\";\n} catch (e) {\n    print e;\n}\ntry {} finally {}\n",
+            },
+            TestCase {
+                input: "outer :for(;;)while(true){break  outer;continue;}",
+                expected_output: "outer: for (;;) while (true) {\n    break outer;\n    continue;\n}\n",
+            },
+            TestCase {
+                input: "a.b%=c?d:e?f:g;print - --a,x**-2~/y--;x**=2;x~/=2;",
+                expected_output: "a.b %= c ? d : e ? f : g;\nprint - --a, x ** -2 ~/ y--;\nx **= 2;\nx ~/= 2;\n",
+            },
+            TestCase {
+                input: "print~a&b|c^d<<1>>~~e;a<<=b&=1;",
+                expected_output: "print ~a & b | c ^ d << 1 >> ~~e;\na <<= b &= 1;\n",
+            },
+        ];
+
+        for test_case in test_cases {
+            let output = format(test_case.input).unwrap();
+            assert_eq!(output, test_case.expected_output, "{}", test_case.input);
+            assert_eq!(format(&output).unwrap(), output, "{}", test_case.input);
+        }
+    }
+
+    #[test]
+    fn commented_programs_are_formatted_the_same_way_twice() {
+        let inputs = [
+            "fun f(a, // A.\n  b) {}",
+            "if (a) { print 1; } // After if.\n// Before else.\nelse { print 2; }",
+            "if (a) {} else // Else.\n{ print 2; }",
+            "while (x) // While.\n{}",
+            "try { f(); } // Try.\ncatch (e) {}\n\n// Finally.\nfinally { // Empty.\n}",
+            "{ // Empty.\n}\nclass A // Class.\n{ m( // Method.\n) {} }",
+            "print [1, // One.\n2] + {\"a\": // A.\n1};",
+        ];
+
+        for input in inputs {
+            let output = format(input).unwrap();
+            assert_eq!(format(&output).unwrap(), output, "{}", input);
+        }
+    }
+
+    #[test]
+    fn programs_with_errors_are_not_formatted() {
+        assert_eq!(
+            format("print (1;").unwrap_err().to_string(),
+            "[line 1] Error: Unexpected token: 1"
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use std::ops::Range;
+
+use crate::interpreter::Interpreter;
+use crate::parser::recursive_descent::RecursiveDescentParser;
+use crate::parser::Parse;
+use crate::resolver::{DeclarationKind, Occurrence, Resolver};
+use crate::scanner::Scanner;
+use crate::token::TokenType;
+
+/// What a token is, for colouring it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Highlight {
+    Keyword,
+    /// `nil`, `true` and `false`.
+    Literal,
+    String,
+    Number,
+    Operator,
+    /// Brackets, braces and the other tokens that only separate things.
+    Punctuation,
+    Comment,
+    Function,
+    Class,
+    /// A built-in type named in an annotation, like `Number`.
+    Type,
+    Method,
+    Parameter,
+    Variable,
+    Property,
+    /// The label of a loop, where it is declared and where `break` or `continue` names it.
+    Label,
+}
+
+impl Highlight {
+    /// The name of the highlight, which is also its CSS class.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Highlight::Keyword => "keyword",
+            Highlight::Literal => "literal",
+            Highlight::String => "string",
+            Highlight::Number => "number",
+            Highlight::Operator => "operator",
+            Highlight::Punctuation => "punctuation",
+            Highlight::Comment => "comment",
+            Highlight::Function => "function",
+            Highlight::Class => "class",
+            Highlight::Type => "type",
+            Highlight::Method => "method",
+            Highlight::Parameter => "parameter",
+            Highlight::Variable => "variable",
+            Highlight::Property => "property",
+            Highlight::Label => "label",
+        }
+    }
+
+    // The SGR parameters of the terminal colour, if the highlight has one.
+    fn ansi_code(&self) -> Option<&'static str> {
+        match self {
+            Highlight::Keyword => Some("35"),
+            Highlight::Literal | Highlight::Number => Some("33"),
+            Highlight::String => Some("32"),
+            Highlight::Comment => Some("90"),
+            Highlight::Function | Highlight::Method => Some("34"),
+            Highlight::Class | Highlight::Type => Some("1;33"),
+            Highlight::Parameter => Some("36"),
+            Highlight::Label => Some("3"),
+            Highlight::Operator
+            | Highlight::Punctuation
+            | Highlight::Variable
+            | Highlight::Property => None,
+        }
+    }
+}
+
+/// A highlighted token, by its byte range in the source.
+#[derive(Debug, PartialEq)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub highlight: Highlight,
+}
+
+/// Classifies every token of a program except whitespace, in source order. Identifiers are
+/// classified by what they name, using the resolver, so a parameter is highlighted as one
+/// wherever it is used. Identifiers of a program that doesn't parse are all variables.
+pub fn highlight(source_code: &str) -> Result<Vec<Span>, crate::Error> {
+    let tokens = Scanner::with_trivia()
+        .scan(source_code)
+        .map_err(crate::Error::Scan)?;
+
+    let meaningful_tokens: Vec<_> = tokens
+        .iter()
+        .filter(|token| !token.token_type.is_trivia())
+        .cloned()
+        .collect();
+    let mut resolver = Resolver::new();
+    if let Ok(statements) = RecursiveDescentParser::new().parse_program(&meaningful_tokens) {
+        // Only what the identifiers name matters here, not whether the program is correct.
+        let _ = resolver.resolve(&statements);
+    }
+    let occurrences = resolver.occurrences();
+    let natives = Interpreter::new().global_names();
+
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    let mut identifiers = 0;
+    for token in &tokens {
+        // The lexeme of a string leaves out its quotes.
+        let length = match token.token_type {
+            TokenType::String => token.lexeme.len() + 2,
+            _ => token.lexeme.len(),
+        };
+        let highlight = match token.token_type {
+            TokenType::Whitespace => None,
+            TokenType::Identifier => {
+                let occurrence = occurrences.get(identifiers).copied();
+                identifiers += 1;
+                Some(match occurrence {
+                    Some(Occurrence::Declaration(kind)) => declaration(kind),
+                    Some(Occurrence::Reference(Some(index)) | Occurrence::Type(Some(index))) => {
+                        match occurrences[index] {
+                            Occurrence::Declaration(kind) => declaration(kind),
+                            _ => unreachable!("references are to declarations"),
+                        }
+                    }
+                    Some(Occurrence::Reference(None))
+                        if natives.iter().any(|native| native == token.lexeme) =>
+                    {
+                        Highlight::Function
+                    }
+                    Some(Occurrence::Property) => Highlight::Property,
+                    Some(Occurrence::Type(None)) => Highlight::Type,
+                    Some(Occurrence::Reference(None)) | None => Highlight::Variable,
+                })
+            }
+            token_type => Some(token_highlight(token_type)),
+        };
+        if let Some(highlight) = highlight {
+            spans.push(Span {
+                range: offset..offset + length,
+                highlight,
+            });
+        }
+        offset += length;
+    }
+    Ok(spans)
+}
+
+/// The program with each highlighted token coloured with ANSI escape codes, for a terminal.
+pub fn to_ansi(source_code: &str) -> Result<String, crate::Error> {
+    let mut output = String::new();
+    for (text, highlight) in pieces(source_code)? {
+        match highlight.and_then(|highlight| highlight.ansi_code()) {
+            Some(code) => output.push_str(&format!("\x1b[{}m{}\x1b[0m", code, text)),
+            None => output.push_str(text),
+        }
+    }
+    Ok(output)
+}
+
+/// The program as a `<pre class="lox">` element, with each highlighted token in a `<span>`
+/// whose class is the name of its highlight, for styling with CSS.
+pub fn to_html(source_code: &str) -> Result<String, crate::Error> {
+    let mut output = String::from("<pre class=\"lox\"><code>");
+    for (text, highlight) in pieces(source_code)? {
+        match highlight {
+            Some(highlight) => output.push_str(&format!(
+                "<span class=\"{}\">{}</span>",
+                highlight.as_str(),
+                escape_html(text)
+            )),
+            None => output.push_str(&escape_html(text)),
+        }
+    }
+    output.push_str("</code></pre>\n");
+    Ok(output)
+}
+
+// The whole source split into the highlighted tokens and the whitespace between them.
+fn pieces(source_code: &str) -> Result<Vec<(&str, Option<Highlight>)>, crate::Error> {
+    let mut pieces = Vec::new();
+    let mut offset = 0;
+    for span in highlight(source_code)? {
+        if offset < span.range.start {
+            pieces.push((&source_code[offset..span.range.start], None));
+        }
+        offset = span.range.end;
+        pieces.push((&source_code[span.range], Some(span.highlight)));
+    }
+    pieces.push((&source_code[offset..], None));
+    Ok(pieces)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn declaration(kind: DeclarationKind) -> Highlight {
+    match kind {
+        DeclarationKind::Variable => Highlight::Variable,
+        DeclarationKind::Function => Highlight::Function,
+        DeclarationKind::Parameter => Highlight::Parameter,
+        DeclarationKind::Class => Highlight::Class,
+        DeclarationKind::Method => Highlight::Method,
+        DeclarationKind::Label => Highlight::Label,
+    }
+}
+
+fn token_highlight(token_type: TokenType) -> Highlight {
+    match token_type {
+        TokenType::LeftParen
+        | TokenType::RightParen
+        | TokenType::LeftBrace
+        | TokenType::RightBrace
+        | TokenType::LeftBracket
+        | TokenType::RightBracket
+        | TokenType::Semicolon
+        | TokenType::Comma
+        | TokenType::Colon
+        | TokenType::Dot => Highlight::Punctuation,
+        TokenType::Plus
+        | TokenType::Minus
+        | TokenType::Star
+        | TokenType::Slash
+        | TokenType::Percent
+        | TokenType::StarStar
+        | TokenType::TildeSlash
+        | TokenType::PlusPlus
+        | TokenType::MinusMinus
+        | TokenType::PlusEqual
+        | TokenType::MinusEqual
+        | TokenType::StarEqual
+        | TokenType::SlashEqual
+        | TokenType::PercentEqual
+        | TokenType::StarStarEqual
+        | TokenType::TildeSlashEqual
+        | TokenType::AmpersandEqual
+        | TokenType::PipeEqual
+        | TokenType::CaretEqual
+        | TokenType::LessLessEqual
+        | TokenType::GreaterGreaterEqual
+        | TokenType::Question
+        | TokenType::Ampersand
+        | TokenType::Pipe
+        | TokenType::Caret
+        | TokenType::Tilde
+        | TokenType::Bang
+        | TokenType::BangEqual
+        | TokenType::Equal
+        | TokenType::EqualEqual
+        | TokenType::Greater
+        | TokenType::GreaterEqual
+        | TokenType::Less
+        | TokenType::LessEqual
+        | TokenType::LessLess
+        | TokenType::GreaterGreater => Highlight::Operator,
+        TokenType::Nil | TokenType::True | TokenType::False => Highlight::Literal,
+        TokenType::String => Highlight::String,
+        TokenType::Number => Highlight::Number,
+        TokenType::Comment => Highlight::Comment,
+        TokenType::Identifier => Highlight::Variable,
+        _ => Highlight::Keyword,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{highlight, to_ansi, to_html, Highlight};
+
+    #[test]
+    fn identifiers_are_highlighted_by_what_they_name() {
+        let source_code = "class A < B { m(p) { var v = p; return this.f(v, nil, \"s\") + clock(); } } // c\nvar B; fun f() { undefined; }";
+        let highlights: Vec<(&str, Highlight)> = highlight(source_code)
+            .unwrap()
+            .into_iter()
+            .map(|span| (&source_code[span.range], span.highlight))
+            .filter(|(_, highlight)| *highlight != Highlight::Punctuation)
+            .collect();
+
+        assert_eq!(
+            highlights,
+            [
+                ("class", Highlight::Keyword),
+                ("A", Highlight::Class),
+                ("<", Highlight::Operator),
+                ("B", Highlight::Variable),
+                ("m", Highlight::Method),
+                ("p", Highlight::Parameter),
+                ("var", Highlight::Keyword),
+                ("v", Highlight::Variable),
+                ("=", Highlight::Operator),
+                ("p", Highlight::Parameter),
+                ("return", Highlight::Keyword),
+                ("this", Highlight::Keyword),
+                ("f", Highlight::Property),
+                ("v", Highlight::Variable),
+                ("nil", Highlight::Literal),
+                ("\"s\"", Highlight::String),
+                ("+", Highlight::Operator),
+                ("clock", Highlight::Function),
+                ("// c", Highlight::Comment),
+                ("var", Highlight::Keyword),
+                ("B", Highlight::Variable),
+                ("fun", Highlight::Keyword),
+                ("f", Highlight::Function),
+                ("undefined", Highlight::Variable),
+            ]
+        );
+    }
+
+    #[test]
+    fn programs_that_dont_parse_are_still_highlighted() {
+        let spans = highlight("fun (x").unwrap();
+        let highlights: Vec<Highlight> = spans.into_iter().map(|span| span.highlight).collect();
+
+        assert_eq!(
+            highlights,
+            [
+                Highlight::Keyword,
+                Highlight::Punctuation,
+                Highlight::Variable
+            ]
+        );
+        assert!(highlight("\"unterminated").is_err());
+    }
+
+    #[test]
+    fn highlights_are_written_as_ansi_and_html() {
+        let source_code = "print a < \"<b>\";\n";
+
+        assert_eq!(
+            to_ansi(source_code).unwrap(),
+            "\x1b[35mprint\x1b[0m a < \x1b[32m\"<b>\"\x1b[0m;\n"
+        );
+        assert_eq!(
+            to_html(source_code).unwrap(),
+            "<pre class=\"lox\"><code><span class=\"keyword\">print</span> \
+             <span class=\"variable\">a</span> <span class=\"operator\">&lt;</span> \
+             <span class=\"string\">&quot;&lt;b&gt;&quot;</span><span class=\"punctuation\">;</span>\n\
+             </code></pre>\n"
+        );
+    }
+}
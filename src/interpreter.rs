@@ -1,59 +1,2634 @@
 use crate::parser::Parse;
-use std::io::Write;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
 
+use crate::arithmetic;
+use crate::ast::{
+    self, AssignOperator, BitwiseOperator, ComparisonOperator, EqualityOperator, Fixity, Function,
+    Imports, IncrementOperator, Literal, LogicalOperator, Node, ProductOperator, ShiftOperator,
+    Statement, SumOperator, UnaryOperator,
+};
+use crate::bitwise;
+use crate::environment::Environment;
+use crate::fold::Folder;
+use crate::list;
+use crate::map;
+use crate::module::Modules;
+use crate::optimizer::Optimizer;
 use crate::parser::recursive_descent::RecursiveDescentParser;
+use crate::printer::AstPrinter;
+use crate::resolver::Resolver;
 use crate::scanner::Scanner;
+use crate::stdlib;
+use crate::symbol::Symbol;
+use crate::types::TypeChecker;
+use crate::value::{Arity, Class, Closure, HostFunction, Instance, Module, NativeFunction, Value};
+
+const HELP: &str = "\
+:help          Show this list of commands.
+:quit          Leave the prompt.
+:load <file>   Run a script, keeping the globals it defines.
+:reset         Forget everything defined since the prompt started.
+:env           List the globals defined since the prompt started.
+:tokens <code> Show the tokens scanned from the code.
+:ast <code>    Show the syntax tree parsed from the code.
+:time <code>   Run the code and show how long it took.
+:type <expr>   Show the type of the value of an expression.
+";
+
+// Each Lox call recurses through several Rust frames, whose size depends heavily on how the
+// interpreter was compiled, so deep Lox recursion is limited by how much native stack it has used
+// rather than by a number of calls. A call takes around 4 KiB unoptimized and 2 KiB optimized, and
+// this leaves room for the host in the 8 MiB main thread of Linux and macOS.
+const DEFAULT_STACK_SIZE: usize = 6 * 1024 * 1024;
+
+// Runtime errors are caught as instances of this class, which scripts can also throw and extend.
+const ERROR_CLASS: &str = "class Error { init(message) { this.message = message; } }";
 
 pub struct Interpreter {
     had_error: bool,
+    had_runtime_error: bool,
+    // The standard library and the functions the host defines, which every module can see.
+    builtins: Rc<RefCell<Environment>>,
+    error_class: Rc<Class>,
+    // The globals of the program being run, which its modules can't see.
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    call_depth: usize,
+    stack_size: usize,
+    // Where the native stack was when the outermost Lox call started.
+    stack_base: usize,
+    modules: Modules,
+    // The line of the most recently evaluated node that knows its position, used to locate errors
+    // raised by nodes that don't.
+    line_number: i32,
+    output: Box<dyn Write>,
+    diagnostics: Box<dyn Write>,
+    input: Box<dyn BufRead>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
-        Interpreter { had_error: false }
+        let builtins = Rc::new(RefCell::new(Environment::new(None)));
+        let error_class = error_class(&builtins);
+        builtins.borrow_mut().define(
+            error_class.name.clone(),
+            Value::Class(Rc::clone(&error_class)),
+        );
+        let globals = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(&builtins)))));
+        let mut interpreter = Interpreter {
+            had_error: false,
+            had_runtime_error: false,
+            builtins,
+            error_class,
+            globals: Rc::clone(&globals),
+            environment: globals,
+            call_depth: 0,
+            stack_size: DEFAULT_STACK_SIZE,
+            stack_base: 0,
+            modules: Modules::default(),
+            line_number: 1,
+            output: Box::new(std::io::stdout()),
+            diagnostics: Box::new(std::io::stderr()),
+            input: Box::new(BufReader::new(std::io::stdin())),
+        };
+        stdlib::define_globals(&mut interpreter);
+        interpreter
+    }
+
+    /// Limits how much native stack Lox calls can use before they fail with a "Stack overflow."
+    /// runtime error, which is 6 MiB by default. The thread running the interpreter needs this much
+    /// stack free on top of what the host itself uses, or deep recursion aborts the process, so
+    /// hosts running scripts on threads with smaller stacks should lower it.
+    pub fn set_stack_size(&mut self, stack_size: usize) {
+        self.stack_size = stack_size;
+    }
+
+    /// Redirects everything the interpreter prints, which is standard output by default.
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.output = Box::new(output);
+    }
+
+    /// Redirects error reports, which go to standard error by default.
+    pub fn set_diagnostics(&mut self, diagnostics: impl Write + 'static) {
+        self.diagnostics = Box::new(diagnostics);
+    }
+
+    /// Replaces the source of prompt lines and `input()`, which is standard input by default.
+    pub fn set_input(&mut self, input: impl Read + 'static) {
+        self.input = Box::new(BufReader::new(input));
+    }
+
+    /// The sink native functions should print to.
+    pub fn output(&mut self) -> &mut dyn Write {
+        &mut self.output
+    }
+
+    /// The source native functions should read from.
+    pub fn input(&mut self) -> &mut dyn BufRead {
+        &mut self.input
+    }
+
+    /// Registers a function implemented in Rust as a global of every module, replacing any existing
+    /// function with the same name.
+    pub fn define_native<F>(&mut self, name: &str, arity: Arity, function: F)
+    where
+        F: Fn(&mut Interpreter, &[Value]) -> Result<Value, String> + 'static,
+    {
+        let name = Symbol::intern(name);
+        let native_function = NativeFunction {
+            name: name.clone(),
+            arity,
+            function: Box::new(function),
+        };
+        self.builtins
+            .borrow_mut()
+            .define(name, Value::NativeFunction(Rc::new(native_function)));
+    }
+
+    /// Registers a plain Rust function as a global, converting its arguments from and its result
+    /// into Lox values.
+    pub fn define_function<Args, F>(&mut self, name: &str, function: F)
+    where
+        F: HostFunction<Args> + 'static,
+    {
+        let arity = Arity::Exact(function.arity());
+        let function_name = name.to_string();
+        self.define_native(name, arity, move |_, arguments| {
+            function.call(&function_name, arguments)
+        });
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals.borrow().get(&Symbol::intern(name))
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().define(Symbol::intern(name), value);
+    }
+
+    /// The names of all globals, including the standard library, in no particular order.
+    pub fn global_names(&self) -> Vec<String> {
+        let builtins = self.builtins.borrow();
+        let globals = self.globals.borrow();
+        builtins
+            .names()
+            .chain(globals.names())
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Sets the directories searched in order for modules that aren't next to the file importing
+    /// them.
+    pub fn set_search_path(&mut self, directories: Vec<PathBuf>) {
+        self.modules.set_search_path(directories);
+    }
+
+    /// Runs a program against this interpreter's globals. Source code consisting of a single
+    /// expression without a trailing semicolon is evaluated instead, and its value returned.
+    pub fn eval_str(&mut self, source_code: &str) -> Result<Value, crate::Error> {
+        let tokens = Scanner::new()
+            .scan(source_code)
+            .map_err(crate::Error::Scan)?;
+        match RecursiveDescentParser::new().parse_program(&tokens) {
+            Ok(statements) => {
+                Resolver::new().resolve(&statements).map_err(|mut resolve_errors| {
+                    crate::Error::Resolve(resolve_errors.remove(0))
+                })?;
+                for statement in &Optimizer::optimize(statements) {
+                    match self.execute(statement) {
+                        Ok(()) => {}
+                        Err(Unwind::Error(error)) => return Err(crate::Error::Runtime(error)),
+                        Err(Unwind::Return(_)) => break,
+                        Err(Unwind::Break(_) | Unwind::Continue(_)) => unreachable!(),
+                    }
+                }
+                Ok(Value::Nil)
+            }
+            Err(mut parse_errors) => match RecursiveDescentParser::new().parse(&tokens) {
+                Ok(node) => self
+                    .evaluate(&Optimizer.fold_node(node))
+                    .map_err(crate::Error::Runtime),
+                Err(_) => Err(crate::Error::Parse(parse_errors.remove(0))),
+            },
+        }
     }
 
+    /// Runs a script like `run`, with the modules it imports found relative to it.
     pub fn run_file(&mut self, filename: &str) -> Result<(), std::io::Error> {
-        let mut source_code = std::fs::read_to_string(filename)?;
-        self.run(&mut source_code);
-        Ok(())
+        let source_code = std::fs::read_to_string(filename)?;
+        self.modules
+            .enter(Path::new(filename))
+            .map_err(std::io::Error::other)?;
+        let result = self.run(&source_code);
+        self.modules.leave(None);
+        result
     }
 
+    /// Reads and runs entries from the input source until it ends. Globals persist between
+    /// entries, input that could still become valid is continued on the next line, and the value
+    /// of a bare expression is printed.
     pub fn run_prompt(&mut self) -> Result<(), std::io::Error> {
+        self.run_prompt_with(|interpreter, prompt| {
+            write!(interpreter.output, "{}", prompt)?;
+            interpreter.output.flush()?;
+            let mut line = String::new();
+            match interpreter.input.read_line(&mut line)? {
+                0 => Ok(None),
+                _ => Ok(Some(line)),
+            }
+        })
+    }
+
+    /// Runs the REPL loop of `run_prompt`, getting each line from `read_line` given the prompt to
+    /// show. `read_line` returns `None` at the end of the input, and an `Interrupted` error to
+    /// discard the entry being typed.
+    pub fn run_prompt_with<F>(&mut self, mut read_line: F) -> Result<(), std::io::Error>
+    where
+        F: FnMut(&mut Interpreter, &str) -> Result<Option<String>, std::io::Error>,
+    {
+        // Globals defined before the session started, such as the standard library and host
+        // functions, survive `:reset`.
+        let initial_globals = self.globals.borrow().bindings();
+        let mut source_code = String::new();
         loop {
-            print!(">> ");
-            std::io::stdout().flush()?;
-            let mut source_code = String::new();
-            match std::io::stdin().read_line(&mut source_code) {
+            let prompt = if source_code.is_empty() { ">> " } else { ".. " };
+            let line = match read_line(self, prompt) {
+                Ok(Some(line)) => line,
+                Ok(None) => return Ok(()),
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {
+                    source_code.clear();
+                    continue;
+                }
                 Err(error) => return Err(error),
-                Ok(0) => return Ok(()),
-                Ok(_) => {
-                    self.run(&mut source_code);
-                    if self.had_error {
-                        return Ok(());
+            };
+            if source_code.is_empty() {
+                if let Some(command) = line.trim().strip_prefix(':') {
+                    self.had_error = false;
+                    self.had_runtime_error = false;
+                    match self.run_command(command, &initial_globals)? {
+                        Command::Continue => continue,
+                        Command::Quit => return Ok(()),
+                    }
+                }
+            }
+
+            // An empty continuation line submits the entry as it is, so that a mistake can't
+            // leave the prompt waiting for input forever.
+            let submit = !source_code.is_empty() && line.trim().is_empty();
+            source_code.push_str(&line);
+            if !source_code.ends_with('\n') {
+                source_code.push('\n');
+            }
+
+            self.had_error = false;
+            self.had_runtime_error = false;
+            if self.run_entry(&source_code, submit)? {
+                source_code.clear();
+            }
+        }
+    }
+
+    fn run_command(
+        &mut self,
+        command: &str,
+        initial_globals: &[(Symbol, Value)],
+    ) -> Result<Command, std::io::Error> {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "help" | "h" => write!(self.output, "{}", HELP)?,
+            "quit" | "q" => return Ok(Command::Quit),
+            "load" => {
+                if let Err(error) = self.run_file(argument) {
+                    writeln!(self.diagnostics, "Could not load '{}': {}", argument, error)?;
+                }
+            }
+            "reset" => {
+                let mut globals = Environment::new(Some(Rc::clone(&self.builtins)));
+                for (name, value) in initial_globals {
+                    globals.define(name.clone(), value.clone());
+                }
+                self.globals = Rc::new(RefCell::new(globals));
+                self.environment = Rc::clone(&self.globals);
+            }
+            "env" => {
+                let mut bindings = self.globals.borrow().bindings();
+                bindings.retain(|binding| !initial_globals.contains(binding));
+                bindings.sort_by(|(left, _), (right, _)| left.as_str().cmp(right.as_str()));
+                for (name, value) in bindings {
+                    writeln!(self.output, "{} = {}", name, value)?;
+                }
+            }
+            "tokens" => match Scanner::new().scan(argument) {
+                Ok(tokens) => {
+                    for token in tokens {
+                        writeln!(self.output, "{:?}", token)?;
+                    }
+                }
+                Err(scan_error) => self.error(scan_error.line_number, &scan_error.message)?,
+            },
+            "ast" => {
+                let tokens = match Scanner::new().scan(argument) {
+                    Ok(tokens) => tokens,
+                    Err(scan_error) => {
+                        self.error(scan_error.line_number, &scan_error.message)?;
+                        return Ok(Command::Continue);
+                    }
+                };
+                match RecursiveDescentParser::new().parse_program(&tokens) {
+                    Ok(statements) => {
+                        write!(self.output, "{}", AstPrinter::print_program(&statements))?
                     }
+                    Err(parse_errors) => match RecursiveDescentParser::new().parse(&tokens) {
+                        Ok(node) => writeln!(self.output, "{}", AstPrinter::print(&node))?,
+                        Err(_) => {
+                            for parse_error in parse_errors {
+                                self.error(parse_error.line_number, &parse_error.message)?;
+                            }
+                        }
+                    },
                 }
+            }
+            "time" => {
+                let start = Instant::now();
+                self.run_entry(argument, true)?;
+                writeln!(self.output, "Took {:?}.", start.elapsed())?;
+            }
+            "type" => match self.eval_str(argument) {
+                Ok(value) => writeln!(self.output, "{}", value.type_name())?,
+                Err(crate::Error::Runtime(runtime_error)) => self.runtime_error(runtime_error)?,
+                Err(error) => self.error(error.line_number(), error.message())?,
+            },
+            _ => writeln!(
+                self.diagnostics,
+                "Unknown command ':{}'. Type :help for a list of commands.",
+                name
+            )?,
+        }
+
+        Ok(Command::Continue)
+    }
+
+    // Runs one REPL entry and returns whether it was complete. Incomplete entries are left for
+    // the caller to extend unless `submit` is set.
+    fn run_entry(&mut self, source_code: &str, submit: bool) -> Result<bool, std::io::Error> {
+        let tokens = match Scanner::new().scan(source_code) {
+            Ok(tokens) => tokens,
+            Err(scan_error) => {
+                self.error(scan_error.line_number, &scan_error.message)?;
+                return Ok(true);
+            }
+        };
+
+        match RecursiveDescentParser::new().parse_program(&tokens) {
+            Ok(statements) => {
+                if self.resolve(&statements)? {
+                    self.execute_program(&Optimizer::optimize(statements))?;
+                }
+            }
+            Err(parse_errors) => match RecursiveDescentParser::new().parse(&tokens) {
+                Ok(node) => {
+                    match self.evaluate(&Optimizer.fold_node(node)) {
+                        Ok(value) => writeln!(self.output, "{}", value)?,
+                        Err(runtime_error) => self.runtime_error(runtime_error)?,
+                    }
+                }
+                Err(_) if parse_errors[0].at_end_of_input && !submit => return Ok(false),
+                Err(_) => {
+                    for parse_error in parse_errors {
+                        self.error(parse_error.line_number, &parse_error.message)?;
+                    }
+                }
+            },
+        }
+
+        Ok(true)
+    }
+
+    /// Runs source code, printing to the output sink and reporting any errors to the diagnostics
+    /// sink. Only failures to write to those sinks are returned.
+    pub fn run(&mut self, source_code: &str) -> Result<(), std::io::Error> {
+        match self.compile(source_code)? {
+            Some(statements) => self.execute_program(&statements),
+            None => Ok(()),
+        }
+    }
+
+    /// Scans, parses and resolves source code without running it, reporting any errors to the
+    /// diagnostics sink.
+    pub fn check(&mut self, source_code: &str) -> Result<(), std::io::Error> {
+        self.compile(source_code).map(|_| ())
+    }
+
+    /// Checks source code like `check`, and when it has no errors, also checks its types against
+    /// its annotations, reporting type errors to the diagnostics sink the same way.
+    pub fn check_types(&mut self, source_code: &str) -> Result<(), std::io::Error> {
+        let Some(statements) = self.analyze(source_code)? else {
+            return Ok(());
+        };
+        if let Err(type_errors) = TypeChecker::new().check(&statements) {
+            for type_error in type_errors {
+                self.error(type_error.line_number, &type_error.message)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether an error that stopped source code from running has been reported.
+    pub fn had_error(&self) -> bool {
+        self.had_error
+    }
+
+    /// Whether a runtime error has been reported.
+    pub fn had_runtime_error(&self) -> bool {
+        self.had_runtime_error
+    }
+
+    // Returns the optimized statements of a program ready to execute, or `None` once its errors
+    // have been reported.
+    fn compile(&mut self, source_code: &str) -> Result<Option<Vec<Statement>>, std::io::Error> {
+        Ok(self.analyze(source_code)?.map(Optimizer::optimize))
+    }
+
+    // Returns the statements of a program as written once they have been resolved, or `None` once
+    // its errors have been reported.
+    fn analyze(&mut self, source_code: &str) -> Result<Option<Vec<Statement>>, std::io::Error> {
+        let tokens = match Scanner::new().scan(source_code) {
+            Ok(tokens) => tokens,
+            Err(scan_error) => {
+                self.error(scan_error.line_number, &scan_error.message)?;
+                return Ok(None);
+            }
+        };
+
+        match RecursiveDescentParser::new().parse_program(&tokens) {
+            Ok(statements) if self.resolve(&statements)? => Ok(Some(statements)),
+            Ok(_) => Ok(None),
+            Err(parse_errors) => {
+                for parse_error in parse_errors {
+                    self.error(parse_error.line_number, &parse_error.message)?;
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    // Reports any resolution errors and returns whether there were none.
+    fn resolve(&mut self, statements: &[Statement]) -> Result<bool, std::io::Error> {
+        match Resolver::new().resolve(statements) {
+            Ok(()) => Ok(true),
+            Err(resolve_errors) => {
+                for resolve_error in resolve_errors {
+                    self.error(resolve_error.line_number, &resolve_error.message)?;
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    fn execute_program(&mut self, statements: &[Statement]) -> Result<(), std::io::Error> {
+        for statement in statements {
+            match self.execute(statement) {
+                Ok(()) => {}
+                Err(Unwind::Error(runtime_error)) => return self.runtime_error(runtime_error),
+                Err(Unwind::Return(_)) => return Ok(()),
+                Err(Unwind::Break(_) | Unwind::Continue(_)) => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+
+    // Each kind of statement is executed by a function of its own, which takes the statement apart
+    // itself, like each kind of node is evaluated.
+    fn execute(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        match statement {
+            Statement::Expression(..) => self.execute_expression(statement),
+            Statement::Print(..) => self.execute_print(statement),
+            Statement::Var(..) => self.execute_var(statement),
+            Statement::Block(statements) => self.execute_scope(statements),
+            Statement::If(..) => self.execute_if(statement),
+            Statement::While(..) | Statement::For(..) => self.execute_loop(None, statement),
+            Statement::Labelled(label, body) => self.execute_loop(Some(label), body),
+            Statement::Break(label, _) => Err(Unwind::Break(label.clone())),
+            Statement::Continue(label, _) => Err(Unwind::Continue(label.clone())),
+            Statement::Function(function) => self.execute_function(function),
+            Statement::Class(class) => self.execute_class(class),
+            Statement::Return(..) => self.execute_return(statement),
+            Statement::Import(..) => self.execute_import(statement),
+            Statement::Export(declaration) => self.execute(declaration),
+            Statement::Try(..) => self.execute_try(statement),
+            Statement::Throw(..) => self.execute_throw(statement),
+        }
+    }
+
+    fn execute_expression(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        let Statement::Expression(node, line_number) = statement else {
+            unreachable!("only expression statements are executed here");
+        };
+        let line_number = *line_number;
+        self.line_number = line_number;
+        self.evaluate(node)?;
+        Ok(())
+    }
+
+    fn execute_print(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        let Statement::Print(node, line_number) = statement else {
+            unreachable!("only print statements are executed here");
+        };
+        let line_number = *line_number;
+        self.line_number = line_number;
+        let value = self.evaluate(node)?;
+        writeln!(self.output, "{}", value)
+            .map_err(|error| Error::new(line_number, &error.to_string()))?;
+        Ok(())
+    }
+
+    fn execute_var(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        let Statement::Var(name, _, initializer, line_number) = statement else {
+            unreachable!("only variable declarations are executed here");
+        };
+        let line_number = *line_number;
+        self.line_number = line_number;
+        let value = match initializer {
+            Some(initializer) => self.evaluate(initializer)?,
+            None => Value::Nil,
+        };
+        self.environment.borrow_mut().define(name.clone(), value);
+        Ok(())
+    }
+
+    // Runs the statements of a block in a scope of their own.
+    fn execute_scope(&mut self, statements: &[Statement]) -> Result<(), Unwind> {
+        let environment = Environment::new(Some(Rc::clone(&self.environment)));
+        self.execute_block(statements, Rc::new(RefCell::new(environment)))
+    }
+
+    fn execute_if(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        let Statement::If(condition, then_branch, else_branch) = statement else {
+            unreachable!("only if statements are executed here");
+        };
+        if self.evaluate(condition)?.is_truthy() {
+            self.execute(then_branch)
+        } else if let Some(else_branch) = else_branch {
+            self.execute(else_branch)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn execute_function(&mut self, function: &Rc<Function>) -> Result<(), Unwind> {
+        let closure = Closure {
+            function: Rc::clone(function),
+            environment: Rc::clone(&self.environment),
+            is_initializer: false,
+        };
+        self.environment
+            .borrow_mut()
+            .define(function.name.clone(), Value::Closure(Rc::new(closure)));
+        Ok(())
+    }
+
+    fn execute_class(&mut self, class: &ast::Class) -> Result<(), Unwind> {
+        self.line_number = class.line_number;
+        let superclass = match &class.superclass {
+            Some(superclass) => match self.evaluate(superclass)? {
+                Value::Class(superclass) => Some(superclass),
+                _ => {
+                    return Err(Unwind::Error(Error::new(
+                        class.line_number,
+                        "Superclass must be a class.",
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        // The methods of a subclass close over a scope where `super` is the superclass.
+        let environment = match &superclass {
+            Some(superclass) => {
+                let mut environment = Environment::new(Some(Rc::clone(&self.environment)));
+                environment.define(Symbol::intern("super"), Value::Class(Rc::clone(superclass)));
+                Rc::new(RefCell::new(environment))
+            }
+            None => Rc::clone(&self.environment),
+        };
+        let methods: HashMap<Symbol, Rc<Closure>> = class
+            .methods
+            .iter()
+            .map(|method| {
+                let closure = Closure {
+                    function: Rc::clone(method),
+                    environment: Rc::clone(&environment),
+                    is_initializer: method.name.as_str() == "init",
+                };
+                (method.name.clone(), Rc::new(closure))
+            })
+            .collect();
+
+        let class = Class {
+            name: class.name.clone(),
+            superclass,
+            methods,
+        };
+        self.environment
+            .borrow_mut()
+            .define(class.name.clone(), Value::Class(Rc::new(class)));
+        Ok(())
+    }
+
+    fn execute_return(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        let Statement::Return(value, line_number) = statement else {
+            unreachable!("only return statements are executed here");
+        };
+        let line_number = *line_number;
+        self.line_number = line_number;
+        let value = match value {
+            Some(value) => self.evaluate(value)?,
+            None => Value::Nil,
+        };
+        Err(Unwind::Return(value))
+    }
+
+    fn execute_import(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        let Statement::Import(path, imports, line_number) = statement else {
+            unreachable!("only imports are executed here");
+        };
+        let line_number = *line_number;
+        self.line_number = line_number;
+        let module = self.import(path, line_number)?;
+        let names = match imports {
+            Imports::Module(name) => {
+                self.environment
+                    .borrow_mut()
+                    .define(name.clone(), Value::Module(module));
+                return Ok(());
+            }
+            Imports::Names(names) => names,
+        };
+        for name in names {
+            let value = module
+                .get(name)
+                .ok_or_else(|| not_exported(&module, name, line_number))?;
+            self.environment.borrow_mut().define(name.clone(), value);
+        }
+        Ok(())
+    }
+
+    // The finally block runs however the rest ends, and how it ends itself takes over.
+    fn execute_try(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        let Statement::Try(body, catch, finally) = statement else {
+            unreachable!("only try statements are executed here");
+        };
+        let result = match (self.execute(body), catch) {
+            (Err(Unwind::Error(error)), Some(catch)) => {
+                let mut environment = Environment::new(Some(Rc::clone(&self.environment)));
+                environment.define(catch.name.clone(), self.caught(error));
+                self.execute_block(
+                    std::slice::from_ref(catch.body.as_ref()),
+                    Rc::new(RefCell::new(environment)),
+                )
+            }
+            (result, _) => result,
+        };
+        if let Some(finally) = finally {
+            self.execute(finally)?;
+        }
+        result
+    }
+
+    fn execute_throw(&mut self, statement: &Statement) -> Result<(), Unwind> {
+        let Statement::Throw(value, line_number) = statement else {
+            unreachable!("only throw statements are executed here");
+        };
+        let line_number = *line_number;
+        self.line_number = line_number;
+        let value = self.evaluate(value)?;
+        Err(Unwind::Error(self.thrown(value, line_number)))
+    }
+
+    // Loads the module at a path the first time it is imported, running it with globals of its
+    // own. An error in the module is reported at the import, after where it was in the module.
+    fn import(&mut self, path: &Symbol, line_number: i32) -> Result<Rc<Module>, Error> {
+        let Some(file) = self.modules.find(path.as_str()) else {
+            return Err(Error::new(
+                line_number,
+                &format!("Can't find module '{}'.", path),
+            ));
+        };
+        if let Some(module) = self.modules.loaded(&file) {
+            return Ok(module);
+        }
+        self.modules
+            .enter(&file)
+            .map_err(|message| Error::new(line_number, &message))?;
+        let result = self.load_module(&file);
+        self.modules.leave(result.as_ref().ok());
+        result.map_err(|(error_line_number, message)| {
+            let message = format!(
+                "{}\n[line {}] in {}",
+                message,
+                error_line_number,
+                file.display()
+            );
+            Error::new(line_number, &message)
+        })
+    }
+
+    // Returns the module, or the line and message of the first error in it.
+    fn load_module(&mut self, file: &Path) -> Result<Rc<Module>, (i32, String)> {
+        let source_code = std::fs::read_to_string(file)
+            .map_err(|error| (0, format!("Can't read module: {}.", error)))?;
+        let tokens = Scanner::new()
+            .scan(&source_code)
+            .map_err(|scan_error| (scan_error.line_number, scan_error.message))?;
+        let statements = RecursiveDescentParser::new()
+            .parse_program(&tokens)
+            .map_err(|mut parse_errors| {
+                let parse_error = parse_errors.remove(0);
+                (parse_error.line_number, parse_error.message)
+            })?;
+        Resolver::new()
+            .resolve(&statements)
+            .map_err(|mut resolve_errors| {
+                let resolve_error = resolve_errors.remove(0);
+                (resolve_error.line_number, resolve_error.message)
+            })?;
+
+        let exports = statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Export(declaration) => Some(match declaration.as_ref() {
+                    Statement::Var(name, ..) => name.clone(),
+                    Statement::Function(function) => function.name.clone(),
+                    Statement::Class(class) => class.name.clone(),
+                    _ => unreachable!("only declarations are exported"),
+                }),
+                _ => None,
+            })
+            .collect();
+        let globals = Environment::new(Some(Rc::clone(&self.builtins)));
+        let globals = Rc::new(RefCell::new(globals));
+        match self.execute_block(&Optimizer::optimize(statements), Rc::clone(&globals)) {
+            Ok(()) | Err(Unwind::Return(_)) => {}
+            Err(Unwind::Error(error)) => return Err((error.line_number, error.message)),
+            Err(Unwind::Break(_) | Unwind::Continue(_)) => unreachable!(),
+        }
+        Ok(Rc::new(Module {
+            name: file.display().to_string(),
+            globals,
+            exports,
+        }))
+    }
+
+    fn execute_loop(
+        &mut self,
+        label: Option<&Symbol>,
+        statement: &Statement,
+    ) -> Result<(), Unwind> {
+        match statement {
+            Statement::While(condition, body) => {
+                while self.evaluate(condition)?.is_truthy() {
+                    if !self.execute_iteration(label, body)? {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            Statement::For(initializer, condition, increment, body) => {
+                // The loop variable is scoped to the loop, and shared by every iteration.
+                let environment = Environment::new(Some(Rc::clone(&self.environment)));
+                let previous =
+                    std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)));
+                let result =
+                    self.execute_for(label, initializer.as_deref(), condition, increment, body);
+                self.environment = previous;
+                result
+            }
+            _ => unreachable!("only loops are labelled"),
+        }
+    }
+
+    fn execute_for(
+        &mut self,
+        label: Option<&Symbol>,
+        initializer: Option<&Statement>,
+        condition: &Option<Node>,
+        increment: &Option<Node>,
+        body: &Statement,
+    ) -> Result<(), Unwind> {
+        if let Some(initializer) = initializer {
+            self.execute(initializer)?;
+        }
+        loop {
+            if let Some(condition) = condition {
+                if !self.evaluate(condition)?.is_truthy() {
+                    return Ok(());
+                }
+            }
+            if !self.execute_iteration(label, body)? {
+                return Ok(());
+            }
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+    }
+
+    // Runs the body of a loop once, returning whether the loop goes on. A `break` or `continue`
+    // without a label is for the innermost loop, and one with a label for the loop with it.
+    fn execute_iteration(
+        &mut self,
+        label: Option<&Symbol>,
+        body: &Statement,
+    ) -> Result<bool, Unwind> {
+        let is_this_loop = |target: &Option<Symbol>| target.is_none() || target.as_ref() == label;
+        match self.execute(body) {
+            Ok(()) => Ok(true),
+            Err(Unwind::Break(target)) if is_this_loop(&target) => Ok(false),
+            Err(Unwind::Continue(target)) if is_this_loop(&target) => Ok(true),
+            Err(unwind) => Err(unwind),
+        }
+    }
+
+    fn execute_block(
+        &mut self,
+        statements: &[Statement],
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), Unwind> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.execute_statements(statements);
+        self.environment = previous;
+        result
+    }
+
+    fn execute_statements(&mut self, statements: &[Statement]) -> Result<(), Unwind> {
+        for statement in statements {
+            self.execute(statement)?;
+        }
+        Ok(())
+    }
+
+    // Each kind of node is evaluated by a function of its own, so that a Lox call, which recurses
+    // through here, only takes the native stack of the nodes it actually evaluates.
+    // Each kind of node is evaluated by a function of its own, which takes the node apart itself,
+    // so that a Lox call, which recurses through here, only takes the native stack of the nodes
+    // it actually evaluates.
+    fn evaluate(&mut self, node: &Node) -> Result<Value, Error> {
+        match node {
+            Node::Expression(expression) | Node::Grouping(expression) => {
+                self.evaluate(expression)
+            }
+            Node::Series(..) => self.evaluate_series(node),
+            Node::Assign(..) => self.evaluate_assign(node),
+            Node::CompoundAssign(..) => self.evaluate_compound_assign(node),
+            Node::Increment(..) => self.evaluate_increment(node),
+            Node::Conditional(..) => self.evaluate_conditional(node),
+            Node::Logical(..) => self.evaluate_logical(node),
+            Node::Equality(..)
+            | Node::Bitwise(..)
+            | Node::Comparison(..)
+            | Node::Shift(..)
+            | Node::Sum(..)
+            | Node::Product(..)
+            | Node::Power(..) => self.evaluate_binary(node),
+            Node::Unary(..) => self.evaluate_unary(node),
+            Node::Call(..) => self.evaluate_call(node),
+            Node::Get(..) => self.evaluate_get(node),
+            Node::Set(..) => self.evaluate_set(node),
+            Node::List(..) => self.evaluate_list(node),
+            Node::Map(..) => self.evaluate_map(node),
+            Node::Index(..) => self.evaluate_index(node),
+            Node::SetIndex(..) => self.evaluate_set_index(node),
+            Node::Slice(..) => self.evaluate_slice(node),
+            Node::This(..) => self.evaluate_this(node),
+            Node::Super(..) => self.evaluate_super(node),
+            Node::Variable(..) => self.evaluate_variable(node),
+            Node::Primary(literal) => Ok(match literal {
+                Literal::Nil => Value::Nil,
+                Literal::True => Value::Bool(true),
+                Literal::False => Value::Bool(false),
+                Literal::String(symbol) => Value::String(symbol.as_rc()),
+                Literal::Number(number) => Value::Number(*number),
+            }),
+        }
+    }
+
+    fn evaluate_series(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Series(left, right) = node else {
+            unreachable!("only series are evaluated here");
+        };
+        self.evaluate(left)?;
+        self.evaluate(right)
+    }
+
+    fn evaluate_assign(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Assign(name, value, depth, line_number) = node else {
+            unreachable!("only assignments are evaluated here");
+        };
+        let depth = depth.get();
+        let line_number = *line_number;
+        let value = self.evaluate(value)?;
+        self.line_number = line_number;
+        if self.assign(name, depth, value.clone()) {
+            Ok(value)
+        } else {
+            Err(Error::new(
+                line_number,
+                &format!("Undefined variable '{}'.", name),
+            ))
+        }
+    }
+
+    fn evaluate_compound_assign(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::CompoundAssign(operator, target, value, line_number) = node else {
+            unreachable!("only compound assignments are evaluated here");
+        };
+        let line_number = *line_number;
+        let (_, new_value) = self.update(target, |interpreter, old_value| {
+            let value = interpreter.evaluate(value)?;
+            interpreter.line_number = line_number;
+            match operator {
+                AssignOperator::PlusEqual => interpreter.sum(&SumOperator::Plus, old_value, value),
+                AssignOperator::MinusEqual => {
+                    interpreter.sum(&SumOperator::Minus, old_value, value)
+                }
+                AssignOperator::StarEqual => {
+                    interpreter.product(&ProductOperator::Star, old_value, value)
+                }
+                AssignOperator::SlashEqual => {
+                    interpreter.product(&ProductOperator::Slash, old_value, value)
+                }
+                AssignOperator::PercentEqual => {
+                    interpreter.product(&ProductOperator::Percent, old_value, value)
+                }
+                AssignOperator::StarStarEqual => interpreter.power(old_value, value),
+                AssignOperator::TildeSlashEqual => {
+                    interpreter.product(&ProductOperator::TildeSlash, old_value, value)
+                }
+                AssignOperator::AmpersandEqual => {
+                    interpreter.bitwise(&BitwiseOperator::Ampersand, old_value, value)
+                }
+                AssignOperator::PipeEqual => {
+                    interpreter.bitwise(&BitwiseOperator::Pipe, old_value, value)
+                }
+                AssignOperator::CaretEqual => {
+                    interpreter.bitwise(&BitwiseOperator::Caret, old_value, value)
+                }
+                AssignOperator::LessLessEqual => {
+                    interpreter.shift(&ShiftOperator::LessLess, old_value, value)
+                }
+                AssignOperator::GreaterGreaterEqual => {
+                    interpreter.shift(&ShiftOperator::GreaterGreater, old_value, value)
+                }
+            }
+        })?;
+        Ok(new_value)
+    }
+
+    fn evaluate_increment(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Increment(operator, fixity, operand, line_number) = node else {
+            unreachable!("only increments are evaluated here");
+        };
+        let line_number = *line_number;
+        let (old_value, new_value) = self.update(operand, |interpreter, old_value| {
+            interpreter.line_number = line_number;
+            let Value::Number(number) = old_value else {
+                return Err(Error::new(line_number, "Operand must be a number."));
             };
+            Ok(Value::Number(match operator {
+                IncrementOperator::PlusPlus => number + 1.0,
+                IncrementOperator::MinusMinus => number - 1.0,
+            }))
+        })?;
+        match fixity {
+            Fixity::Prefix => Ok(new_value),
+            Fixity::Postfix => Ok(old_value),
         }
     }
 
-    fn run(&mut self, source_code: &mut str) {
-        let mut scanner = Scanner::new();
-        if let Err(scan_error) = scanner.scan(source_code) {
-            self.error(scan_error.line_number, &scan_error.message)
+    fn evaluate_conditional(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Conditional(condition, then_value, else_value) = node else {
+            unreachable!("only conditionals are evaluated here");
+        };
+        if self.evaluate(condition)?.is_truthy() {
+            self.evaluate(then_value)
+        } else {
+            self.evaluate(else_value)
         }
-        let tokens = &scanner.tokens;
-        println!("Token:\n{:#?}", tokens);
-        let node = RecursiveDescentParser::new().parse(tokens);
-        println!("AST:\n{:#?}", node);
     }
 
-    fn error(&mut self, line_number: i32, message: &str) {
-        self.report(line_number, message);
+    fn evaluate_logical(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Logical(operator, left, right) = node else {
+            unreachable!("only logical operations are evaluated here");
+        };
+        let left = self.evaluate(left)?;
+        match operator {
+            LogicalOperator::And if !left.is_truthy() => Ok(left),
+            LogicalOperator::Or if left.is_truthy() => Ok(left),
+            _ => self.evaluate(right),
+        }
     }
 
-    fn report(&mut self, line_number: i32, message: &str) {
-        eprintln!("[line {}] Error: {}", line_number, message);
+    // The operators that evaluate both of their operands, left to right, before they apply.
+    fn evaluate_binary(&mut self, node: &Node) -> Result<Value, Error> {
+        let (Node::Equality(_, left, right)
+        | Node::Bitwise(_, left, right)
+        | Node::Comparison(_, left, right)
+        | Node::Shift(_, left, right)
+        | Node::Sum(_, left, right)
+        | Node::Product(_, left, right)
+        | Node::Power(left, right)) = node
+        else {
+            unreachable!("only binary operations are evaluated here");
+        };
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        self.operate(node, left, right)
+    }
+
+    // Applies the operator of a binary operation to its operands.
+    fn operate(&self, node: &Node, left: Value, right: Value) -> Result<Value, Error> {
+        match node {
+            Node::Equality(EqualityOperator::Equal, ..) => Ok(Value::Bool(left == right)),
+            Node::Equality(EqualityOperator::NotEqual, ..) => Ok(Value::Bool(left != right)),
+            Node::Bitwise(operator, ..) => self.bitwise(operator, left, right),
+            Node::Comparison(operator, ..) => {
+                let (left, right) = self.number_operands(&left, &right)?;
+                Ok(Value::Bool(match operator {
+                    ComparisonOperator::Greater => left > right,
+                    ComparisonOperator::GreaterEqual => left >= right,
+                    ComparisonOperator::Less => left < right,
+                    ComparisonOperator::LessEqual => left <= right,
+                }))
+            }
+            Node::Shift(operator, ..) => self.shift(operator, left, right),
+            Node::Sum(operator, ..) => self.sum(operator, left, right),
+            Node::Product(operator, ..) => self.product(operator, left, right),
+            _ => self.power(left, right),
+        }
+    }
+
+    fn evaluate_unary(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Unary(operator, operand) = node else {
+            unreachable!("only unary operations are evaluated here");
+        };
+        let operand = self.evaluate(operand)?;
+        match (operator, operand) {
+            (UnaryOperator::Bang, operand) => Ok(Value::Bool(!operand.is_truthy())),
+            (UnaryOperator::Minus, Value::Number(number)) => Ok(Value::Number(-number)),
+            (UnaryOperator::Tilde, Value::Number(number)) => bitwise::not(number)
+                .map(Value::Number)
+                .map_err(|message| Error::new(self.line_number, &message)),
+            (UnaryOperator::Minus | UnaryOperator::Tilde, _) => {
+                Err(Error::new(self.line_number, "Operand must be a number."))
+            }
+        }
+    }
+
+    fn evaluate_call(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Call(callee, arguments, line_number) = node else {
+            unreachable!("only calls are evaluated here");
+        };
+        let line_number = *line_number;
+        let callee = self.evaluate(callee)?;
+        let mut argument_values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            argument_values.push(self.evaluate(argument)?);
+        }
+        self.line_number = line_number;
+        self.call(callee, &argument_values)
+    }
+
+    fn evaluate_get(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Get(object, name, line_number) = node else {
+            unreachable!("only property lookups are evaluated here");
+        };
+        let line_number = *line_number;
+        let object = self.evaluate(object)?;
+        self.line_number = line_number;
+        let undefined = || Error::new(line_number, &format!("Undefined property '{}'.", name));
+        match object {
+            Value::Instance(instance) => instance.get(name).ok_or_else(undefined),
+            Value::List(list) => list::method(&list, name).ok_or_else(undefined),
+            Value::Map(map) => map::method(&map, name).ok_or_else(undefined),
+            Value::Module(module) => module
+                .get(name)
+                .ok_or_else(|| not_exported(&module, name, line_number)),
+            _ => Err(Error::new(line_number, "Only instances have properties.")),
+        }
+    }
+
+    fn evaluate_set(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Set(object, name, value, line_number) = node else {
+            unreachable!("only property assignments are evaluated here");
+        };
+        let line_number = *line_number;
+        let object = self.evaluate(object)?;
+        self.line_number = line_number;
+        let Value::Instance(instance) = object else {
+            return Err(Error::new(line_number, "Only instances have fields."));
+        };
+        let value = self.evaluate(value)?;
+        instance.set(name.clone(), value.clone());
+        Ok(value)
+    }
+
+    fn evaluate_list(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::List(elements) = node else {
+            unreachable!("only list literals are evaluated here");
+        };
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            values.push(self.evaluate(element)?);
+        }
+        Ok(Value::from(values))
+    }
+
+    fn evaluate_map(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Map(entries, line_number) = node else {
+            unreachable!("only map literals are evaluated here");
+        };
+        let line_number = *line_number;
+        let mut values = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            values.push((self.evaluate(key)?, self.evaluate(value)?));
+        }
+        self.line_number = line_number;
+        map::from_entries(values).map_err(|message| Error::new(line_number, &message))
+    }
+
+    fn evaluate_index(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Index(object, index, line_number) = node else {
+            unreachable!("only element lookups are evaluated here");
+        };
+        let line_number = *line_number;
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        self.line_number = line_number;
+        let element = match object {
+            Value::List(list) => list::get(&list, &index),
+            Value::Map(map) => map::get(&map, &index),
+            _ => {
+                return Err(Error::new(
+                    line_number,
+                    "Only lists and maps can be indexed.",
+                ))
+            }
+        };
+        element.map_err(|message| Error::new(line_number, &message))
+    }
+
+    fn evaluate_set_index(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::SetIndex(object, index, value, line_number) = node else {
+            unreachable!("only element assignments are evaluated here");
+        };
+        let line_number = *line_number;
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        let value = self.evaluate(value)?;
+        self.line_number = line_number;
+        let result = match object {
+            Value::List(list) => list::set(&list, &index, value.clone()),
+            Value::Map(map) => map::set(&map, &index, value.clone()),
+            _ => {
+                return Err(Error::new(
+                    line_number,
+                    "Only lists and maps can be indexed.",
+                ))
+            }
+        };
+        result.map_err(|message| Error::new(line_number, &message))?;
+        Ok(value)
+    }
+
+    fn evaluate_slice(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Slice(object, start, end, line_number) = node else {
+            unreachable!("only slices are evaluated here");
+        };
+        let line_number = *line_number;
+        let (start, end) = (start.as_deref(), end.as_deref());
+        let object = self.evaluate(object)?;
+        let start = start.map(|start| self.evaluate(start)).transpose()?;
+        let end = end.map(|end| self.evaluate(end)).transpose()?;
+        self.line_number = line_number;
+        let Value::List(list) = object else {
+            return Err(Error::new(line_number, "Only lists can be sliced."));
+        };
+        list::slice(&list, start.as_ref(), end.as_ref())
+            .map_err(|message| Error::new(line_number, &message))
+    }
+
+    fn evaluate_this(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::This(depth, line_number) = node else {
+            unreachable!("only 'this' is evaluated here");
+        };
+        let depth = depth.get();
+        let line_number = *line_number;
+        self.line_number = line_number;
+        self.look_up(&Symbol::intern("this"), depth)
+            .ok_or_else(|| Error::new(line_number, "Can't use 'this' outside of a class."))
+    }
+
+    // `this` is bound in the scope just inside the one binding `super`.
+    fn evaluate_super(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Super(method, depth, line_number) = node else {
+            unreachable!("only 'super' is evaluated here");
+        };
+        let depth = depth.get();
+        let line_number = *line_number;
+        self.line_number = line_number;
+        let (Some(Value::Class(superclass)), Some(Value::Instance(instance))) = (
+            self.look_up(&Symbol::intern("super"), depth),
+            self.look_up(&Symbol::intern("this"), depth.map(|depth| depth - 1)),
+        ) else {
+            return Err(Error::new(
+                line_number,
+                "Can't use 'super' outside of a class.",
+            ));
+        };
+        match superclass.find_method(method) {
+            Some(closure) => Ok(Value::Closure(Rc::new(closure.bind(instance)))),
+            None => Err(Error::new(
+                line_number,
+                &format!("Undefined property '{}'.", method),
+            )),
+        }
+    }
+
+    fn evaluate_variable(&mut self, node: &Node) -> Result<Value, Error> {
+        let Node::Variable(name, depth, line_number) = node else {
+            unreachable!("only variables are evaluated here");
+        };
+        let depth = depth.get();
+        let line_number = *line_number;
+        self.line_number = line_number;
+        match self.look_up(name, depth) {
+            Some(value) => Ok(value),
+            None => Err(Error::new(
+                line_number,
+                &format!("Undefined variable '{}'.", name),
+            )),
+        }
+    }
+
+    fn call(&mut self, callee: Value, arguments: &[Value]) -> Result<Value, Error> {
+        match callee {
+            Value::NativeFunction(native_function) => {
+                self.call_native_function(&native_function, arguments)
+            }
+            Value::Closure(closure) => self.call_closure(&closure, arguments),
+            Value::Class(class) => self.instantiate(&class, arguments),
+            _ => Err(Error::new(
+                self.line_number,
+                "Can only call functions and classes.",
+            )),
+        }
+    }
+
+    fn call_native_function(
+        &mut self,
+        native_function: &NativeFunction,
+        arguments: &[Value],
+    ) -> Result<Value, Error> {
+        if !native_function.arity.accepts(arguments.len()) {
+            if let Arity::Exact(arity) = native_function.arity {
+                return Err(Error::new(
+                    self.line_number,
+                    &format!(
+                        "Expected {} arguments but got {}.",
+                        arity,
+                        arguments.len()
+                    ),
+                ));
+            }
+        }
+        (native_function.function)(self, arguments)
+            .map_err(|message| Error::new(self.line_number, &message))
+    }
+
+    fn instantiate(&mut self, class: &Rc<Class>, arguments: &[Value]) -> Result<Value, Error> {
+        let instance = Rc::new(Instance::new(Rc::clone(class)));
+        match class.find_method(&Symbol::intern("init")) {
+            Some(initializer) => {
+                self.call_closure(&initializer.bind(Rc::clone(&instance)), arguments)?;
+            }
+            None if !arguments.is_empty() => {
+                return Err(Error::new(
+                    self.line_number,
+                    &format!("Expected 0 arguments but got {}.", arguments.len()),
+                ))
+            }
+            None => {}
+        }
+        Ok(Value::Instance(instance))
+    }
+
+    fn call_closure(&mut self, closure: &Closure, arguments: &[Value]) -> Result<Value, Error> {
+        let environment = self.enter_call(closure, arguments)?;
+        self.call_depth += 1;
+        let result = self.execute_block(&closure.function.body, environment);
+        self.call_depth -= 1;
+        self.returned(closure, result)
+    }
+
+    // Checks that a closure can be called with the arguments, and gives back the scope its body
+    // runs in, with each parameter bound to its argument.
+    fn enter_call(
+        &mut self,
+        closure: &Closure,
+        arguments: &[Value],
+    ) -> Result<Rc<RefCell<Environment>>, Error> {
+        let parameters = &closure.function.parameters;
+        if parameters.len() != arguments.len() {
+            return Err(Error::new(
+                self.line_number,
+                &format!(
+                    "Expected {} arguments but got {}.",
+                    parameters.len(),
+                    arguments.len()
+                ),
+            ));
+        }
+        let marker = 0u8;
+        let stack_position = std::ptr::addr_of!(marker) as usize;
+        if self.call_depth == 0 {
+            self.stack_base = stack_position;
+        } else if self.stack_base.abs_diff(stack_position) > self.stack_size {
+            return Err(Error::new(self.line_number, "Stack overflow."));
+        }
+
+        let mut environment = Environment::new(Some(Rc::clone(&closure.environment)));
+        for (parameter, argument) in parameters.iter().zip(arguments) {
+            environment.define(parameter.clone(), argument.clone());
+        }
+        Ok(Rc::new(RefCell::new(environment)))
+    }
+
+    // The value a call gives back once the body of the closure has run.
+    fn returned(&self, closure: &Closure, result: Result<(), Unwind>) -> Result<Value, Error> {
+        match result {
+            // An initializer gives back the instance, even from an early `return;`.
+            Ok(()) | Err(Unwind::Return(_)) if closure.is_initializer => Ok(closure
+                .environment
+                .borrow()
+                .get(&Symbol::intern("this"))
+                .unwrap_or(Value::Nil)),
+            Ok(()) => Ok(Value::Nil),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(error)) => Err(error),
+            Err(Unwind::Break(_) | Unwind::Continue(_)) => unreachable!(),
+        }
+    }
+
+    // A thrown error object gets the line it was first thrown from, and is reported with its
+    // message if it isn't caught.
+    fn thrown(&self, value: Value, line_number: i32) -> Error {
+        let mut error = Error::new(line_number, &format!("Uncaught exception: {}.", value));
+        if let Value::Instance(instance) = &value {
+            if self.is_error(instance) {
+                let mut fields = instance.fields.borrow_mut();
+                let line = fields
+                    .entry(Symbol::intern("line"))
+                    .or_insert(Value::Number(line_number as f64));
+                if let Value::Number(line) = line {
+                    error.line_number = *line as i32;
+                }
+                if let Some(message) = fields.get(&Symbol::intern("message")) {
+                    error.message = message.to_string();
+                }
+            }
+        }
+        error.thrown = Some(value);
+        error
+    }
+
+    // What a catch clause binds: the value thrown, or an error object for a runtime error.
+    fn caught(&self, error: Error) -> Value {
+        if let Some(value) = error.thrown {
+            return value;
+        }
+        let instance = Instance::new(Rc::clone(&self.error_class));
+        instance.fields.borrow_mut().extend([
+            (Symbol::intern("message"), Value::from(error.message)),
+            (
+                Symbol::intern("line"),
+                Value::Number(error.line_number as f64),
+            ),
+        ]);
+        Value::Instance(Rc::new(instance))
+    }
+
+    fn is_error(&self, instance: &Instance) -> bool {
+        let mut class = Some(&instance.class);
+        while let Some(current) = class {
+            if Rc::ptr_eq(current, &self.error_class) {
+                return true;
+            }
+            class = current.superclass.as_ref();
+        }
+        false
+    }
+
+    fn sum(&self, operator: &SumOperator, left: Value, right: Value) -> Result<Value, Error> {
+        match (operator, &left, &right) {
+            (SumOperator::Plus, Value::Number(left), Value::Number(right)) => {
+                Ok(Value::Number(left + right))
+            }
+            (SumOperator::Plus, Value::String(left), Value::String(right)) => {
+                Ok(Value::String(Rc::from(format!("{}{}", left, right))))
+            }
+            (SumOperator::Plus, _, _) => Err(Error::new(
+                self.line_number,
+                "Operands must be two numbers or two strings.",
+            )),
+            (SumOperator::Minus, _, _) => {
+                let (left, right) = self.number_operands(&left, &right)?;
+                Ok(Value::Number(left - right))
+            }
+        }
+    }
+
+    fn product(
+        &self,
+        operator: &ProductOperator,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, Error> {
+        let (left, right) = self.number_operands(&left, &right)?;
+        arithmetic::product(operator, left, right)
+            .map(Value::Number)
+            .map_err(|message| Error::new(self.line_number, &message))
+    }
+
+    fn bitwise(
+        &self,
+        operator: &BitwiseOperator,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, Error> {
+        let (left, right) = self.number_operands(&left, &right)?;
+        bitwise::bitwise(operator, left, right)
+            .map(Value::Number)
+            .map_err(|message| Error::new(self.line_number, &message))
+    }
+
+    fn shift(&self, operator: &ShiftOperator, left: Value, right: Value) -> Result<Value, Error> {
+        let (left, right) = self.number_operands(&left, &right)?;
+        bitwise::shift(operator, left, right)
+            .map(Value::Number)
+            .map_err(|message| Error::new(self.line_number, &message))
+    }
+
+    fn power(&self, base: Value, exponent: Value) -> Result<Value, Error> {
+        let (base, exponent) = self.number_operands(&base, &exponent)?;
+        Ok(Value::Number(base.powf(exponent)))
+    }
+
+    // Gives a variable, property or element a value computed from the one it has, returning both.
+    // The object and index of the target are evaluated once, before the new value is computed.
+    fn update(
+        &mut self,
+        target: &Node,
+        compute: impl FnOnce(&mut Self, Value) -> Result<Value, Error>,
+    ) -> Result<(Value, Value), Error> {
+        match target {
+            Node::Variable(name, depth, _) => {
+                let old_value = self.evaluate(target)?;
+                let new_value = compute(self, old_value.clone())?;
+                self.assign(name, depth.get(), new_value.clone());
+                Ok((old_value, new_value))
+            }
+            Node::Get(object, name, line_number) => {
+                let object = self.evaluate(object)?;
+                self.line_number = *line_number;
+                let Value::Instance(instance) = object else {
+                    return Err(Error::new(*line_number, "Only instances have fields."));
+                };
+                let old_value = instance.get(name).ok_or_else(|| {
+                    Error::new(*line_number, &format!("Undefined property '{}'.", name))
+                })?;
+                let new_value = compute(self, old_value.clone())?;
+                instance.set(name.clone(), new_value.clone());
+                Ok((old_value, new_value))
+            }
+            Node::Index(object, index, line_number) => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                self.line_number = *line_number;
+                let old_value = match &object {
+                    Value::List(list) => list::get(list, &index),
+                    Value::Map(map) => map::get(map, &index),
+                    _ => {
+                        return Err(Error::new(
+                            *line_number,
+                            "Only lists and maps can be indexed.",
+                        ))
+                    }
+                }
+                .map_err(|message| Error::new(*line_number, &message))?;
+                let new_value = compute(self, old_value.clone())?;
+                self.line_number = *line_number;
+                match &object {
+                    Value::List(list) => list::set(list, &index, new_value.clone()),
+                    Value::Map(map) => map::set(map, &index, new_value.clone()),
+                    _ => unreachable!("only lists and maps were indexed"),
+                }
+                .map_err(|message| Error::new(*line_number, &message))?;
+                Ok((old_value, new_value))
+            }
+            _ => unreachable!("the parser only allows variables, properties and elements"),
+        }
+    }
+
+    // Looks a variable up in the scope the resolver found it in, or in each enclosing scope in turn
+    // when it wasn't resolved.
+    fn look_up(&self, name: &Symbol, depth: Option<usize>) -> Option<Value> {
+        match depth {
+            Some(depth) => self.environment.borrow().get_at(depth, name),
+            None => self.environment.borrow().get(name),
+        }
+    }
+
+    fn assign(&self, name: &Symbol, depth: Option<usize>, value: Value) -> bool {
+        let mut environment = self.environment.borrow_mut();
+        match depth {
+            Some(depth) => environment.assign_at(depth, name, value),
+            None => environment.assign(name, value),
+        }
+    }
+
+    fn number_operands(&self, left: &Value, right: &Value) -> Result<(f64, f64), Error> {
+        match (left, right) {
+            (Value::Number(left), Value::Number(right)) => Ok((*left, *right)),
+            _ => Err(Error::new(self.line_number, "Operands must be numbers.")),
+        }
+    }
+
+    fn error(&mut self, line_number: i32, message: &str) -> Result<(), std::io::Error> {
+        self.report(line_number, message)
+    }
+
+    fn runtime_error(&mut self, error: Error) -> Result<(), std::io::Error> {
+        writeln!(
+            self.diagnostics,
+            "{}\n[line {}]",
+            error.message, error.line_number
+        )?;
+        self.had_runtime_error = true;
+        Ok(())
+    }
+
+    fn report(&mut self, line_number: i32, message: &str) -> Result<(), std::io::Error> {
+        writeln!(
+            self.diagnostics,
+            "[line {}] Error: {}",
+            line_number, message
+        )?;
         self.had_error = true;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub line_number: i32,
+    pub message: String,
+    // The value of the `throw` that raised the error, if one did.
+    thrown: Option<Value>,
+}
+
+impl Error {
+    fn new(line_number: i32, message: &str) -> Error {
+        Error {
+            line_number,
+            message: message.to_string(),
+            thrown: None,
+        }
+    }
+}
+
+// Makes the class of error objects, whose initializer is written in Lox.
+fn error_class(builtins: &Rc<RefCell<Environment>>) -> Rc<Class> {
+    let tokens = Scanner::new()
+        .scan(ERROR_CLASS)
+        .expect("the Error class scans");
+    let statements = RecursiveDescentParser::new()
+        .parse_program(&tokens)
+        .expect("the Error class parses");
+    Resolver::new()
+        .resolve(&statements)
+        .expect("the Error class resolves");
+    let Some(Statement::Class(class)) = statements.first() else {
+        unreachable!("the Error class is a class declaration");
+    };
+    let methods = class
+        .methods
+        .iter()
+        .map(|method| {
+            let closure = Closure {
+                function: Rc::clone(method),
+                environment: Rc::clone(builtins),
+                is_initializer: method.name.as_str() == "init",
+            };
+            (method.name.clone(), Rc::new(closure))
+        })
+        .collect();
+    Rc::new(Class {
+        name: class.name.clone(),
+        superclass: None,
+        methods,
+    })
+}
+
+fn not_exported(module: &Module, name: &Symbol, line_number: i32) -> Error {
+    Error::new(
+        line_number,
+        &format!("Module '{}' doesn't export '{}'.", module.name, name),
+    )
+}
+
+// What the prompt should do after running a meta-command.
+enum Command {
+    Continue,
+    Quit,
+}
+
+// The ways executing a statement can leave the statements that follow it unexecuted.
+enum Unwind {
+    Error(Error),
+    Return(Value),
+    // The resolver keeps these inside the loops they name, so they never reach a function call or
+    // the top level.
+    Break(Option<Symbol>),
+    Continue(Option<Symbol>),
+}
+
+impl From<Error> for Unwind {
+    fn from(error: Error) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::Interpreter;
+    use crate::io::SharedBuffer;
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::scanner::Scanner;
+    use crate::value::{Arity, Value};
+
+    fn evaluate(interpreter: &mut Interpreter, source_code: &str) -> Result<Value, String> {
+        let tokens = Scanner::new().scan(source_code).unwrap();
+        let node = RecursiveDescentParser::new().parse(&tokens).unwrap();
+        interpreter
+            .evaluate(&node)
+            .map_err(|runtime_error| runtime_error.message)
+    }
+
+    #[test]
+    fn expressions_are_evaluated() {
+        struct TestCase<'a> {
+            input: &'a str,
+            expected_output: Value,
+        }
+
+        let test_cases = [
+            TestCase {
+                input: "1 + 2 * 3",
+                expected_output: Value::Number(7.0),
+            },
+            TestCase {
+                input: "(1 + 2) * 3",
+                expected_output: Value::Number(9.0),
+            },
+            TestCase {
+                input: "-4 / 2",
+                expected_output: Value::Number(-2.0),
+            },
+            TestCase {
+                input: "\"sushi\" + \"roll\"",
+                expected_output: Value::String("sushiroll".into()),
+            },
+            TestCase {
+                input: "1 < 2 == !false",
+                expected_output: Value::Bool(true),
+            },
+            TestCase {
+                input: "nil != false",
+                expected_output: Value::Bool(true),
+            },
+            TestCase {
+                input: "1, 2",
+                expected_output: Value::Number(2.0),
+            },
+            TestCase {
+                input: "-7 % 3 + -7 ~/ 2",
+                expected_output: Value::Number(-4.0),
+            },
+            TestCase {
+                input: "-2 ** 3 ** 2",
+                expected_output: Value::Number(-512.0),
+            },
+            TestCase {
+                input: "nil ? 1 : false ? 2 : 3",
+                expected_output: Value::Number(3.0),
+            },
+            TestCase {
+                input: "12 & 10 | 1 ^ 3",
+                expected_output: Value::Number(10.0),
+            },
+            TestCase {
+                input: "~5 >> 1 == -3",
+                expected_output: Value::Bool(true),
+            },
+            TestCase {
+                input: "1 << 4 + 1",
+                expected_output: Value::Number(32.0),
+            },
+        ];
+
+        let mut interpreter = Interpreter::new();
+        for test_case in test_cases {
+            let output = evaluate(&mut interpreter, test_case.input).unwrap();
+            assert_eq!(output, test_case.expected_output);
+        }
+    }
+
+    #[test]
+    fn type_errors_are_runtime_errors() {
+        let mut interpreter = Interpreter::new();
+
+        assert_eq!(
+            evaluate(&mut interpreter, "\"a\" - 1"),
+            Err("Operands must be numbers.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "-\"a\""),
+            Err("Operand must be a number.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "\"a\" + 1"),
+            Err("Operands must be two numbers or two strings.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "2 ** \"a\""),
+            Err("Operands must be numbers.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "1 | nil"),
+            Err("Operands must be numbers.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "1.5 & 1"),
+            Err("Operands must be integers.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "~true"),
+            Err("Operand must be a number.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "1 << 64"),
+            Err("Shift amount must be between 0 and 63.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "5 ~/ 0"),
+            Err("Division by zero.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "5 % 0"),
+            Err("Division by zero.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "5 / 0"),
+            Ok(Value::Number(f64::INFINITY))
+        );
+    }
+
+    #[test]
+    fn native_functions_can_be_registered_and_called() {
+        let mut interpreter = Interpreter::new();
+        interpreter.define_native("twice", Arity::Exact(1), |_, arguments| {
+            match arguments[0] {
+                Value::Number(number) => Ok(Value::Number(number * 2.0)),
+                _ => Err("twice() expects a number.".to_string()),
+            }
+        });
+
+        assert_eq!(
+            evaluate(&mut interpreter, "twice(21)"),
+            Ok(Value::Number(42.0))
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "twice(nil)"),
+            Err("twice() expects a number.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "twice(1, 2)"),
+            Err("Expected 1 arguments but got 2.".to_string())
+        );
+    }
+
+    #[test]
+    fn undefined_variables_and_uncallable_values_are_runtime_errors() {
+        let mut interpreter = Interpreter::new();
+
+        assert_eq!(
+            evaluate(&mut interpreter, "breakfast"),
+            Err("Undefined variable 'breakfast'.".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "\"sushi\"()"),
+            Err("Can only call functions and classes.".to_string())
+        );
+    }
+
+    #[test]
+    fn output_and_diagnostics_can_be_captured() {
+        let output = SharedBuffer::new();
+        let diagnostics = SharedBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+        interpreter.set_diagnostics(diagnostics.clone());
+
+        interpreter.run("print upper(\"sushi\");").unwrap();
+        assert!(output.contents().ends_with("SUSHI\n"));
+        assert_eq!(diagnostics.contents(), "");
+
+        interpreter.run("-nil;").unwrap();
+        assert_eq!(diagnostics.contents(), "Operand must be a number.\n[line 1]\n");
+    }
+
+    #[test]
+    fn type_errors_are_only_reported_when_checking_types() {
+        let output = SharedBuffer::new();
+        let diagnostics = SharedBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+        interpreter.set_diagnostics(diagnostics.clone());
+        let source_code = "var a: Number = \"sushi\";\nprint a;";
+
+        interpreter.check(source_code).unwrap();
+        assert_eq!(diagnostics.contents(), "");
+        interpreter.check_types(source_code).unwrap();
+        assert_eq!(
+            diagnostics.contents(),
+            "[line 1] Error: Expected Number for 'a' but found String.\n"
+        );
+        assert!(interpreter.had_error());
+        assert_eq!(output.contents(), "");
+    }
+
+    #[test]
+    fn input_reads_from_the_input_source() {
+        let output = SharedBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+        interpreter.set_input("sushi\nramen\n".as_bytes());
+
+        assert_eq!(
+            evaluate(&mut interpreter, "input(\"? \")"),
+            Ok(Value::String("sushi".into()))
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "input()"),
+            Ok(Value::String("ramen".into()))
+        );
+        assert_eq!(evaluate(&mut interpreter, "input()"), Ok(Value::Nil));
+        assert_eq!(output.contents(), "? ");
+    }
+
+    fn run(source_code: &str) -> (String, String) {
+        let output = SharedBuffer::new();
+        let diagnostics = SharedBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+        interpreter.set_diagnostics(diagnostics.clone());
+        interpreter.run(source_code).unwrap();
+        (output.contents(), diagnostics.contents())
+    }
+
+    #[test]
+    fn blocks_scope_variables() {
+        let (output, _) = run("
+            var meal = \"breakfast\";
+            {
+                var meal = \"lunch\";
+                print meal;
+                meal = \"dinner\";
+                print meal;
+            }
+            print meal;
+        ");
+
+        assert_eq!(output, "lunch\ndinner\nbreakfast\n");
+    }
+
+    #[test]
+    fn control_flow_statements_are_executed() {
+        let (output, _) = run("
+            for (var i = 0; i < 3; i = i + 1) {
+                if (i == 1) print \"one\"; else print i;
+            }
+            var n = 3;
+            while (n > 0) n = n - 1;
+            print n;
+            print nil or \"default\";
+            print false and undefined;
+        ");
+
+        assert_eq!(output, "0\none\n2\n0\ndefault\nfalse\n");
+    }
+
+    #[test]
+    fn compound_assignments_and_increments_update_their_targets() {
+        let (output, diagnostics) = run("
+            var a = 10;
+            a += 2;
+            a -= 4;
+            a *= 3;
+            a /= 2;
+            a %= 5;
+            a **= 3;
+            a ~/= 3;
+            print a;
+            var bits = 12;
+            bits &= 10;
+            bits |= 1;
+            bits ^= 3;
+            bits <<= 2;
+            bits >>= 1;
+            print bits;
+            var s = \"sushi\";
+            s += \" roll\";
+            print s;
+            print [a++, a, ++a, a--, --a];
+            class Counter {}
+            var counter = Counter();
+            counter.count = 0;
+            counter.count += 5;
+            print counter.count++ + counter.count;
+            var calls = 0;
+            fun index() {
+                calls = calls + 1;
+                return 1;
+            }
+            var xs = [1, 2];
+            xs[index()] *= 10;
+            xs[index()]--;
+            print [xs, calls];
+            var m = {\"k\": 1};
+            print ++m[\"k\"];
+            s++;
+        ");
+
+        assert_eq!(
+            output,
+            "2\n20\nsushi roll\n[2, 3, 4, 4, 2]\n11\n[[1, 19], 2]\n2\n"
+        );
+        assert_eq!(diagnostics, "Operand must be a number.\n[line 38]\n");
+    }
+
+    #[test]
+    fn functions_return_values_and_close_over_their_scope() {
+        let (output, _) = run("
+            fun fib(n) {
+                if (n < 2) return n;
+                return fib(n - 1) + fib(n - 2);
+            }
+            print fib(10);
+
+            fun make_counter() {
+                var count = 0;
+                fun counter() {
+                    count = count + 1;
+                    return count;
+                }
+                return counter;
+            }
+            var counter = make_counter();
+            counter();
+            print counter();
+            print make_counter;
+        ");
+
+        assert_eq!(output, "55\n2\n<fn make_counter>\n");
+    }
+
+    #[test]
+    fn closures_are_lexically_scoped() {
+        let (output, _) = run("
+            var a = \"global\";
+            {
+                fun showA() {
+                    print a;
+                }
+                showA();
+                var a = \"block\";
+                showA();
+                a = \"assigned\";
+                print a;
+            }
+            print a;
+        ");
+
+        assert_eq!(output, "global\nglobal\nassigned\nglobal\n");
+    }
+
+    #[test]
+    fn classes_create_instances_with_fields_and_inherited_methods() {
+        let (output, _) = run("
+            class Point {
+                init(x, y) {
+                    this.x = x;
+                    this.y = y;
+                }
+                sum() { return this.x + this.y; }
+            }
+            class Scaled < Point {
+                init(x, y, factor) {
+                    super.init(x * factor, y * factor);
+                    if (factor == 0) return;
+                    this.factor = factor;
+                }
+                sum() { return super.sum() / this.factor; }
+            }
+            var point = Point(1, 2);
+            var sum = point.sum;
+            point.x = 10;
+            print sum();
+            print Scaled(1, 2, 3).sum();
+            print point.init(0, 0) == point;
+            print Point;
+            print Scaled(1, 1, 0);
+        ");
+
+        assert_eq!(output, "12\n3\ntrue\nPoint\nScaled instance\n");
+    }
+
+    #[test]
+    fn lists_are_indexed_sliced_and_changed_by_their_methods() {
+        let (output, _) = run("
+            var xs = [1, 2, 3];
+            var ys = xs;
+            xs[0] = \"a\";
+            print ys;
+            print xs[-1] + xs[1];
+            print xs[1:] == xs[1:];
+            print xs[:-1];
+            ys.push(4);
+            ys.insert(0, nil);
+            print ys.pop() + ys.len();
+            print ys.remove(-1);
+            print xs.contains(\"a\") and !xs.contains(3);
+            print xs;
+            print len(xs);
+            print type(xs);
+            print [[], [1]];
+        ");
+
+        assert_eq!(
+            output,
+            "[a, 2, 3]\n5\nfalse\n[a, 2]\n8\n3\ntrue\n[nil, a, 2]\n3\nlist\n[[], [1]]\n"
+        );
+    }
+
+    #[test]
+    fn maps_keep_their_keys_in_insertion_order() {
+        let (output, _) = run("
+            var m = {\"b\": 1, nil: 2, true: 3};
+            m[0] = 4;
+            m[\"b\"] = m[-0] + m[nil];
+            print m;
+            print m.keys();
+            print m.values()[1:];
+            print m.has(0) and !m.has(\"0\");
+            print m.delete(nil);
+            print m.delete(nil);
+            var keys = m.keys();
+            for (var i = 0; i < len(keys); i = i + 1) print keys[i];
+            print len(m);
+            print type({});
+            { print {1: {}}; }
+        ");
+
+        assert_eq!(
+            output,
+            "{b: 6, nil: 2, true: 3, 0: 4}
+[b, nil, true, 0]
+[2, 3, 4]
+true
+true
+false
+b
+true
+0
+3
+map
+{1: {}}
+"
+        );
+    }
+
+    #[test]
+    fn maps_are_iterated_over_through_their_keys() {
+        let (output, _) = run("
+            var prices = {\"apple\": 3, \"pear\": 5, \"plum\": 2};
+            var keys = prices.keys();
+            for (var i = 0; i < len(keys); i = i + 1) {
+                var key = keys[i];
+                if (prices[key] < 3) prices.delete(key);
+                else prices[key] = prices[key] * 2;
+            }
+            print keys;
+            print prices;
+        ");
+
+        assert_eq!(output, "[apple, pear, plum]\n{apple: 6, pear: 10}\n");
+    }
+
+    #[test]
+    fn lists_and_maps_containing_themselves_are_printed() {
+        let (output, _) = run("
+            var xs = [1];
+            xs.push(xs);
+            print xs;
+            var m = {\"a\": xs};
+            m[\"b\"] = m;
+            print m;
+            print str([m, m]);
+        ");
+
+        assert_eq!(
+            output,
+            "[1, [...]]\n{a: [1, [...]], b: {...}}\n[{a: [1, [...]], b: {...}}, {a: [1, [...]], b: {...}}]\n"
+        );
+    }
+
+    #[test]
+    fn misusing_maps_are_runtime_errors() {
+        let test_cases = [
+            ("print {}[\"a\"];", "Undefined key 'a'."),
+            (
+                "var m = {[]: 1};",
+                "Map key must be nil, a boolean, a number or a string but got list.",
+            ),
+            (
+                "var m = {}; m[m] = 1;",
+                "Map key must be nil, a boolean, a number or a string but got map.",
+            ),
+            ("print {}.has(0/0);", "NaN can't be a map key."),
+            ("print {}.size;", "Undefined property 'size'."),
+            ("nil[0] = 1;", "Only lists and maps can be indexed."),
+        ];
+
+        for (input, expected_output) in test_cases {
+            let (_, diagnostics) = run(input);
+            assert_eq!(diagnostics, format!("{}\n[line 1]\n", expected_output));
+        }
+    }
+
+    #[test]
+    fn misusing_lists_are_runtime_errors() {
+        let test_cases = [
+            (
+                "[1][1];",
+                "Index 1 is out of bounds for a list of length 1.",
+            ),
+            (
+                "[1][-2] = 0;",
+                "Index -2 is out of bounds for a list of length 1.",
+            ),
+            ("[1][0.5];", "List index must be an integer."),
+            ("[1][\"0\"];", "List index must be an integer."),
+            ("[].pop();", "Can't pop from an empty list."),
+            (
+                "[].insert(1, 2);",
+                "Index 1 is out of bounds for a list of length 0.",
+            ),
+            (
+                "[].remove(0);",
+                "Index 0 is out of bounds for a list of length 0.",
+            ),
+            ("[].push();", "Expected 1 arguments but got 0."),
+            ("[].size;", "Undefined property 'size'."),
+            ("\"a\"[0];", "Only lists and maps can be indexed."),
+            ("nil[0:1];", "Only lists can be sliced."),
+        ];
+
+        for (input, expected_output) in test_cases {
+            let (_, diagnostics) = run(input);
+            assert_eq!(diagnostics, format!("{}\n[line 1]\n", expected_output));
+        }
+    }
+
+    #[test]
+    fn misusing_classes_and_instances_are_runtime_errors() {
+        let test_cases = [
+            ("var A = 1; class B < A {}", "Superclass must be a class."),
+            ("class A {} A().b;", "Undefined property 'b'."),
+            ("true.b;", "Only instances have properties."),
+            ("\"a\".b = 1;", "Only instances have fields."),
+            ("class A {} A(1);", "Expected 0 arguments but got 1."),
+            (
+                "class A { init(a) {} } A();",
+                "Expected 1 arguments but got 0.",
+            ),
+        ];
+
+        for (input, expected_output) in test_cases {
+            let (_, diagnostics) = run(input);
+            assert_eq!(diagnostics, format!("{}\n[line 1]\n", expected_output));
+        }
+    }
+
+    #[test]
+    fn thrown_values_and_runtime_errors_are_caught() {
+        let (output, diagnostics) = run("
+            try {
+                throw \"a\";
+            } catch (e) {
+                print e;
+            }
+            try {
+                print nil + 1;
+            } catch (e) {
+                print e.message;
+                print e.line;
+            }
+            class NotFound < Error {}
+            fun find(key) { throw NotFound(key); }
+            try {
+                find(\"b\");
+            } catch (e) {
+                print e.message + \" at \" + str(e.line);
+            }
+            var log = [];
+            fun attempt(f) {
+                try {
+                    return f();
+                } catch (e) {
+                    log.push(e.message);
+                    return nil;
+                } finally {
+                    log.push(\"done\");
+                }
+            }
+            fun one() { return 1; }
+            fun missing() { return [][0]; }
+            print attempt(one);
+            print attempt(missing);
+            print log;
+            fun counter() {
+                var count = 0;
+                fun next() {
+                    count = count + 1;
+                    if (count == 2) throw count;
+                    return count;
+                }
+                return next;
+            }
+            var next = counter();
+            try {
+                while (true) next();
+            } catch (e) {
+                print next();
+            }
+        ");
+
+        assert_eq!(
+            output,
+            "a\nOperands must be two numbers or two strings.\n8\nb at 14\n1\nnil\n[done, Index 0 is out of bounds for a list of length 0., done]\n3\n"
+        );
+        assert_eq!(diagnostics, "");
+    }
+
+    #[test]
+    fn finally_runs_however_its_try_statement_ends() {
+        let (output, diagnostics) = run("
+            fun f() {
+                try {
+                    return \"try\";
+                } finally {
+                    print \"finally\";
+                }
+            }
+            print f();
+            fun g() {
+                try {
+                    throw Error(\"lost\");
+                } finally {
+                    return \"finally\";
+                }
+            }
+            print g();
+            try {
+                try {
+                    throw 1;
+                } finally {
+                    print \"inner\";
+                }
+            } catch (e) {
+                print e;
+            }
+            try {
+                print \"no error\";
+            } catch (e) {
+                print \"unreachable\";
+            }
+            var e = Error(\"rethrown\");
+            try {
+                throw e;
+            } catch (e) {
+                print \"after\";
+                throw e;
+            }
+        ");
+
+        assert_eq!(output, "finally\ntry\nfinally\ninner\n1\nno error\nafter\n");
+        assert_eq!(diagnostics, "rethrown\n[line 34]\n");
+    }
+
+    #[test]
+    fn break_and_continue_leave_their_loops() {
+        let (output, diagnostics) = run("
+            var i = 0;
+            while (true) {
+                i = i + 1;
+                if (i == 2) continue;
+                if (i > 3) break;
+                print i;
+            }
+            for (var j = 0; j < 5; j = j + 1) {
+                if (j < 3) continue;
+                print j;
+            }
+            outer: for (var a = 0; a < 3; a = a + 1) {
+                for (var b = 0; b < 3; b = b + 1) {
+                    if (b > a) continue outer;
+                    if (a == 2) break outer;
+                    print a + b;
+                }
+            }
+            while (true) {
+                try {
+                    break;
+                } finally {
+                    print \"finally\";
+                }
+            }
+        ");
+
+        assert_eq!(output, "1\n3\n3\n4\n0\n1\n2\nfinally\n");
+        assert_eq!(diagnostics, "");
+    }
+
+    #[test]
+    fn uncaught_throws_are_runtime_errors() {
+        let test_cases = [
+            ("throw \"a\";", "Uncaught exception: a."),
+            ("throw Error(\"b\");", "b"),
+            (
+                "try { throw 1; } catch (e) { throw e + 1; }",
+                "Uncaught exception: 2.",
+            ),
+            (
+                "try {} finally { nil(); }",
+                "Can only call functions and classes.",
+            ),
+        ];
+
+        for (input, expected_output) in test_cases {
+            let (_, diagnostics) = run(input);
+            assert_eq!(diagnostics, format!("{}\n[line 1]\n", expected_output));
+        }
+    }
+
+    // Writes the files into a directory of their own and runs the first of them.
+    fn run_files(name: &str, files: &[(&str, &str)]) -> (String, String) {
+        let directory =
+            std::env::temp_dir().join(format!("rustlox-{}-{}", name, std::process::id()));
+        for (filename, source_code) in files {
+            let file = directory.join(filename);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, source_code).unwrap();
+        }
+        let output = SharedBuffer::new();
+        let diagnostics = SharedBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+        interpreter.set_diagnostics(diagnostics.clone());
+        interpreter.set_search_path(vec![directory.join("lib")]);
+        interpreter
+            .run_file(directory.join(files[0].0).to_str().unwrap())
+            .unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        let prefix = format!("{}{}", directory.display(), std::path::MAIN_SEPARATOR);
+        (
+            output.contents(),
+            diagnostics.contents().replace(&prefix, ""),
+        )
+    }
+
+    #[test]
+    fn modules_run_once_with_globals_of_their_own() {
+        let (output, diagnostics) = run_files(
+            "modules",
+            &[
+                (
+                    "main.lox",
+                    "
+                    var secret = \"main\";
+                    import \"shapes/square.lox\" as square;
+                    import { area, count } from \"shapes/square.lox\";
+                    import { twice } from \"math.lox\";
+                    print square.area(3);
+                    print twice(area(2));
+                    print count;
+                    print square.count;
+                    print type(square);
+                    print secret;
+                    ",
+                ),
+                (
+                    "shapes/square.lox",
+                    "
+                    import { twice } from \"../lib/math.lox\";
+                    var secret = \"square\";
+                    print \"loading \" + secret;
+                    export var count = 0;
+                    export fun area(side) {
+                        count = count + 1;
+                        return side * side;
+                    }
+                    ",
+                ),
+                (
+                    "lib/math.lox",
+                    "print \"loading math\"; export fun twice(x) { return 2 * x; }",
+                ),
+            ],
+        );
+
+        assert_eq!(
+            output,
+            "loading math\nloading square\n9\n8\n0\n2\nmodule\nmain\n"
+        );
+        assert_eq!(diagnostics, "");
+    }
+    #[test]
+    fn misusing_modules_are_runtime_errors() {
+        let test_cases = [
+            (
+                "import \"b.lox\" as b;",
+                "import \"a.lox\" as a;",
+                "Import cycle: a.lox -> b.lox -> a.lox.\n[line 1] in b.lox\n[line 1]",
+            ),
+            (
+                "import { b } from \"b.lox\";",
+                "var b = 1;",
+                "Module 'b.lox' doesn't export 'b'.\n[line 1]",
+            ),
+            (
+                "import \"b.lox\" as b; print b.c;",
+                "export var b;",
+                "Module 'b.lox' doesn't export 'c'.\n[line 1]",
+            ),
+            (
+                "import \"c.lox\" as c;",
+                "",
+                "Can't find module 'c.lox'.\n[line 1]",
+            ),
+            (
+                "var a = 1;\nimport \"b.lox\" as b;",
+                "\nprint a;",
+                "Undefined variable 'a'.\n[line 2] in b.lox\n[line 2]",
+            ),
+            (
+                "import \"b.lox\" as b;",
+                "export fun f() {\n  return;\n  print;\n}",
+                "Unexpected token: ;\n[line 3] in b.lox\n[line 1]",
+            ),
+        ];
+
+        for (i, (main, b, expected_output)) in test_cases.into_iter().enumerate() {
+            let (_, diagnostics) = run_files(
+                &format!("module-errors-{}", i),
+                &[("a.lox", main), ("b.lox", b)],
+            );
+            assert_eq!(diagnostics, format!("{}\n", expected_output), "{}", main);
+        }
+    }
+
+    #[test]
+    fn runtime_errors_stop_the_program() {
+        let (output, diagnostics) = run("
+            fun f(a) { return a; }
+            print \"before\";
+            f(1, 2);
+            print \"after\";
+        ");
+
+        assert_eq!(output, "before\n");
+        assert_eq!(diagnostics, "Expected 1 arguments but got 2.\n[line 4]\n");
+    }
+
+    #[test]
+    fn unbounded_recursion_is_a_runtime_error() {
+        let program = "
+            fun f() { return f(); }
+            for (var i = 0; i < 2; i = i + 1) {
+                try { f(); } catch (e) { print e.message; }
+            }
+            f();
+        ";
+        let (output, diagnostics) = run_on_main_thread_stack(program);
+
+        assert_eq!(output, "Stack overflow.\nStack overflow.\n");
+        assert_eq!(diagnostics, "Stack overflow.\n[line 2]\n");
+    }
+
+    #[test]
+    fn deep_recursion_fits_in_the_default_stack_size() {
+        let program = "
+            fun f(n) { if (n == 0) return 0; return f(n - 1) + 1; }
+            print f(1000);
+        ";
+        let (output, diagnostics) = run_on_main_thread_stack(program);
+
+        assert_eq!(output, "1000\n");
+        assert_eq!(diagnostics, "");
+    }
+
+    // Runs a program with a default interpreter on a thread with the 8 MiB stack of a main thread,
+    // which the default stack size is meant for, rather than the smaller one of a test's thread.
+    fn run_on_main_thread_stack(source_code: &'static str) -> (String, String) {
+        std::thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(|| run(source_code))
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn prompt_keeps_state_continues_lines_and_prints_expressions() {
+        let output = SharedBuffer::new();
+        let diagnostics = SharedBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+        interpreter.set_diagnostics(diagnostics.clone());
+        interpreter.set_input(
+            "var a = 1\n+ 2;\nfun f() {\nreturn a;\n}\nf()\n1 +;\na\n".as_bytes(),
+        );
+
+        interpreter.run_prompt().unwrap();
+
+        assert_eq!(output.contents(), ">> .. >> .. .. >> 3\n>> >> 3\n>> ");
+        assert_eq!(
+            diagnostics.contents(),
+            "[line 1] Error: Unexpected token: ;\n"
+        );
+    }
+
+    #[test]
+    fn prompt_runs_meta_commands() {
+        let output = SharedBuffer::new();
+        let diagnostics = SharedBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(output.clone());
+        interpreter.set_diagnostics(diagnostics.clone());
+        interpreter.set_input(
+            ":type len\nvar b = 2;\nvar a = \"sushi\";\n:env\n:reset\n:env\n:tokens a;\n:ast a * -b\n:ast print 1; {}\n:bogus\n:quit\n1\n"
+                .as_bytes(),
+        );
+
+        interpreter.run_prompt().unwrap();
+
+        assert_eq!(
+            output.contents(),
+            ">> function\n>> >> >> a = sushi\nb = 2\n>> >> >> \
+             Token { token_type: Identifier, lexeme: \"a\", line_number: 1 }\n\
+             Token { token_type: Semicolon, lexeme: \";\", line_number: 1 }\n>> \
+             (* a (- b))\n>> (print 1)\n(block)\n>> >> "
+        );
+        assert_eq!(
+            diagnostics.contents(),
+            "Unknown command ':bogus'. Type :help for a list of commands.\n"
+        );
     }
 }
@@ -0,0 +1,1143 @@
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+use serde_json::{json, Value};
+
+use crate::ast::{
+    AssignOperator, BitwiseOperator, Catch, Class, ComparisonOperator, Depth, EqualityOperator,
+    Fixity, Function, Imports, IncrementOperator, Literal, LogicalOperator, Node, ProductOperator,
+    ShiftOperator, Statement, SumOperator, UnaryOperator,
+};
+use crate::cst;
+use crate::lint::Lint;
+use crate::scanner::Scanner;
+use crate::symbol::Symbol;
+use crate::token::TokenType;
+
+/// Writes the tokens of the source as a JSON array of objects like
+/// `{"type": "Identifier", "lexeme": "a", "line": 1, "span": {"start": 4, "end": 5}}`, where the
+/// span is the byte range of the token in the source. Whitespace and comments are left out.
+pub fn tokens_to_json(source_code: &str) -> Result<String, crate::Error> {
+    let tokens = Scanner::with_trivia()
+        .scan(source_code)
+        .map_err(crate::Error::Scan)?;
+
+    let mut offset = 0;
+    let mut array = Vec::new();
+    for token in tokens {
+        // The lexeme of a string leaves out its quotes.
+        let length = match token.token_type {
+            TokenType::String => token.lexeme.len() + 2,
+            _ => token.lexeme.len(),
+        };
+        if !token.token_type.is_trivia() {
+            array.push(json!({
+                "type": format!("{:?}", token.token_type),
+                "lexeme": token.lexeme,
+                "line": token.line_number,
+                "span": span(offset..offset + length),
+            }));
+        }
+        offset += length;
+    }
+    Ok(to_string(&Value::Array(array)))
+}
+
+/// Writes the statements of a program as a JSON array. Each statement and node is an object with
+/// its `kind`, named like the variants of `Statement` and `Node` (with `Literal` for literals),
+/// its operands, the line it's reported at if it has one, and the byte range of its source as its
+/// `span`. Omitted parts, like a `var` without an initializer, are `null`. JSON has no numbers
+/// that aren't finite, so a literal too large for a number is written as `{"number": "Infinity"}`.
+pub fn ast_to_json(source_code: &str) -> Result<String, crate::Error> {
+    let root = cst::parse(source_code)?;
+    let (statements, spans) = cst::lower::program_with_spans(&root);
+    let mut writer = AstWriter {
+        spans: spans.into_iter(),
+    };
+    let array = statements
+        .iter()
+        .map(|statement| writer.statement(statement))
+        .collect();
+    Ok(to_string(&Value::Array(array)))
+}
+
+/// Writes lints as a JSON array of objects like
+/// `{"file": "a.lox", "line": 2, "rule": "empty-block", "severity": "info", "message": "..."}`,
+/// each with the name of the script it was found in.
+pub fn lints_to_json<'a>(lints: impl IntoIterator<Item = (&'a str, &'a Lint)>) -> String {
+    let array = lints
+        .into_iter()
+        .map(|(file, lint)| {
+            json!({
+                "file": file,
+                "line": lint.line_number,
+                "rule": lint.rule.id(),
+                "severity": lint.rule.severity().as_str(),
+                "message": lint.message,
+            })
+        })
+        .collect();
+    to_string(&Value::Array(array))
+}
+
+/// Reads back the statements written by `ast_to_json`. Spans are ignored, so the JSON may also be
+/// written by hand without them.
+pub fn program_from_json(json: &str) -> Result<Vec<Statement>, Error> {
+    let value: Value = serde_json::from_str(json)?;
+    statements(&value)
+}
+
+/// Reads back a single node in the format written by `ast_to_json`.
+pub fn node_from_json(json: &str) -> Result<Node, Error> {
+    let value: Value = serde_json::from_str(json)?;
+    node(&value)
+}
+
+/// An error reading a syntax tree from JSON, either because it isn't valid JSON or because it
+/// doesn't describe a statement or node.
+#[derive(Debug)]
+pub struct Error {
+    pub message: String,
+}
+
+impl Error {
+    fn new(message: &str) -> Error {
+        Error {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid syntax tree: {}", self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Error {
+        Error::new(&error.to_string())
+    }
+}
+
+fn to_string(value: &Value) -> String {
+    serde_json::to_string_pretty(value).expect("JSON values can always be written")
+}
+
+fn span(range: Range<usize>) -> Value {
+    json!({ "start": range.start, "end": range.end })
+}
+
+// The spans come in the order the lowering built the statements and nodes, each one after its
+// children, so they are taken in the same order here.
+struct AstWriter {
+    spans: std::vec::IntoIter<Range<usize>>,
+}
+
+impl AstWriter {
+    fn spanned(&mut self, mut value: Value) -> Value {
+        let range = self
+            .spans
+            .next()
+            .expect("every statement and node has a span");
+        value["span"] = span(range);
+        value
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Value {
+        let value = match statement {
+            Statement::Expression(expression, line_number) => json!({
+                "kind": "Expression",
+                "expression": self.node(expression),
+                "line": line_number,
+            }),
+            Statement::Print(expression, line_number) => json!({
+                "kind": "Print",
+                "expression": self.node(expression),
+                "line": line_number,
+            }),
+            Statement::Var(name, annotation, initializer, line_number) => json!({
+                "kind": "Var",
+                "name": name.as_str(),
+                "type": annotation.as_ref().map(Symbol::as_str),
+                "initializer": initializer.as_ref().map(|initializer| self.node(initializer)),
+                "line": line_number,
+            }),
+            Statement::Block(statements) => json!({
+                "kind": "Block",
+                "statements": self.statements(statements),
+            }),
+            Statement::If(condition, then_branch, else_branch) => json!({
+                "kind": "If",
+                "condition": self.node(condition),
+                "then_branch": self.statement(then_branch),
+                "else_branch": else_branch.as_ref().map(|else_branch| self.statement(else_branch)),
+            }),
+            Statement::While(condition, body) => json!({
+                "kind": "While",
+                "condition": self.node(condition),
+                "body": self.statement(body),
+            }),
+            Statement::For(initializer, condition, increment, body) => json!({
+                "kind": "For",
+                "initializer": initializer.as_ref().map(|initializer| self.statement(initializer)),
+                "condition": condition.as_ref().map(|condition| self.node(condition)),
+                "increment": increment.as_ref().map(|increment| self.node(increment)),
+                "body": self.statement(body),
+            }),
+            Statement::Function(function) => self.function(function),
+            Statement::Class(class) => json!({
+                "kind": "Class",
+                "name": class.name.as_str(),
+                "superclass": class.superclass.as_ref().map(|superclass| self.node(superclass)),
+                "methods": class
+                    .methods
+                    .iter()
+                    .map(|method| {
+                        let method = self.function(method);
+                        self.spanned(method)
+                    })
+                    .collect::<Vec<_>>(),
+                "line": class.line_number,
+            }),
+            Statement::Return(value, line_number) => json!({
+                "kind": "Return",
+                "value": value.as_ref().map(|value| self.node(value)),
+                "line": line_number,
+            }),
+            Statement::Import(path, Imports::Module(name), line_number) => json!({
+                "kind": "Import",
+                "path": path.as_str(),
+                "module": name.as_str(),
+                "line": line_number,
+            }),
+            Statement::Import(path, Imports::Names(names), line_number) => json!({
+                "kind": "Import",
+                "path": path.as_str(),
+                "names": names.iter().map(Symbol::as_str).collect::<Vec<_>>(),
+                "line": line_number,
+            }),
+            Statement::Export(declaration) => json!({
+                "kind": "Export",
+                "declaration": self.statement(declaration),
+            }),
+            Statement::Try(body, catch, finally) => json!({
+                "kind": "Try",
+                "body": self.statement(body),
+                "catch": catch.as_ref().map(|catch| json!({
+                    "name": catch.name.as_str(),
+                    "body": self.statement(&catch.body),
+                    "line": catch.line_number,
+                })),
+                "finally": finally.as_ref().map(|finally| self.statement(finally)),
+            }),
+            Statement::Throw(value, line_number) => json!({
+                "kind": "Throw",
+                "value": self.node(value),
+                "line": line_number,
+            }),
+            Statement::Labelled(label, body) => json!({
+                "kind": "Labelled",
+                "label": label.as_str(),
+                "body": self.statement(body),
+            }),
+            Statement::Break(label, line_number) => json!({
+                "kind": "Break",
+                "label": label.as_ref().map(Symbol::as_str),
+                "line": line_number,
+            }),
+            Statement::Continue(label, line_number) => json!({
+                "kind": "Continue",
+                "label": label.as_ref().map(Symbol::as_str),
+                "line": line_number,
+            }),
+        };
+        self.spanned(value)
+    }
+
+    fn function(&mut self, function: &Function) -> Value {
+        json!({
+            "kind": "Function",
+            "name": function.name.as_str(),
+            "parameters": function
+                .parameters
+                .iter()
+                .map(|parameter| parameter.as_str())
+                .collect::<Vec<_>>(),
+            "parameter_types": function
+                .parameter_types
+                .iter()
+                .map(|annotation| annotation.as_ref().map(Symbol::as_str))
+                .collect::<Vec<_>>(),
+            "return_type": function.return_type.as_ref().map(Symbol::as_str),
+            "body": self.statements(&function.body),
+            "line": function.line_number,
+        })
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> Vec<Value> {
+        statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .collect()
+    }
+
+    fn node(&mut self, node: &Node) -> Value {
+        let value = match node {
+            Node::Expression(expression) => json!({
+                "kind": "Expression",
+                "expression": self.node(expression),
+            }),
+            Node::Grouping(expression) => json!({
+                "kind": "Grouping",
+                "expression": self.node(expression),
+            }),
+            Node::Series(left, right) => json!({
+                "kind": "Series",
+                "left": self.node(left),
+                "right": self.node(right),
+            }),
+            Node::Assign(name, value, _, line_number) => json!({
+                "kind": "Assign",
+                "name": name.as_str(),
+                "value": self.node(value),
+                "line": line_number,
+            }),
+            Node::CompoundAssign(operator, target, value, line_number) => json!({
+                "kind": "CompoundAssign",
+                "operator": operator.as_str(),
+                "target": self.node(target),
+                "value": self.node(value),
+                "line": line_number,
+            }),
+            Node::Increment(operator, fixity, operand, line_number) => json!({
+                "kind": "Increment",
+                "operator": operator.as_str(),
+                "fixity": match fixity {
+                    Fixity::Prefix => "prefix",
+                    Fixity::Postfix => "postfix",
+                },
+                "operand": self.node(operand),
+                "line": line_number,
+            }),
+            Node::Conditional(condition, then_value, else_value) => json!({
+                "kind": "Conditional",
+                "condition": self.node(condition),
+                "then_value": self.node(then_value),
+                "else_value": self.node(else_value),
+            }),
+            Node::Logical(operator, left, right) => {
+                self.binary("Logical", operator.as_str(), left, right)
+            }
+            Node::Bitwise(operator, left, right) => {
+                self.binary("Bitwise", operator.as_str(), left, right)
+            }
+            Node::Equality(operator, left, right) => {
+                self.binary("Equality", operator.as_str(), left, right)
+            }
+            Node::Comparison(operator, left, right) => {
+                self.binary("Comparison", operator.as_str(), left, right)
+            }
+            Node::Shift(operator, left, right) => {
+                self.binary("Shift", operator.as_str(), left, right)
+            }
+            Node::Sum(operator, left, right) => self.binary("Sum", operator.as_str(), left, right),
+            Node::Product(operator, left, right) => {
+                self.binary("Product", operator.as_str(), left, right)
+            }
+            Node::Power(base, exponent) => json!({
+                "kind": "Power",
+                "base": self.node(base),
+                "exponent": self.node(exponent),
+            }),
+            Node::Unary(operator, operand) => json!({
+                "kind": "Unary",
+                "operator": operator.as_str(),
+                "operand": self.node(operand),
+            }),
+            Node::Call(callee, arguments, line_number) => json!({
+                "kind": "Call",
+                "callee": self.node(callee),
+                "arguments": arguments
+                    .iter()
+                    .map(|argument| self.node(argument))
+                    .collect::<Vec<_>>(),
+                "line": line_number,
+            }),
+            Node::Get(object, name, line_number) => json!({
+                "kind": "Get",
+                "object": self.node(object),
+                "name": name.as_str(),
+                "line": line_number,
+            }),
+            Node::Set(object, name, value, line_number) => json!({
+                "kind": "Set",
+                "object": self.node(object),
+                "name": name.as_str(),
+                "value": self.node(value),
+                "line": line_number,
+            }),
+            Node::List(elements) => json!({
+                "kind": "List",
+                "elements": elements
+                    .iter()
+                    .map(|element| self.node(element))
+                    .collect::<Vec<_>>(),
+            }),
+            Node::Map(entries, line_number) => json!({
+                "kind": "Map",
+                "entries": entries
+                    .iter()
+                    .map(|(key, value)| json!({"key": self.node(key), "value": self.node(value)}))
+                    .collect::<Vec<_>>(),
+                "line": line_number,
+            }),
+            Node::Index(object, index, line_number) => json!({
+                "kind": "Index",
+                "object": self.node(object),
+                "index": self.node(index),
+                "line": line_number,
+            }),
+            Node::SetIndex(object, index, value, line_number) => json!({
+                "kind": "SetIndex",
+                "object": self.node(object),
+                "index": self.node(index),
+                "value": self.node(value),
+                "line": line_number,
+            }),
+            Node::Slice(object, start, end, line_number) => json!({
+                "kind": "Slice",
+                "object": self.node(object),
+                "start": start.as_ref().map(|start| self.node(start)),
+                "end": end.as_ref().map(|end| self.node(end)),
+                "line": line_number,
+            }),
+            Node::This(_, line_number) => json!({
+                "kind": "This",
+                "line": line_number,
+            }),
+            Node::Super(method, _, line_number) => json!({
+                "kind": "Super",
+                "method": method.as_str(),
+                "line": line_number,
+            }),
+            Node::Variable(name, _, line_number) => json!({
+                "kind": "Variable",
+                "name": name.as_str(),
+                "line": line_number,
+            }),
+            Node::Primary(literal) => json!({
+                "kind": "Literal",
+                "value": match literal {
+                    Literal::Nil => Value::Null,
+                    Literal::True => Value::Bool(true),
+                    Literal::False => Value::Bool(false),
+                    Literal::Number(number) if number.is_finite() => json!(number),
+                    Literal::Number(number) => json!({ "number": non_finite_name(*number) }),
+                    Literal::String(string) => json!(string.as_str()),
+                },
+            }),
+        };
+        self.spanned(value)
+    }
+
+    fn binary(&mut self, kind: &str, operator: &str, left: &Node, right: &Node) -> Value {
+        json!({
+            "kind": kind,
+            "operator": operator,
+            "left": self.node(left),
+            "right": self.node(right),
+        })
+    }
+}
+
+fn statements(value: &Value) -> Result<Vec<Statement>, Error> {
+    value
+        .as_array()
+        .ok_or_else(|| Error::new("Expected an array of statements."))?
+        .iter()
+        .map(statement)
+        .collect()
+}
+
+fn function(value: &Value) -> Result<Function, Error> {
+    let parameters = field(value, "parameters")?
+        .as_array()
+        .ok_or_else(|| Error::new("Expected 'parameters' to be an array."))?
+        .iter()
+        .map(|parameter| {
+            parameter
+                .as_str()
+                .map(Symbol::intern)
+                .ok_or_else(|| Error::new("Expected parameters to be strings."))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // Annotations may be left out altogether, as they are when none are written.
+    let parameter_types = optional(value, "parameter_types", |types| {
+        types
+            .as_array()
+            .ok_or_else(|| Error::new("Expected 'parameter_types' to be an array."))?
+            .iter()
+            .map(|annotation| match annotation {
+                Value::Null => Ok(None),
+                annotation => type_name(annotation).map(Some),
+            })
+            .collect()
+    })?
+    .unwrap_or_else(|| vec![None; parameters.len()]);
+    if parameter_types.len() != parameters.len() {
+        return Err(Error::new("Expected a type or null for each parameter."));
+    }
+    Ok(Function {
+        name: symbol(value, "name")?,
+        parameters,
+        parameter_types,
+        return_type: optional(value, "return_type", type_name)?,
+        body: statements(field(value, "body")?)?,
+        line_number: line(value)?,
+    })
+}
+
+fn statement(value: &Value) -> Result<Statement, Error> {
+    let statement = match kind(value)? {
+        "Expression" => Statement::Expression(node(field(value, "expression")?)?, line(value)?),
+        "Print" => Statement::Print(node(field(value, "expression")?)?, line(value)?),
+        "Var" => Statement::Var(
+            symbol(value, "name")?,
+            optional(value, "type", type_name)?,
+            optional(value, "initializer", node)?,
+            line(value)?,
+        ),
+        "Block" => Statement::Block(statements(field(value, "statements")?)?),
+        "If" => Statement::If(
+            node(field(value, "condition")?)?,
+            Box::new(statement(field(value, "then_branch")?)?),
+            optional(value, "else_branch", statement)?.map(Box::new),
+        ),
+        "While" => Statement::While(
+            node(field(value, "condition")?)?,
+            Box::new(statement(field(value, "body")?)?),
+        ),
+        "For" => Statement::For(
+            optional(value, "initializer", statement)?.map(Box::new),
+            optional(value, "condition", node)?,
+            optional(value, "increment", node)?,
+            Box::new(statement(field(value, "body")?)?),
+        ),
+        "Function" => Statement::Function(Rc::new(function(value)?)),
+        "Class" => {
+            let methods = field(value, "methods")?
+                .as_array()
+                .ok_or_else(|| Error::new("Expected 'methods' to be an array."))?
+                .iter()
+                .map(|method| function(method).map(Rc::new))
+                .collect::<Result<_, _>>()?;
+            Statement::Class(Class {
+                name: symbol(value, "name")?,
+                superclass: optional(value, "superclass", node)?,
+                methods,
+                line_number: line(value)?,
+            })
+        }
+        "Return" => Statement::Return(optional(value, "value", node)?, line(value)?),
+        // An import has either the name of the module or the names imported from it.
+        "Import" => {
+            let imports = match value.get("names") {
+                Some(names) => Imports::Names(
+                    names
+                        .as_array()
+                        .ok_or_else(|| Error::new("Expected 'names' to be an array."))?
+                        .iter()
+                        .map(|name| {
+                            name.as_str()
+                                .map(Symbol::intern)
+                                .ok_or_else(|| Error::new("Expected names to be strings."))
+                        })
+                        .collect::<Result<_, _>>()?,
+                ),
+                None => Imports::Module(symbol(value, "module")?),
+            };
+            Statement::Import(symbol(value, "path")?, imports, line(value)?)
+        }
+        "Export" => match statement(field(value, "declaration")?)? {
+            declaration @ (Statement::Var(..) | Statement::Function(_) | Statement::Class(_)) => {
+                Statement::Export(Box::new(declaration))
+            }
+            _ => return Err(Error::new("Only declarations can be exported.")),
+        },
+        "Try" => Statement::Try(
+            block(value, "body")?,
+            optional(value, "catch", catch)?,
+            optional(value, "finally", |finally| match statement(finally)? {
+                finally @ Statement::Block(_) => Ok(Box::new(finally)),
+                _ => Err(Error::new("Expected 'finally' to be a block.")),
+            })?,
+        ),
+        "Throw" => Statement::Throw(node(field(value, "value")?)?, line(value)?),
+        "Labelled" => match statement(field(value, "body")?)? {
+            body @ (Statement::While(..) | Statement::For(..)) => {
+                Statement::Labelled(symbol(value, "label")?, Box::new(body))
+            }
+            _ => return Err(Error::new("Only loops can be labelled.")),
+        },
+        "Break" => Statement::Break(optional(value, "label", label)?, line(value)?),
+        "Continue" => Statement::Continue(optional(value, "label", label)?, line(value)?),
+        kind => return Err(Error::new(&format!("Unknown statement kind '{}'.", kind))),
+    };
+    Ok(statement)
+}
+
+fn catch(value: &Value) -> Result<Catch, Error> {
+    Ok(Catch {
+        name: symbol(value, "name")?,
+        body: block(value, "body")?,
+        line_number: line(value)?,
+    })
+}
+
+fn node(value: &Value) -> Result<Node, Error> {
+    let node = match kind(value)? {
+        "Expression" => Node::Expression(boxed_node(value, "expression")?),
+        "Grouping" => Node::Grouping(boxed_node(value, "expression")?),
+        "Series" => Node::Series(boxed_node(value, "left")?, boxed_node(value, "right")?),
+        "Assign" => Node::Assign(
+            symbol(value, "name")?,
+            boxed_node(value, "value")?,
+            Depth::default(),
+            line(value)?,
+        ),
+        "CompoundAssign" => Node::CompoundAssign(
+            operator(
+                value,
+                [
+                    AssignOperator::PlusEqual,
+                    AssignOperator::MinusEqual,
+                    AssignOperator::StarEqual,
+                    AssignOperator::SlashEqual,
+                    AssignOperator::PercentEqual,
+                    AssignOperator::StarStarEqual,
+                    AssignOperator::TildeSlashEqual,
+                    AssignOperator::AmpersandEqual,
+                    AssignOperator::PipeEqual,
+                    AssignOperator::CaretEqual,
+                    AssignOperator::LessLessEqual,
+                    AssignOperator::GreaterGreaterEqual,
+                ],
+                AssignOperator::as_str,
+            )?,
+            target(value, "target")?,
+            boxed_node(value, "value")?,
+            line(value)?,
+        ),
+        "Increment" => Node::Increment(
+            operator(
+                value,
+                [IncrementOperator::PlusPlus, IncrementOperator::MinusMinus],
+                IncrementOperator::as_str,
+            )?,
+            match string(value, "fixity")? {
+                "prefix" => Fixity::Prefix,
+                "postfix" => Fixity::Postfix,
+                fixity => return Err(Error::new(&format!("Unknown fixity '{}'.", fixity))),
+            },
+            target(value, "operand")?,
+            line(value)?,
+        ),
+        "Conditional" => Node::Conditional(
+            boxed_node(value, "condition")?,
+            boxed_node(value, "then_value")?,
+            boxed_node(value, "else_value")?,
+        ),
+        "Logical" => Node::Logical(
+            operator(
+                value,
+                [LogicalOperator::And, LogicalOperator::Or],
+                LogicalOperator::as_str,
+            )?,
+            boxed_node(value, "left")?,
+            boxed_node(value, "right")?,
+        ),
+        "Bitwise" => Node::Bitwise(
+            operator(
+                value,
+                [
+                    BitwiseOperator::Ampersand,
+                    BitwiseOperator::Pipe,
+                    BitwiseOperator::Caret,
+                ],
+                BitwiseOperator::as_str,
+            )?,
+            boxed_node(value, "left")?,
+            boxed_node(value, "right")?,
+        ),
+        "Equality" => Node::Equality(
+            operator(
+                value,
+                [EqualityOperator::Equal, EqualityOperator::NotEqual],
+                EqualityOperator::as_str,
+            )?,
+            boxed_node(value, "left")?,
+            boxed_node(value, "right")?,
+        ),
+        "Comparison" => Node::Comparison(
+            operator(
+                value,
+                [
+                    ComparisonOperator::Greater,
+                    ComparisonOperator::GreaterEqual,
+                    ComparisonOperator::Less,
+                    ComparisonOperator::LessEqual,
+                ],
+                ComparisonOperator::as_str,
+            )?,
+            boxed_node(value, "left")?,
+            boxed_node(value, "right")?,
+        ),
+        "Shift" => Node::Shift(
+            operator(
+                value,
+                [ShiftOperator::LessLess, ShiftOperator::GreaterGreater],
+                ShiftOperator::as_str,
+            )?,
+            boxed_node(value, "left")?,
+            boxed_node(value, "right")?,
+        ),
+        "Sum" => Node::Sum(
+            operator(
+                value,
+                [SumOperator::Plus, SumOperator::Minus],
+                SumOperator::as_str,
+            )?,
+            boxed_node(value, "left")?,
+            boxed_node(value, "right")?,
+        ),
+        "Product" => Node::Product(
+            operator(
+                value,
+                [
+                    ProductOperator::Star,
+                    ProductOperator::Slash,
+                    ProductOperator::Percent,
+                    ProductOperator::TildeSlash,
+                ],
+                ProductOperator::as_str,
+            )?,
+            boxed_node(value, "left")?,
+            boxed_node(value, "right")?,
+        ),
+        "Power" => Node::Power(boxed_node(value, "base")?, boxed_node(value, "exponent")?),
+        "Unary" => Node::Unary(
+            operator(
+                value,
+                [
+                    UnaryOperator::Bang,
+                    UnaryOperator::Minus,
+                    UnaryOperator::Tilde,
+                ],
+                UnaryOperator::as_str,
+            )?,
+            boxed_node(value, "operand")?,
+        ),
+        "Call" => Node::Call(
+            boxed_node(value, "callee")?,
+            field(value, "arguments")?
+                .as_array()
+                .ok_or_else(|| Error::new("Expected 'arguments' to be an array."))?
+                .iter()
+                .map(node)
+                .collect::<Result<_, _>>()?,
+            line(value)?,
+        ),
+        "Get" => Node::Get(
+            boxed_node(value, "object")?,
+            symbol(value, "name")?,
+            line(value)?,
+        ),
+        "Set" => Node::Set(
+            boxed_node(value, "object")?,
+            symbol(value, "name")?,
+            boxed_node(value, "value")?,
+            line(value)?,
+        ),
+        "List" => Node::List(
+            field(value, "elements")?
+                .as_array()
+                .ok_or_else(|| Error::new("Expected 'elements' to be an array."))?
+                .iter()
+                .map(node)
+                .collect::<Result<_, _>>()?,
+        ),
+        "Map" => Node::Map(
+            field(value, "entries")?
+                .as_array()
+                .ok_or_else(|| Error::new("Expected 'entries' to be an array."))?
+                .iter()
+                .map(|entry| Ok((node(field(entry, "key")?)?, node(field(entry, "value")?)?)))
+                .collect::<Result<_, Error>>()?,
+            line(value)?,
+        ),
+        "Index" => Node::Index(
+            boxed_node(value, "object")?,
+            boxed_node(value, "index")?,
+            line(value)?,
+        ),
+        "SetIndex" => Node::SetIndex(
+            boxed_node(value, "object")?,
+            boxed_node(value, "index")?,
+            boxed_node(value, "value")?,
+            line(value)?,
+        ),
+        "Slice" => Node::Slice(
+            boxed_node(value, "object")?,
+            optional(value, "start", node)?.map(Box::new),
+            optional(value, "end", node)?.map(Box::new),
+            line(value)?,
+        ),
+        "This" => Node::This(Depth::default(), line(value)?),
+        "Super" => Node::Super(symbol(value, "method")?, Depth::default(), line(value)?),
+        "Variable" => Node::Variable(symbol(value, "name")?, Depth::default(), line(value)?),
+        "Literal" => Node::Primary(match field(value, "value")? {
+            Value::Null => Literal::Nil,
+            Value::Bool(true) => Literal::True,
+            Value::Bool(false) => Literal::False,
+            Value::Number(number) => Literal::Number(number.as_f64().unwrap_or(f64::NAN)),
+            Value::String(string) => Literal::String(Symbol::intern(string)),
+            Value::Object(_) => Literal::Number(non_finite(value)?),
+            _ => {
+                return Err(Error::new(
+                    "Expected a literal to be null, a boolean, a number or a string.",
+                ))
+            }
+        }),
+        kind => return Err(Error::new(&format!("Unknown node kind '{}'.", kind))),
+    };
+    Ok(node)
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value, Error> {
+    value
+        .get(name)
+        .ok_or_else(|| Error::new(&format!("Missing field '{}'.", name)))
+}
+
+fn string<'a>(value: &'a Value, name: &str) -> Result<&'a str, Error> {
+    field(value, name)?
+        .as_str()
+        .ok_or_else(|| Error::new(&format!("Expected '{}' to be a string.", name)))
+}
+
+fn kind(value: &Value) -> Result<&str, Error> {
+    string(value, "kind")
+}
+
+fn symbol(value: &Value, name: &str) -> Result<Symbol, Error> {
+    string(value, name).map(Symbol::intern)
+}
+
+fn type_name(value: &Value) -> Result<Symbol, Error> {
+    value
+        .as_str()
+        .map(Symbol::intern)
+        .ok_or_else(|| Error::new("Expected type names to be strings."))
+}
+
+fn label(value: &Value) -> Result<Symbol, Error> {
+    value
+        .as_str()
+        .map(Symbol::intern)
+        .ok_or_else(|| Error::new("Expected labels to be strings."))
+}
+
+fn line(value: &Value) -> Result<i32, Error> {
+    field(value, "line")?
+        .as_i64()
+        .and_then(|line| i32::try_from(line).ok())
+        .ok_or_else(|| Error::new("Expected 'line' to be an integer."))
+}
+
+fn non_finite_name(number: f64) -> &'static str {
+    if number.is_nan() {
+        "NaN"
+    } else if number > 0.0 {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+// A number that isn't finite, from a literal's value like `{"number": "Infinity"}`.
+fn non_finite(literal: &Value) -> Result<f64, Error> {
+    match string(field(literal, "value")?, "number")? {
+        "Infinity" => Ok(f64::INFINITY),
+        "-Infinity" => Ok(f64::NEG_INFINITY),
+        "NaN" => Ok(f64::NAN),
+        _ => Err(Error::new(
+            "Expected 'number' to be 'Infinity', '-Infinity' or 'NaN'.",
+        )),
+    }
+}
+
+fn boxed_node(value: &Value, name: &str) -> Result<Box<Node>, Error> {
+    node(field(value, name)?).map(Box::new)
+}
+
+// A node that can be assigned to, as the target of a compound assignment or increment must be.
+fn target(value: &Value, name: &str) -> Result<Box<Node>, Error> {
+    match node(field(value, name)?)? {
+        target @ (Node::Variable(..) | Node::Get(..) | Node::Index(..)) => Ok(Box::new(target)),
+        _ => Err(Error::new(&format!(
+            "Expected '{}' to be a variable, property or element.",
+            name
+        ))),
+    }
+}
+
+// A statement that must be a block, like the parts of a try statement.
+fn block(value: &Value, name: &str) -> Result<Box<Statement>, Error> {
+    match statement(field(value, name)?)? {
+        block @ Statement::Block(_) => Ok(Box::new(block)),
+        _ => Err(Error::new(&format!("Expected '{}' to be a block.", name))),
+    }
+}
+
+// A part that may be omitted, either as `null` or by leaving the field out.
+fn optional<T>(
+    value: &Value,
+    name: &str,
+    read: fn(&Value) -> Result<T, Error>,
+) -> Result<Option<T>, Error> {
+    match value.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(part) => read(part).map(Some),
+    }
+}
+
+// Finds the operator written in the node among the operators of its kind.
+fn operator<T, const N: usize>(
+    value: &Value,
+    operators: [T; N],
+    as_str: fn(&T) -> &'static str,
+) -> Result<T, Error> {
+    let text = string(value, "operator")?;
+    operators
+        .into_iter()
+        .find(|operator| as_str(operator) == text)
+        .ok_or_else(|| Error::new(&format!("Unknown operator '{}'.", text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::ast::{Literal, Node};
+    use crate::cst;
+    use crate::lint::lint;
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::scanner::Scanner;
+
+    use super::{ast_to_json, lints_to_json, node_from_json, program_from_json, tokens_to_json};
+
+    // The kind and source of every node with a span, children first.
+    fn spanned_sources(value: &Value, source_code: &str, output: &mut Vec<(String, String)>) {
+        match value {
+            Value::Array(values) => {
+                for value in values {
+                    spanned_sources(value, source_code, output);
+                }
+            }
+            Value::Object(object) => {
+                for (name, value) in object {
+                    if name != "span" {
+                        spanned_sources(value, source_code, output);
+                    }
+                }
+                if let Some(span) = object.get("span") {
+                    let start = span["start"].as_u64().unwrap() as usize;
+                    let end = span["end"].as_u64().unwrap() as usize;
+                    output.push((
+                        object["kind"].as_str().unwrap().to_string(),
+                        source_code[start..end].to_string(),
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn tokens_are_written_with_their_byte_ranges() {
+        let json = tokens_to_json("print \"é\"; // c\nx").unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!([
+                {"type": "Print", "lexeme": "print", "line": 1, "span": {"start": 0, "end": 5}},
+                {"type": "String", "lexeme": "é", "line": 1, "span": {"start": 6, "end": 10}},
+                {"type": "Semicolon", "lexeme": ";", "line": 1, "span": {"start": 10, "end": 11}},
+                {"type": "Identifier", "lexeme": "x", "line": 2, "span": {"start": 17, "end": 18}},
+            ])
+        );
+    }
+
+    #[test]
+    fn lints_are_written_with_their_file() {
+        let lints = lint("{}").unwrap();
+        let json = lints_to_json(lints.iter().map(|lint| ("a.lox", lint)));
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!([{
+                "file": "a.lox",
+                "line": 1,
+                "rule": "empty-block",
+                "severity": "info",
+                "message": "Empty block.",
+            }])
+        );
+    }
+
+    #[test]
+    fn statements_and_nodes_are_written_with_their_source_ranges() {
+        let source_code = "if (a) f(1, -b); // call\nelse { var c; }";
+        let json = ast_to_json(source_code).unwrap();
+        let mut output = Vec::new();
+        spanned_sources(
+            &serde_json::from_str(&json).unwrap(),
+            source_code,
+            &mut output,
+        );
+
+        let expected_output = [
+            ("Variable", "a"),
+            ("Expression", "a"),
+            ("Variable", "f"),
+            ("Literal", "1"),
+            ("Variable", "b"),
+            ("Unary", "-b"),
+            ("Call", "f(1, -b)"),
+            ("Expression", "f(1, -b)"),
+            ("Expression", "f(1, -b);"),
+            ("Var", "var c;"),
+            ("Block", "{ var c; }"),
+            ("If", "if (a) f(1, -b); // call\nelse { var c; }"),
+        ];
+        assert_eq!(
+            output,
+            expected_output.map(|(kind, source)| (kind.to_string(), source.to_string()))
+        );
+    }
+
+    #[test]
+    fn written_trees_read_back_into_the_same_ast() {
+        for program in [
+            "var a = (1 + 2) * -3; print a, \"é\";",
+            "fun f(x, y) { return x(y)(nil); } for (var i = 0; i < 3; i = i + 1) f(i != true);",
+            "while (!a and b or c >= 1) { if (d) print d; else e = f / 2; } for (;;) {}",
+            "class A < B { init(x) { this.x = x; } get() { return super.get(this.x).y; } } class C {}",
+            "var a: Number; fun f(b: String, c): Bool { var d: Any = c; }",
+            "var a = [1, [], [b]]; a[0] = a[-1][0]; print a[1:], a[:2], a[:];",
+            "var m = {\"a\": {}, 1: [nil]}; { m[true] = m[\"a\"]; }",
+            "import \"a.lox\" as a; import { b, c } from \"c.lox\"; export var d = a.d; export class E {}",
+            "try { throw 1; } catch (e) { print e; } finally {} try {} finally { print 2; }",
+            "a: while (true) for (;;) { break a; continue; }",
+            "a.b -= c ? d ** 2 % e : f ~/ 2; print ++g[0], h--;",
+            "print ~a & b | c ^ d << 1 >> 2;",
+        ] {
+            let json = ast_to_json(program).unwrap();
+            let expected_output = cst::lower::program(&cst::parse(program).unwrap());
+
+            assert_eq!(program_from_json(&json).unwrap(), expected_output);
+        }
+    }
+
+    #[test]
+    fn numbers_too_large_for_json_read_back_into_the_same_ast() {
+        let program = format!("print {};", "9".repeat(400));
+        let json = ast_to_json(&program).unwrap();
+        let expected_output = cst::lower::program(&cst::parse(&program).unwrap());
+
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let literal = &value[0]["expression"]["expression"];
+        assert_eq!(literal["value"], json!({"number": "Infinity"}));
+        assert_eq!(program_from_json(&json).unwrap(), expected_output);
+        assert_eq!(
+            node_from_json(r#"{"kind": "Literal", "value": {"number": "-Infinity"}}"#).unwrap(),
+            Node::Primary(Literal::Number(f64::NEG_INFINITY))
+        );
+    }
+
+    #[test]
+    fn nodes_can_be_read_without_spans() {
+        let json = r#"{"kind": "Expression", "expression": {"kind": "Sum", "operator": "-",
+            "left": {"kind": "Variable", "name": "a", "line": 1}, "right": {"kind": "Literal", "value": 1}}}"#;
+        let tokens = Scanner::new().scan("a - 1").unwrap();
+        let node = RecursiveDescentParser::new().parse(&tokens).unwrap();
+
+        assert_eq!(node_from_json(json).unwrap(), node);
+    }
+
+    #[test]
+    fn invalid_trees_are_reported() {
+        let test_cases = [
+            (
+                "{}",
+                "Invalid syntax tree: Expected an array of statements.",
+            ),
+            (
+                r#"[{"kind": "Loop"}]"#,
+                "Invalid syntax tree: Unknown statement kind 'Loop'.",
+            ),
+            (
+                r#"[{"kind": "Print", "line": 1, "expression": {"kind": "Unary", "operator": "+"}}]"#,
+                "Invalid syntax tree: Unknown operator '+'.",
+            ),
+            (
+                r#"[{"kind": "Var", "name": "a"}]"#,
+                "Invalid syntax tree: Missing field 'line'.",
+            ),
+            (
+                r#"[{"kind": "Expression", "line": 1, "expression": {"kind": "Increment",
+                    "operator": "++", "fixity": "prefix", "line": 1,
+                    "operand": {"kind": "Literal", "value": 1}}}]"#,
+                "Invalid syntax tree: Expected 'operand' to be a variable, property or element.",
+            ),
+            (
+                r#"[{"kind": "Expression", "line": 1, "expression": {"kind": "CompoundAssign",
+                    "operator": "+=", "line": 1, "target": {"kind": "Literal", "value": "a"},
+                    "value": {"kind": "Literal", "value": 1}}}]"#,
+                "Invalid syntax tree: Expected 'target' to be a variable, property or element.",
+            ),
+            (
+                r#"[{"kind": "Export", "declaration": {"kind": "Block", "statements": []}}]"#,
+                "Invalid syntax tree: Only declarations can be exported.",
+            ),
+            (
+                r#"[{"kind": "Try", "body": {"kind": "Break", "line": 1}}]"#,
+                "Invalid syntax tree: Expected 'body' to be a block.",
+            ),
+            (
+                r#"[{"kind": "Try", "body": {"kind": "Block", "statements": []},
+                    "finally": {"kind": "Throw", "line": 1, "value": {"kind": "Literal", "value": 1}}}]"#,
+                "Invalid syntax tree: Expected 'finally' to be a block.",
+            ),
+            (
+                r#"[{"kind": "Labelled", "label": "a", "body": {"kind": "Break", "line": 1}}]"#,
+                "Invalid syntax tree: Only loops can be labelled.",
+            ),
+            (
+                r#"[{"kind": "Print", "line": 1, "expression": {"kind": "Literal",
+                    "value": {"number": "inf"}}}]"#,
+                "Invalid syntax tree: Expected 'number' to be 'Infinity', '-Infinity' or 'NaN'.",
+            ),
+        ];
+
+        for (input, expected_output) in test_cases {
+            let error = program_from_json(input).unwrap_err();
+            assert_eq!(error.to_string(), expected_output, "{}", input);
+        }
+    }
+}
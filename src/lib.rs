@@ -0,0 +1,138 @@
+mod arithmetic;
+pub mod ast;
+mod bitwise;
+pub mod cst;
+pub mod environment;
+pub mod fold;
+pub mod formatter;
+pub mod highlight;
+pub mod interpreter;
+pub mod io;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod lint;
+mod list;
+mod map;
+mod module;
+pub mod optimizer;
+pub mod parser;
+pub mod printer;
+#[cfg(feature = "repl")]
+pub mod repl;
+pub mod resolver;
+pub mod scanner;
+mod stdlib;
+pub mod symbol;
+pub mod test_runner;
+pub mod token;
+pub mod types;
+pub mod value;
+pub mod visit;
+
+use std::fmt;
+
+pub use crate::interpreter::Interpreter;
+pub use crate::io::SharedBuffer;
+pub use crate::value::{Arity, Value};
+
+/// Evaluates a Lox expression in a fresh interpreter with the standard library loaded.
+///
+/// Use [`Interpreter::eval_str`] instead to keep globals and host functions between evaluations.
+pub fn eval_str(source_code: &str) -> Result<Value, Error> {
+    Interpreter::new().eval_str(source_code)
+}
+
+/// Any error raised while scanning, parsing, resolving or evaluating source code.
+#[derive(Debug)]
+pub enum Error {
+    Scan(scanner::Error),
+    Parse(parser::Error),
+    Resolve(resolver::Error),
+    Runtime(interpreter::Error),
+}
+
+impl Error {
+    pub fn line_number(&self) -> i32 {
+        match self {
+            Error::Scan(error) => error.line_number,
+            Error::Parse(error) => error.line_number,
+            Error::Resolve(error) => error.line_number,
+            Error::Runtime(error) => error.line_number,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Error::Scan(error) => &error.message,
+            Error::Parse(error) => &error.message,
+            Error::Resolve(error) => &error.message,
+            Error::Runtime(error) => &error.message,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}] Error: {}", self.line_number(), self.message())
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval_str, Error, Interpreter, Value};
+
+    #[test]
+    fn eval_str_returns_the_value_of_the_expression() {
+        assert_eq!(eval_str("1 + 2").unwrap(), Value::Number(3.0));
+        assert_eq!(eval_str("upper(\"sushi\")").unwrap(), Value::from("SUSHI"));
+    }
+
+    #[test]
+    fn eval_str_reports_each_kind_of_error() {
+        assert!(matches!(eval_str("\"sushi"), Err(Error::Scan(_))));
+        assert!(matches!(eval_str("1 +"), Err(Error::Parse(_))));
+        assert!(matches!(eval_str("1 + nil"), Err(Error::Runtime(_))));
+    }
+
+    #[test]
+    fn host_functions_convert_their_arguments_and_results() {
+        let mut interpreter = Interpreter::new();
+        interpreter.define_function("greet", |name: String, times: f64| {
+            format!("hello {}", name).repeat(times as usize)
+        });
+        interpreter.define_function("answer", || 42.0);
+
+        assert_eq!(
+            interpreter.eval_str("greet(\"sushi\", 2)").unwrap(),
+            Value::from("hello sushihello sushi")
+        );
+        assert_eq!(
+            interpreter.eval_str("answer()").unwrap(),
+            Value::Number(42.0)
+        );
+        assert_eq!(
+            interpreter
+                .eval_str("greet(1, 2)")
+                .unwrap_err()
+                .message(),
+            "greet() expects a string but got number."
+        );
+    }
+
+    #[test]
+    fn globals_are_shared_with_the_host() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("favorite", Value::from("sushi"));
+
+        assert_eq!(
+            interpreter.eval_str("favorite + \"!\"").unwrap(),
+            Value::from("sushi!")
+        );
+        assert_eq!(interpreter.get_global("favorite"), Some(Value::from("sushi")));
+        assert_eq!(interpreter.get_global("dessert"), None);
+    }
+}
@@ -0,0 +1,628 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::ast::{Catch, Class, EqualityOperator, Function, Imports, Literal, Node, Statement};
+use crate::interpreter::Interpreter;
+use crate::parser::recursive_descent::RecursiveDescentParser;
+use crate::parser::Parse;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::symbol::Symbol;
+use crate::token::TokenType;
+use crate::visit::{walk_node, walk_statement, Visitor};
+
+/// The checks the linter makes. Each can be turned off for a file with a comment like
+/// `// lint: disable unused-variable, empty-block`, and back on with `// lint: enable ...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A local variable that is never read. Names starting with `_` are exempt.
+    UnusedVariable,
+    /// A parameter that is never read. Names starting with `_` are exempt.
+    UnusedParameter,
+    /// Statements after a `return` in the same block.
+    UnreachableCode,
+    /// A local with the same name as a global of the program or the standard library.
+    ShadowedGlobal,
+    /// An assignment as the condition of an `if`, `while` or `for`. Wrapping it in another pair
+    /// of parentheses says it is meant.
+    AssignmentInCondition,
+    /// Comparing a value to `nil` with `==` or `!=` inside a loop.
+    NilComparisonInLoop,
+    /// A block without any statements.
+    EmptyBlock,
+    /// A lint comment naming a rule that doesn't exist.
+    UnknownRule,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 8] = [
+        Rule::UnusedVariable,
+        Rule::UnusedParameter,
+        Rule::UnreachableCode,
+        Rule::ShadowedGlobal,
+        Rule::AssignmentInCondition,
+        Rule::NilComparisonInLoop,
+        Rule::EmptyBlock,
+        Rule::UnknownRule,
+    ];
+
+    /// The name of the rule in lint comments and output.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::UnusedParameter => "unused-parameter",
+            Rule::UnreachableCode => "unreachable-code",
+            Rule::ShadowedGlobal => "shadowed-global",
+            Rule::AssignmentInCondition => "assignment-in-condition",
+            Rule::NilComparisonInLoop => "nil-comparison-in-loop",
+            Rule::EmptyBlock => "empty-block",
+            Rule::UnknownRule => "unknown-rule",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.id() == id)
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::NilComparisonInLoop | Rule::EmptyBlock => Severity::Info,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+}
+
+/// A problem found by a rule.
+#[derive(Debug, PartialEq)]
+pub struct Lint {
+    pub rule: Rule,
+    pub line_number: i32,
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.rule.severity().as_str(),
+            self.rule.id(),
+            self.message
+        )
+    }
+}
+
+/// Checks a program against the enabled rules, returning what they found in line order. The
+/// program must scan, parse and resolve, or the first error is returned instead.
+pub fn lint(source_code: &str) -> Result<Vec<Lint>, crate::Error> {
+    let mut enabled: HashSet<Rule> = Rule::ALL.into_iter().collect();
+    let mut unknown_rules = Vec::new();
+    let mut tokens = Vec::new();
+    let mut braces = Vec::new();
+    for token in Scanner::with_trivia()
+        .scan(source_code)
+        .map_err(crate::Error::Scan)?
+    {
+        match token.token_type {
+            TokenType::Comment => {
+                for id in configure(&mut enabled, token.lexeme) {
+                    unknown_rules.push(Lint {
+                        rule: Rule::UnknownRule,
+                        line_number: token.line_number,
+                        message: format!("Unknown lint rule '{}'.", id),
+                    });
+                }
+            }
+            TokenType::Whitespace => {}
+            TokenType::LeftBrace => {
+                braces.push(token.line_number);
+                tokens.push(token);
+            }
+            _ => tokens.push(token),
+        }
+    }
+
+    let statements = RecursiveDescentParser::new()
+        .parse_program(&tokens)
+        .map_err(|mut parse_errors| crate::Error::Parse(parse_errors.remove(0)))?;
+    Resolver::new()
+        .resolve(&statements)
+        .map_err(|mut resolve_errors| crate::Error::Resolve(resolve_errors.remove(0)))?;
+
+    let mut globals: HashSet<Symbol> = Interpreter::new()
+        .global_names()
+        .iter()
+        .map(|name| Symbol::intern(name))
+        .collect();
+    for statement in &statements {
+        let statement = match statement {
+            Statement::Export(declaration) => declaration,
+            statement => statement,
+        };
+        match statement {
+            Statement::Var(name, ..) => globals.insert(name.clone()),
+            Statement::Function(function) => globals.insert(function.name.clone()),
+            Statement::Class(class) => globals.insert(class.name.clone()),
+            Statement::Import(_, Imports::Module(name), _) => globals.insert(name.clone()),
+            Statement::Import(_, Imports::Names(names), _) => {
+                globals.extend(names.iter().cloned());
+                true
+            }
+            _ => false,
+        };
+    }
+
+    let mut linter = Linter {
+        scopes: Vec::new(),
+        globals,
+        braces: braces.into_iter(),
+        loop_depth: 0,
+        line_number: 1,
+        lints: Vec::new(),
+    };
+    linter.statements(&statements);
+
+    let mut lints = linter.lints;
+    lints.extend(unknown_rules);
+    lints.retain(|lint| enabled.contains(&lint.rule));
+    lints.sort_by_key(|lint| lint.line_number);
+    Ok(lints)
+}
+
+// Applies a `// lint: enable ...` or `// lint: disable ...` comment, returning the ids in it that
+// don't name a rule.
+fn configure<'a>(enabled: &mut HashSet<Rule>, comment: &'a str) -> Vec<&'a str> {
+    let Some(directive) = comment.trim_start_matches('/').trim().strip_prefix("lint:") else {
+        return Vec::new();
+    };
+    let Some((action, ids)) = directive.trim().split_once(char::is_whitespace) else {
+        return Vec::new();
+    };
+    let enable = match action {
+        "enable" => true,
+        "disable" => false,
+        _ => return Vec::new(),
+    };
+    let mut unknown_ids = Vec::new();
+    for id in ids
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|id| !id.is_empty())
+    {
+        match Rule::from_id(id) {
+            Some(rule) if enable => {
+                enabled.insert(rule);
+            }
+            Some(rule) => {
+                enabled.remove(&rule);
+            }
+            None => unknown_ids.push(id),
+        }
+    }
+    unknown_ids
+}
+
+struct Linter {
+    // The locals of each enclosing block or function, innermost last, in declaration order.
+    scopes: Vec<Vec<(Symbol, Local)>>,
+    globals: HashSet<Symbol>,
+    // The lines of the `{` tokens, which the walk meets in the same order. Blocks don't record
+    // where they are, so this is how their lints find a line.
+    braces: std::vec::IntoIter<i32>,
+    loop_depth: usize,
+    // The line of the most recent statement, node or block that knows its position, for
+    // reporting comparisons, which don't.
+    line_number: i32,
+    lints: Vec<Lint>,
+}
+
+struct Local {
+    kind: LocalKind,
+    line_number: i32,
+    read: bool,
+}
+
+#[derive(PartialEq)]
+enum LocalKind {
+    Variable,
+    Parameter,
+    // Functions, classes and caught errors, which aren't reported when unused.
+    Declaration,
+}
+
+impl Linter {
+    fn report(&mut self, rule: Rule, line_number: i32, message: &str) {
+        self.lints.push(Lint {
+            rule,
+            line_number,
+            message: message.to_string(),
+        });
+    }
+
+    fn declare(&mut self, name: &Symbol, kind: LocalKind, line_number: i32) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        scope.push((
+            name.clone(),
+            Local {
+                kind,
+                line_number,
+                read: false,
+            },
+        ));
+        if self.globals.contains(name) {
+            self.report(
+                Rule::ShadowedGlobal,
+                line_number,
+                &format!("'{}' shadows a global of the same name.", name),
+            );
+        }
+    }
+
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().expect("a scope was begun");
+        for (name, local) in scope {
+            if local.read || name.as_str().starts_with('_') {
+                continue;
+            }
+            match local.kind {
+                LocalKind::Variable => self.report(
+                    Rule::UnusedVariable,
+                    local.line_number,
+                    &format!("Local variable '{}' is never read.", name),
+                ),
+                LocalKind::Parameter => self.report(
+                    Rule::UnusedParameter,
+                    local.line_number,
+                    &format!("Parameter '{}' is never read.", name),
+                ),
+                LocalKind::Declaration => {}
+            }
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for (index, statement) in statements.iter().enumerate() {
+            self.visit_statement(statement);
+            let (keyword, line_number) = match statement {
+                Statement::Return(_, line_number) => ("return", line_number),
+                Statement::Break(_, line_number) => ("break", line_number),
+                Statement::Continue(_, line_number) => ("continue", line_number),
+                _ => continue,
+            };
+            if index + 1 < statements.len() {
+                self.report(
+                    Rule::UnreachableCode,
+                    *line_number,
+                    &format!("Code after '{}' is unreachable.", keyword),
+                );
+            }
+        }
+    }
+
+    fn brace(&mut self) -> i32 {
+        self.braces.next().unwrap_or(self.line_number)
+    }
+
+    fn function_body(&mut self, function: &Function) {
+        self.line_number = function.line_number;
+        self.brace();
+        self.scopes.push(Vec::new());
+        for parameter in &function.parameters {
+            self.declare(parameter, LocalKind::Parameter, function.line_number);
+        }
+        self.statements(&function.body);
+        self.end_scope();
+    }
+
+    fn condition(&mut self, condition: &Node) {
+        let assignment = match condition {
+            Node::Expression(expression) => expression,
+            _ => condition,
+        };
+        if let Node::Assign(.., line_number) = assignment {
+            self.report(
+                Rule::AssignmentInCondition,
+                *line_number,
+                "Assignment used as a condition. Use '==' to compare.",
+            );
+        }
+        self.visit_node(condition);
+    }
+}
+
+impl Visitor for Linter {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(_, line_number)
+            | Statement::Print(_, line_number)
+            | Statement::Return(_, line_number)
+            | Statement::Throw(_, line_number)
+            | Statement::Break(_, line_number)
+            | Statement::Continue(_, line_number) => self.line_number = *line_number,
+            _ => {}
+        }
+        walk_statement(self, statement)
+    }
+
+    fn visit_var(
+        &mut self,
+        name: &Symbol,
+        _annotation: Option<&Symbol>,
+        initializer: Option<&Node>,
+        line_number: i32,
+    ) {
+        self.line_number = line_number;
+        if let Some(initializer) = initializer {
+            self.visit_node(initializer);
+        }
+        self.declare(name, LocalKind::Variable, line_number);
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) {
+        self.line_number = self.brace();
+        if statements.is_empty() {
+            self.report(Rule::EmptyBlock, self.line_number, "Empty block.");
+        }
+        self.scopes.push(Vec::new());
+        self.statements(statements);
+        self.end_scope();
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Node,
+        then_branch: &Statement,
+        else_branch: Option<&Statement>,
+    ) {
+        self.condition(condition);
+        self.visit_statement(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.visit_statement(else_branch);
+        }
+    }
+
+    fn visit_while(&mut self, condition: &Node, body: &Statement) {
+        self.loop_depth += 1;
+        self.condition(condition);
+        self.visit_statement(body);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_for(
+        &mut self,
+        initializer: Option<&Statement>,
+        condition: Option<&Node>,
+        increment: Option<&Node>,
+        body: &Statement,
+    ) {
+        self.scopes.push(Vec::new());
+        if let Some(initializer) = initializer {
+            self.visit_statement(initializer);
+        }
+        self.loop_depth += 1;
+        if let Some(condition) = condition {
+            self.condition(condition);
+        }
+        if let Some(increment) = increment {
+            self.visit_node(increment);
+        }
+        self.visit_statement(body);
+        self.loop_depth -= 1;
+        self.end_scope();
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        self.declare(&function.name, LocalKind::Declaration, function.line_number);
+        // A loop around a function doesn't run its body repeatedly.
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        self.function_body(function);
+        self.loop_depth = loop_depth;
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        self.line_number = class.line_number;
+        self.declare(&class.name, LocalKind::Declaration, class.line_number);
+        if let Some(superclass) = &class.superclass {
+            self.visit_node(superclass);
+        }
+        self.brace();
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        for method in &class.methods {
+            self.function_body(method);
+        }
+        self.loop_depth = loop_depth;
+    }
+
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::Assign(.., line_number)
+            | Node::Call(_, _, line_number)
+            | Node::Get(_, _, line_number)
+            | Node::Set(_, _, _, line_number)
+            | Node::This(_, line_number)
+            | Node::Super(.., line_number) => self.line_number = *line_number,
+            _ => {}
+        }
+        walk_node(self, node)
+    }
+
+    fn visit_try(&mut self, body: &Statement, catch: Option<&Catch>, finally: Option<&Statement>) {
+        self.visit_statement(body);
+        if let Some(catch) = catch {
+            self.scopes.push(Vec::new());
+            self.declare(&catch.name, LocalKind::Declaration, catch.line_number);
+            self.visit_statement(&catch.body);
+            self.end_scope();
+        }
+        if let Some(finally) = finally {
+            self.visit_statement(finally);
+        }
+    }
+
+    // The brace of a selective import is skipped too.
+    fn visit_import(&mut self, _path: &Symbol, imports: &Imports, line_number: i32) {
+        self.line_number = line_number;
+        if let Imports::Names(_) = imports {
+            self.brace();
+        }
+    }
+
+    // A map's brace is skipped, so that blocks after it get their own.
+    fn visit_map(&mut self, entries: &[(Node, Node)], line_number: i32) {
+        self.line_number = line_number;
+        self.brace();
+        for (key, value) in entries {
+            self.visit_node(key);
+            self.visit_node(value);
+        }
+    }
+
+    fn visit_equality(&mut self, operator: &EqualityOperator, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+        let is_nil = |node: &Node| matches!(node, Node::Primary(Literal::Nil));
+        if self.loop_depth > 0 && (is_nil(left) || is_nil(right)) {
+            self.report(
+                Rule::NilComparisonInLoop,
+                self.line_number,
+                &format!(
+                    "Comparison with nil using '{}' in a loop.",
+                    operator.as_str()
+                ),
+            );
+        }
+    }
+
+    fn visit_variable(&mut self, name: &Symbol, line_number: i32) {
+        self.line_number = line_number;
+        let local = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|(local, _)| local == name));
+        if let Some((_, local)) = local {
+            local.read = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, Rule};
+
+    struct TestCase {
+        input: &'static str,
+        expected_output: Vec<(i32, &'static str)>,
+    }
+
+    #[test]
+    fn rules_find_their_problems() {
+        let test_cases = vec![
+            TestCase {
+                input: "var a = 1;\nfun f(b, _c) {\n  var d = 2;\n  var e;\n  e = b;\n  return d;\n}",
+                expected_output: vec![(4, "unused-variable")],
+            },
+            TestCase {
+                input: "fun f(a, b) {\n  return a;\n}\nclass C { m(x) {} }",
+                expected_output: vec![(1, "unused-parameter"), (4, "unused-parameter")],
+            },
+            TestCase {
+                input: "fun f() {\n  return 1;\n  print 2;\n}\nfun g() { return; }",
+                expected_output: vec![(2, "unreachable-code")],
+            },
+            TestCase {
+                input: "var a;\nfun f(clock) {\n  var a = clock;\n  return a;\n}",
+                expected_output: vec![(2, "shadowed-global"), (3, "shadowed-global")],
+            },
+            TestCase {
+                input: "var a;\nif (a = 1) print a;\nwhile ((a = nil)) {}",
+                expected_output: vec![(2, "assignment-in-condition"), (3, "empty-block")],
+            },
+            TestCase {
+                input: "var a;\nwhile (a != nil)\n  for (;;) if (nil == a) { fun f() { return a == nil; } }\nprint a == nil;",
+                expected_output: vec![
+                    (2, "nil-comparison-in-loop"),
+                    (3, "nil-comparison-in-loop"),
+                ],
+            },
+            TestCase {
+                input: "if (true) {\n} else {\n  print 1;\n}",
+                expected_output: vec![(1, "empty-block")],
+            },
+            TestCase {
+                input: "var m = {1: {}};
+if (m) {
+}",
+                expected_output: vec![(2, "empty-block")],
+            },
+            TestCase {
+                input: "import { a } from \"a.lox\";\nexport var b;\nfun f(a) {\n  if (b) {\n  }\n  return a;\n}",
+                expected_output: vec![(3, "shadowed-global"), (4, "empty-block")],
+            },
+            TestCase {
+                input: "var e;\ntry {\n  print 1;\n} catch (e) {\n} finally { print 2; }",
+                expected_output: vec![(4, "shadowed-global"), (4, "empty-block")],
+            },
+            TestCase {
+                input: "a: while (true) {\n  for (;;) {\n    continue a;\n    print 1;\n  }\n  break;\n}",
+                expected_output: vec![(3, "unreachable-code")],
+            },
+        ];
+
+        for test_case in test_cases {
+            let lints: Vec<(i32, &str)> = lint(test_case.input)
+                .unwrap()
+                .iter()
+                .map(|lint| (lint.line_number, lint.rule.id()))
+                .collect();
+            assert_eq!(lints, test_case.expected_output, "{}", test_case.input);
+        }
+    }
+
+    #[test]
+    fn rules_are_configured_with_comments() {
+        let source_code = "// lint: disable empty-block, unused-variable\n{ var a; }\n{}\n// lint: disable nil-comparison-in-loop\n// lint: enable empty-block bogus\n";
+        let lints: Vec<(i32, Rule)> = lint(source_code)
+            .unwrap()
+            .into_iter()
+            .map(|lint| (lint.line_number, lint.rule))
+            .collect();
+
+        assert_eq!(lints, [(3, Rule::EmptyBlock), (5, Rule::UnknownRule)]);
+        assert_eq!(
+            lint("// lint: disable bogus-rule\n").unwrap()[0].to_string(),
+            "warning[unknown-rule]: Unknown lint rule 'bogus-rule'."
+        );
+        assert_eq!(
+            lint("// lint: disable unknown-rule, bogus-rule\n").unwrap(),
+            []
+        );
+    }
+
+    #[test]
+    fn lints_are_displayed_with_their_severity_and_rule() {
+        let lints = lint("{\n  var a;\n}").unwrap();
+
+        assert_eq!(
+            lints[0].to_string(),
+            "warning[unused-variable]: Local variable 'a' is never read."
+        );
+        assert!(lint("{ var a = a; }").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::symbol::Symbol;
+use crate::value::{Arity, NativeFunction, Value};
+
+type List = Rc<RefCell<Vec<Value>>>;
+
+// A method implemented in Rust, given the list it is called on.
+type Method = fn(&List, &[Value]) -> Result<Value, String>;
+
+/// The method of a list with the given name, bound to the list, if lists have one.
+pub fn method(list: &List, name: &Symbol) -> Option<Value> {
+    let (arity, method): (usize, Method) = match name.as_str() {
+        "push" => (1, push),
+        "pop" => (0, pop),
+        "len" => (0, len),
+        "insert" => (2, insert),
+        "remove" => (1, remove),
+        "contains" => (1, contains),
+        _ => return None,
+    };
+    let list = Rc::clone(list);
+    Some(Value::NativeFunction(Rc::new(NativeFunction {
+        name: name.clone(),
+        arity: Arity::Exact(arity),
+        function: Box::new(move |_, arguments| method(&list, arguments)),
+    })))
+}
+
+/// The element at an index, which counts from the end of the list when it is negative.
+pub fn get(list: &List, index: &Value) -> Result<Value, String> {
+    let list = list.borrow();
+    let index = element_index(index, list.len())?;
+    Ok(list[index].clone())
+}
+
+pub fn set(list: &List, index: &Value, value: Value) -> Result<(), String> {
+    let mut list = list.borrow_mut();
+    let index = element_index(index, list.len())?;
+    list[index] = value;
+    Ok(())
+}
+
+/// A new list of the elements from `start` up to but not including `end`. Bounds count from the
+/// end when they are negative and are clamped to the list, and omitted ones are its ends.
+pub fn slice(list: &List, start: Option<&Value>, end: Option<&Value>) -> Result<Value, String> {
+    let list = list.borrow();
+    let length = list.len() as i64;
+    let bound = |bound: Option<&Value>, default: i64| match bound {
+        Some(bound) => offset(bound, list.len()).map(|offset| offset.clamp(0, length) as usize),
+        None => Ok(default as usize),
+    };
+    let start = bound(start, 0)?;
+    let end = bound(end, length)?.max(start);
+    Ok(Value::from(list[start..end].to_vec()))
+}
+
+fn push(list: &List, arguments: &[Value]) -> Result<Value, String> {
+    list.borrow_mut().push(arguments[0].clone());
+    Ok(Value::Nil)
+}
+
+fn pop(list: &List, _: &[Value]) -> Result<Value, String> {
+    list.borrow_mut()
+        .pop()
+        .ok_or_else(|| "Can't pop from an empty list.".to_string())
+}
+
+fn len(list: &List, _: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(list.borrow().len() as f64))
+}
+
+/// Inserts an element before the one at an index, or at the end when the index is the length.
+fn insert(list: &List, arguments: &[Value]) -> Result<Value, String> {
+    let mut list = list.borrow_mut();
+    let index = match offset(&arguments[0], list.len())? {
+        index if (0..=list.len() as i64).contains(&index) => index as usize,
+        _ => return Err(out_of_bounds(&arguments[0], list.len())),
+    };
+    list.insert(index, arguments[1].clone());
+    Ok(Value::Nil)
+}
+
+/// Removes the element at an index and returns it.
+fn remove(list: &List, arguments: &[Value]) -> Result<Value, String> {
+    let mut list = list.borrow_mut();
+    let index = element_index(&arguments[0], list.len())?;
+    Ok(list.remove(index))
+}
+
+fn contains(list: &List, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(list.borrow().contains(&arguments[0])))
+}
+
+// The position of the element at an index in a list of the given length.
+fn element_index(index: &Value, length: usize) -> Result<usize, String> {
+    match offset(index, length)? {
+        offset if (0..length as i64).contains(&offset) => Ok(offset as usize),
+        _ => Err(out_of_bounds(index, length)),
+    }
+}
+
+// Turns a negative index into one counting from the start, which may still be out of bounds.
+fn offset(index: &Value, length: usize) -> Result<i64, String> {
+    match index {
+        Value::Number(number) if number.fract() == 0.0 && *number < 0.0 => {
+            Ok(*number as i64 + length as i64)
+        }
+        Value::Number(number) if number.fract() == 0.0 => Ok(*number as i64),
+        _ => Err("List index must be an integer.".to_string()),
+    }
+}
+
+fn out_of_bounds(index: &Value, length: usize) -> String {
+    format!(
+        "Index {} is out of bounds for a list of length {}.",
+        index, length
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::Value;
+
+    use super::{get, slice};
+
+    fn list(numbers: &[f64]) -> Value {
+        Value::from(
+            numbers
+                .iter()
+                .map(|&number| Value::Number(number))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn negative_indexes_count_from_the_end() {
+        let Value::List(numbers) = list(&[1.0, 2.0, 3.0]) else {
+            unreachable!()
+        };
+
+        assert_eq!(get(&numbers, &Value::Number(-1.0)), Ok(Value::Number(3.0)));
+        assert_eq!(
+            get(&numbers, &Value::Number(-4.0)),
+            Err("Index -4 is out of bounds for a list of length 3.".to_string())
+        );
+        assert_eq!(
+            get(&numbers, &Value::Number(0.5)),
+            Err("List index must be an integer.".to_string())
+        );
+    }
+
+    #[test]
+    fn slices_are_clamped_to_the_list() {
+        let Value::List(numbers) = list(&[1.0, 2.0, 3.0, 4.0]) else {
+            unreachable!()
+        };
+        let test_cases = [
+            (Some(1.0), Some(3.0), "[2, 3]"),
+            (None, Some(-1.0), "[1, 2, 3]"),
+            (Some(-2.0), None, "[3, 4]"),
+            (Some(-10.0), Some(10.0), "[1, 2, 3, 4]"),
+            (Some(3.0), Some(1.0), "[]"),
+        ];
+
+        for (start, end, expected_output) in test_cases {
+            let (start, end) = (start.map(Value::Number), end.map(Value::Number));
+            let sliced = slice(&numbers, start.as_ref(), end.as_ref()).unwrap();
+            assert_eq!(sliced.to_string(), expected_output);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::ops::Range;
+
+use crate::cst::{self, SyntaxElement, SyntaxKind, SyntaxNode};
+use crate::parser::recursive_descent::RecursiveDescentParser;
+use crate::parser::Parse;
+use crate::resolver::{DeclarationKind, Occurrence, Resolver};
+use crate::scanner::Scanner;
+use crate::token::TokenType;
+
+/// What the server knows about one version of a document. Positions are byte ranges into the
+/// text the analysis was made from.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    /// The identifiers of the document in source order. They are only known when it parses.
+    pub identifiers: Vec<Identifier>,
+    /// The functions and classes of the document, each with what is declared inside it.
+    pub symbols: Vec<DocumentSymbol>,
+}
+
+/// An error found in the document, which covers the whole line it was reported on.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Identifier {
+    pub name: String,
+    pub range: Range<usize>,
+    pub occurrence: Occurrence,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub kind: DeclarationKind,
+    pub range: Range<usize>,
+    pub name_range: Range<usize>,
+    pub children: Vec<DocumentSymbol>,
+}
+
+impl Analysis {
+    /// Scans, parses and resolves a document, keeping the errors of the first stage that fails.
+    pub fn new(text: &str) -> Analysis {
+        let mut analysis = Analysis::default();
+        let tokens = match Scanner::with_trivia().scan(text) {
+            Ok(tokens) => tokens,
+            Err(scan_error) => {
+                analysis.error(text, scan_error.line_number, &scan_error.message);
+                return analysis;
+            }
+        };
+        let root = match RecursiveDescentParser::new().parse_syntax(&tokens) {
+            Ok(root) => root,
+            Err(parse_errors) => {
+                for parse_error in parse_errors {
+                    analysis.error(text, parse_error.line_number, &parse_error.message);
+                }
+                return analysis;
+            }
+        };
+
+        let mut resolver = Resolver::new();
+        if let Err(resolve_errors) = resolver.resolve(&cst::lower::program(&root)) {
+            for resolve_error in resolve_errors {
+                analysis.error(text, resolve_error.line_number, &resolve_error.message);
+            }
+        }
+        // The resolver records an occurrence for each identifier token, in the same order.
+        let mut identifiers = Vec::new();
+        collect_identifiers(&root, &mut identifiers);
+        debug_assert_eq!(identifiers.len(), resolver.occurrences().len());
+        analysis.identifiers = identifiers
+            .into_iter()
+            .zip(resolver.occurrences())
+            .map(|((name, range), occurrence)| Identifier {
+                name,
+                range,
+                occurrence: *occurrence,
+            })
+            .collect();
+        analysis.symbols = document_symbols(&root);
+        analysis
+    }
+
+    /// The index of the identifier at a byte offset. A cursor just after a name is on it.
+    pub fn identifier_at(&self, offset: usize) -> Option<usize> {
+        self.identifiers
+            .iter()
+            .position(|identifier| identifier.range.contains(&offset))
+            .or_else(|| {
+                self.identifiers
+                    .iter()
+                    .position(|identifier| identifier.range.end == offset)
+            })
+    }
+
+    /// The index of the declaration an identifier refers to, which is itself for a declaration.
+    pub fn declaration_of(&self, index: usize) -> Option<usize> {
+        match self.identifiers[index].occurrence {
+            Occurrence::Declaration(_) => Some(index),
+            Occurrence::Reference(declaration) | Occurrence::Type(declaration) => declaration,
+            Occurrence::Property => None,
+        }
+    }
+
+    /// The indexes of the identifiers referring to a declaration, in source order.
+    pub fn references(&self, declaration: usize, include_declaration: bool) -> Vec<usize> {
+        (0..self.identifiers.len())
+            .filter(|&index| {
+                (include_declaration && index == declaration)
+                    || matches!(
+                        self.identifiers[index].occurrence,
+                        Occurrence::Reference(Some(referenced)) | Occurrence::Type(Some(referenced))
+                            if referenced == declaration
+                    )
+            })
+            .collect()
+    }
+
+    fn error(&mut self, text: &str, line_number: i32, message: &str) {
+        self.diagnostics.push(Diagnostic {
+            range: line_range(text, line_number),
+            message: message.to_string(),
+        });
+    }
+}
+
+/// The zero-based line and UTF-16 column of a byte offset, which is how LSP counts positions.
+pub fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line = before.matches('\n').count();
+    (line, before[line_start..].encode_utf16().count())
+}
+
+/// The byte offset of a zero-based line and UTF-16 column, clamped to the end of the line.
+pub fn offset(text: &str, line: usize, character: usize) -> usize {
+    let line_start: usize = text.split_inclusive('\n').take(line).map(str::len).sum();
+    let mut units = 0;
+    for (index, c) in text[line_start..].char_indices() {
+        if c == '\n' || units >= character {
+            return line_start + index;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+// The byte range of a one-based line, without its line break.
+fn line_range(text: &str, line_number: i32) -> Range<usize> {
+    let skipped = line_number.max(1) as usize - 1;
+    let start: usize = text.split_inclusive('\n').take(skipped).map(str::len).sum();
+    let line = text[start..].split('\n').next().unwrap_or("");
+    start..start + line.trim_end_matches('\r').len()
+}
+
+fn collect_identifiers(node: &SyntaxNode, identifiers: &mut Vec<(String, Range<usize>)>) {
+    for child in node.children_with_tokens() {
+        match child {
+            SyntaxElement::Node(node) => collect_identifiers(&node, identifiers),
+            SyntaxElement::Token(token) if token.token_type() == TokenType::Identifier => {
+                identifiers.push((token.text().to_string(), token.text_range()));
+            }
+            SyntaxElement::Token(_) => {}
+        }
+    }
+}
+
+fn document_symbols(node: &SyntaxNode) -> Vec<DocumentSymbol> {
+    let mut symbols = Vec::new();
+    for child in node.children() {
+        let kind = match child.kind() {
+            SyntaxKind::FunctionDeclaration => Some(DeclarationKind::Function),
+            SyntaxKind::ClassDeclaration => Some(DeclarationKind::Class),
+            SyntaxKind::Method => Some(DeclarationKind::Method),
+            _ => None,
+        };
+        let name = child
+            .tokens()
+            .find(|token| token.token_type() == TokenType::Identifier);
+        match kind.zip(name) {
+            Some((kind, name)) => symbols.push(DocumentSymbol {
+                name: name.text().to_string(),
+                kind,
+                range: child.text_range(),
+                name_range: name.text_range(),
+                children: document_symbols(&child),
+            }),
+            None => symbols.extend(document_symbols(&child)),
+        }
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resolver::DeclarationKind;
+
+    use super::{offset, position, Analysis, Diagnostic, DocumentSymbol};
+
+    #[test]
+    fn references_are_linked_to_their_declarations() {
+        let text = "var a = 1;\nfun f(a) { return a; }\nprint a + f(a) + clock();";
+        let analysis = Analysis::new(text);
+        let names: Vec<(&str, usize)> = analysis
+            .identifiers
+            .iter()
+            .map(|identifier| (identifier.name.as_str(), identifier.range.start))
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                ("a", 4),
+                ("f", 15),
+                ("a", 17),
+                ("a", 29),
+                ("a", 40),
+                ("f", 44),
+                ("a", 46),
+                ("clock", 51)
+            ]
+        );
+        assert_eq!(analysis.declaration_of(3), Some(2));
+        assert_eq!(analysis.declaration_of(6), Some(0));
+        assert_eq!(analysis.declaration_of(7), None);
+        assert_eq!(analysis.references(0, true), [0, 4, 6]);
+        assert_eq!(analysis.references(2, false), [3]);
+        assert_eq!(analysis.identifier_at(41), Some(4));
+        assert_eq!(analysis.identifier_at(45), Some(5));
+        assert_eq!(analysis.identifier_at(43), None);
+    }
+
+    #[test]
+    fn errors_are_reported_on_their_whole_line() {
+        let test_cases = [
+            (
+                "print 1;\nprint (2;\r\n",
+                vec![(9..18, "Unexpected token: 2")],
+            ),
+            ("var a = \"b;", vec![(0..11, "Unterminated string")]),
+            (
+                "{ var a = a; }\nthis;",
+                vec![
+                    (0..14, "Can't read local variable in its own initializer."),
+                    (15..20, "Can't use 'this' outside of a class."),
+                ],
+            ),
+        ];
+
+        for (text, expected_output) in test_cases {
+            let expected_output: Vec<Diagnostic> = expected_output
+                .into_iter()
+                .map(|(range, message)| Diagnostic {
+                    range,
+                    message: message.to_string(),
+                })
+                .collect();
+            assert_eq!(Analysis::new(text).diagnostics, expected_output, "{}", text);
+        }
+    }
+
+    #[test]
+    fn functions_and_classes_are_outlined() {
+        let text = "class A { m() { fun f() {} } }\nvar b;\nfun g() {}";
+        let symbol = |name: &str, kind, range, name_range, children| DocumentSymbol {
+            name: name.to_string(),
+            kind,
+            range,
+            name_range,
+            children,
+        };
+
+        assert_eq!(
+            Analysis::new(text).symbols,
+            [
+                symbol(
+                    "A",
+                    DeclarationKind::Class,
+                    0..30,
+                    6..7,
+                    vec![symbol(
+                        "m",
+                        DeclarationKind::Method,
+                        10..28,
+                        10..11,
+                        vec![symbol(
+                            "f",
+                            DeclarationKind::Function,
+                            16..26,
+                            20..21,
+                            vec![]
+                        )]
+                    )]
+                ),
+                symbol("g", DeclarationKind::Function, 38..48, 42..43, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_count_utf16_code_units() {
+        let text = "print \"é😀\";\nx";
+
+        assert_eq!(position(text, 0), (0, 0));
+        assert_eq!(position(text, 13), (0, 10));
+        assert_eq!(position(text, 16), (1, 0));
+        assert_eq!(offset(text, 0, 10), 13);
+        assert_eq!(offset(text, 0, 99), 15);
+        assert_eq!(offset(text, 1, 1), 17);
+    }
+}
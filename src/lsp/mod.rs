@@ -0,0 +1,620 @@
+pub mod analysis;
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+
+use serde_json::{json, Value};
+
+use crate::highlight::{self, Highlight};
+use crate::interpreter::Interpreter;
+use crate::resolver::{DeclarationKind, Occurrence};
+use crate::token::TokenType;
+
+use self::analysis::{Analysis, DocumentSymbol};
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// LSP's numbering of the kinds of completions and document symbols.
+const COMPLETION_METHOD: u8 = 2;
+const COMPLETION_FUNCTION: u8 = 3;
+const COMPLETION_VARIABLE: u8 = 6;
+const COMPLETION_CLASS: u8 = 7;
+const COMPLETION_KEYWORD: u8 = 14;
+const SYMBOL_CLASS: u8 = 5;
+const SYMBOL_METHOD: u8 = 6;
+const SYMBOL_FUNCTION: u8 = 12;
+
+const DIAGNOSTIC_ERROR: u8 = 1;
+
+// The semantic token types the server reports, by their LSP names. A token's type is its index.
+const TOKEN_TYPES: [&str; 13] = [
+    "keyword",
+    "string",
+    "number",
+    "operator",
+    "comment",
+    "function",
+    "class",
+    "method",
+    "parameter",
+    "variable",
+    "property",
+    "type",
+    "label",
+];
+
+/// Serves the Language Server Protocol over a pair of streams until the client sends `exit`,
+/// and returns the exit code the protocol asks for: 0 if the client asked the server to shut
+/// down first, and 1 otherwise.
+pub fn serve(mut input: impl BufRead, output: impl Write) -> io::Result<i32> {
+    let mut server = Server {
+        output,
+        documents: HashMap::new(),
+        natives: Interpreter::new().global_names(),
+        shutting_down: false,
+    };
+    server.natives.sort();
+
+    while let Some(body) = read_message(&mut input)? {
+        let message = match serde_json::from_slice::<Value>(&body) {
+            Ok(message) => message,
+            Err(error) => {
+                server.send(&json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": PARSE_ERROR, "message": error.to_string() },
+                }))?;
+                continue;
+            }
+        };
+        if message["method"] == "exit" {
+            return Ok(if server.shutting_down { 0 } else { 1 });
+        }
+        server.handle(&message)?;
+    }
+    Ok(1)
+}
+
+// Reads the body of the next message, which is framed by a `Content-Length` header, or returns
+// `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let Some(content_length) = content_length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Expected a Content-Length header.",
+        ));
+    };
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+struct Server<W: Write> {
+    output: W,
+    // The open documents by URI, with the text the client last sent.
+    documents: HashMap<String, (String, Analysis)>,
+    // The globals of the standard library, sorted.
+    natives: Vec<String>,
+    shutting_down: bool,
+}
+
+impl<W: Write> Server<W> {
+    fn handle(&mut self, message: &Value) -> io::Result<()> {
+        let Some(method) = message["method"].as_str() else {
+            // The server sends no requests, so there are no responses to read.
+            return Ok(());
+        };
+        let params = &message["params"];
+        let Some(id) = message.get("id") else {
+            return self.notification(method, params);
+        };
+
+        let result = if self.shutting_down {
+            Err(ResponseError::new(
+                INVALID_REQUEST,
+                "The server is shutting down.",
+            ))
+        } else {
+            self.request(method, params)
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": error.code, "message": error.message },
+            }),
+        };
+        self.send(&response)
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<()> {
+        let uri = uri(params);
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.update(uri, text.to_string())
+            }
+            // The server asks for the whole text on every change, so the last change has it.
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                match changes.and_then(|changes| changes.last()) {
+                    Some(change) => {
+                        let text = change["text"].as_str().unwrap_or_default();
+                        self.update(uri, text.to_string())
+                    }
+                    None => Ok(()),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                self.publish_diagnostics(uri, Vec::new())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, ResponseError> {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                    "documentSymbolProvider": true,
+                    "completionProvider": {},
+                    "semanticTokensProvider": {
+                        "legend": { "tokenTypes": TOKEN_TYPES, "tokenModifiers": [] },
+                        "full": true,
+                    },
+                },
+                "serverInfo": { "name": "rustlox", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutting_down = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => {
+                let (text, analysis) = self.document(params)?;
+                let Some(index) = self.identifier(params)? else {
+                    return Ok(Value::Null);
+                };
+                Ok(match analysis.declaration_of(index) {
+                    Some(declaration) => {
+                        location(uri(params), text, &analysis.identifiers[declaration].range)
+                    }
+                    None => Value::Null,
+                })
+            }
+            "textDocument/references" => {
+                let (text, analysis) = self.document(params)?;
+                let Some(index) = self.identifier(params)? else {
+                    return Ok(Value::Null);
+                };
+                let include_declaration = params["context"]["includeDeclaration"] == true;
+                let references = match analysis.declaration_of(index) {
+                    Some(declaration) => analysis.references(declaration, include_declaration),
+                    None => Vec::new(),
+                };
+                Ok(references
+                    .into_iter()
+                    .map(|index| location(uri(params), text, &analysis.identifiers[index].range))
+                    .collect())
+            }
+            "textDocument/hover" => {
+                let (text, analysis) = self.document(params)?;
+                let Some(index) = self.identifier(params)? else {
+                    return Ok(Value::Null);
+                };
+                let identifier = &analysis.identifiers[index];
+                let description = match analysis.declaration_of(index) {
+                    Some(declaration) => match analysis.identifiers[declaration].occurrence {
+                        Occurrence::Declaration(kind) => kind.as_str(),
+                        _ => unreachable!("references are to declarations"),
+                    },
+                    None if identifier.occurrence == Occurrence::Property => "property",
+                    None if identifier.occurrence == Occurrence::Type(None) => "type",
+                    None if self.natives.contains(&identifier.name) => "native function",
+                    None => return Ok(Value::Null),
+                };
+                Ok(json!({
+                    "contents": {
+                        "kind": "plaintext",
+                        "value": format!("{} {}", description, identifier.name),
+                    },
+                    "range": range(text, &identifier.range),
+                }))
+            }
+            "textDocument/documentSymbol" => {
+                let (text, analysis) = self.document(params)?;
+                Ok(document_symbols(text, &analysis.symbols))
+            }
+            "textDocument/semanticTokens/full" => {
+                let (text, _) = self.document(params)?;
+                Ok(json!({ "data": semantic_tokens(text) }))
+            }
+            "textDocument/completion" => {
+                let (_, analysis) = self.document(params)?;
+                Ok(self.completions(analysis))
+            }
+            _ => Err(ResponseError::new(
+                METHOD_NOT_FOUND,
+                &format!("Unknown method '{}'.", method),
+            )),
+        }
+    }
+
+    // The text and analysis of the open document a request is about.
+    fn document(&self, params: &Value) -> Result<(&str, &Analysis), ResponseError> {
+        match self.documents.get(uri(params)) {
+            Some((text, analysis)) => Ok((text, analysis)),
+            None => Err(ResponseError::new(
+                INVALID_PARAMS,
+                &format!("Unknown document '{}'.", uri(params)),
+            )),
+        }
+    }
+
+    // The index of the identifier at the position a request is about, if there is one.
+    fn identifier(&self, params: &Value) -> Result<Option<usize>, ResponseError> {
+        let (text, analysis) = self.document(params)?;
+        let position = &params["position"];
+        let (Some(line), Some(character)) =
+            (position["line"].as_u64(), position["character"].as_u64())
+        else {
+            return Err(ResponseError::new(INVALID_PARAMS, "Expected a position."));
+        };
+        let offset = analysis::offset(text, line as usize, character as usize);
+        Ok(analysis.identifier_at(offset))
+    }
+
+    // Keywords, the names the document declares and the standard library.
+    fn completions(&self, analysis: &Analysis) -> Value {
+        let mut items: Vec<(&str, u8)> = TokenType::KEYWORDS
+            .iter()
+            .map(|keyword| (*keyword, COMPLETION_KEYWORD))
+            .collect();
+        for identifier in &analysis.identifiers {
+            let Occurrence::Declaration(kind) = identifier.occurrence else {
+                continue;
+            };
+            let kind = match kind {
+                DeclarationKind::Variable | DeclarationKind::Parameter => COMPLETION_VARIABLE,
+                DeclarationKind::Function => COMPLETION_FUNCTION,
+                DeclarationKind::Class => COMPLETION_CLASS,
+                DeclarationKind::Method => COMPLETION_METHOD,
+                DeclarationKind::Label => continue,
+            };
+            if !items.iter().any(|(name, _)| *name == identifier.name) {
+                items.push((&identifier.name, kind));
+            }
+        }
+        for native in &self.natives {
+            if !items.iter().any(|(name, _)| name == native) {
+                items.push((native, COMPLETION_FUNCTION));
+            }
+        }
+        items
+            .into_iter()
+            .map(|(label, kind)| json!({ "label": label, "kind": kind }))
+            .collect()
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> io::Result<()> {
+        let analysis = Analysis::new(&text);
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                json!({
+                    "range": range(&text, &diagnostic.range),
+                    "severity": DIAGNOSTIC_ERROR,
+                    "source": "rustlox",
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+        self.documents.insert(uri.to_string(), (text, analysis));
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Vec<Value>) -> io::Result<()> {
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }))
+    }
+
+    fn send(&mut self, message: &Value) -> io::Result<()> {
+        let body = message.to_string();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.output.flush()
+    }
+}
+
+// Why a request failed, sent back in place of its result.
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+impl ResponseError {
+    fn new(code: i64, message: &str) -> ResponseError {
+        ResponseError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+// The URI of the document a request or notification is about.
+fn uri(params: &Value) -> &str {
+    params["textDocument"]["uri"].as_str().unwrap_or_default()
+}
+
+fn range(text: &str, range: &Range<usize>) -> Value {
+    let (start_line, start_character) = analysis::position(text, range.start);
+    let (end_line, end_character) = analysis::position(text, range.end);
+    json!({
+        "start": { "line": start_line, "character": start_character },
+        "end": { "line": end_line, "character": end_character },
+    })
+}
+
+fn location(uri: &str, text: &str, byte_range: &Range<usize>) -> Value {
+    json!({ "uri": uri, "range": range(text, byte_range) })
+}
+
+// The highlighted tokens as LSP encodes them: five numbers per token, which are its line and
+// start relative to the token before, its length, its type and its modifiers. Tokens never span
+// lines, since strings can't and comments end before the line break.
+fn semantic_tokens(text: &str) -> Vec<usize> {
+    let Ok(spans) = highlight::highlight(text) else {
+        return Vec::new();
+    };
+    let mut data = Vec::new();
+    let (mut previous_line, mut previous_start) = (0, 0);
+    for span in spans {
+        let token_type = match span.highlight {
+            Highlight::Punctuation => continue,
+            Highlight::Literal => "keyword",
+            highlight => highlight.as_str(),
+        };
+        let token_type = TOKEN_TYPES
+            .iter()
+            .position(|name| *name == token_type)
+            .expect("every highlight but punctuation has a token type");
+
+        let (line, start) = analysis::position(text, span.range.start);
+        if line != previous_line {
+            previous_start = 0;
+        }
+        data.extend([
+            line - previous_line,
+            start - previous_start,
+            text[span.range].encode_utf16().count(),
+            token_type,
+            0,
+        ]);
+        (previous_line, previous_start) = (line, start);
+    }
+    data
+}
+
+fn document_symbols(text: &str, symbols: &[DocumentSymbol]) -> Value {
+    symbols
+        .iter()
+        .map(|symbol| {
+            let kind = match symbol.kind {
+                DeclarationKind::Class => SYMBOL_CLASS,
+                DeclarationKind::Method => SYMBOL_METHOD,
+                _ => SYMBOL_FUNCTION,
+            };
+            json!({
+                "name": symbol.name,
+                "kind": kind,
+                "range": range(text, &symbol.range),
+                "selectionRange": range(text, &symbol.name_range),
+                "children": document_symbols(text, &symbol.children),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::serve;
+
+    fn frame(message: Value) -> String {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    // Runs a session and returns the exit code with the messages the server sent.
+    fn session(messages: Vec<Value>) -> (i32, Vec<Value>) {
+        let input: String = messages.into_iter().map(frame).collect();
+        let mut output = Vec::new();
+        let exit_code = serve(input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let messages = output
+            .split("Content-Length: ")
+            .skip(1)
+            .map(|message| {
+                let (_, body) = message.split_once("\r\n\r\n").unwrap();
+                serde_json::from_str(body).unwrap()
+            })
+            .collect();
+        (exit_code, messages)
+    }
+
+    fn request(id: i32, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn notification(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "method": method, "params": params })
+    }
+
+    fn at(line: u32, character: u32) -> Value {
+        json!({
+            "textDocument": { "uri": "file:///a.lox" },
+            "position": { "line": line, "character": character },
+            "context": { "includeDeclaration": true },
+        })
+    }
+
+    fn range(line: u32, start: u32, end: u32) -> Value {
+        json!({
+            "start": { "line": line, "character": start },
+            "end": { "line": line, "character": end },
+        })
+    }
+
+    #[test]
+    fn documents_are_analyzed_as_they_change() {
+        let text = "fun add(a, b) {\n  return a + b;\n}\nprint add(1, 2) + clock();";
+        let (exit_code, messages) = session(vec![
+            request(1, "initialize", json!({})),
+            notification("initialized", json!({})),
+            notification(
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": "file:///a.lox", "text": "print (;" } }),
+            ),
+            notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": "file:///a.lox" },
+                    "contentChanges": [{ "text": text }],
+                }),
+            ),
+            request(2, "textDocument/definition", at(3, 7)),
+            request(3, "textDocument/references", at(1, 9)),
+            request(4, "textDocument/hover", at(3, 19)),
+            request(5, "textDocument/documentSymbol", at(0, 0)),
+            request(6, "textDocument/formatting", at(0, 0)),
+            request(7, "shutdown", Value::Null),
+            notification("exit", Value::Null),
+        ]);
+
+        assert_eq!(exit_code, 0);
+        assert_eq!(messages.len(), 9);
+        assert_eq!(messages[0]["result"]["capabilities"]["hoverProvider"], true);
+        assert_eq!(
+            messages[1]["params"]["diagnostics"],
+            json!([{
+                "range": range(0, 0, 8),
+                "severity": 1,
+                "source": "rustlox",
+                "message": "Unexpected token: ;",
+            }])
+        );
+        assert_eq!(messages[2]["params"]["diagnostics"], json!([]));
+        assert_eq!(messages[3]["result"]["range"], range(0, 4, 7));
+        assert_eq!(
+            messages[4]["result"],
+            json!([
+                { "uri": "file:///a.lox", "range": range(0, 8, 9) },
+                { "uri": "file:///a.lox", "range": range(1, 9, 10) },
+            ])
+        );
+        assert_eq!(
+            messages[5]["result"]["contents"]["value"],
+            "native function clock"
+        );
+        assert_eq!(messages[6]["result"][0]["name"], "add");
+        assert_eq!(messages[6]["result"][0]["kind"], 12);
+        assert_eq!(messages[7]["error"]["code"], -32601);
+        assert_eq!(messages[8]["result"], Value::Null);
+    }
+
+    #[test]
+    fn completions_include_keywords_declarations_and_the_standard_library() {
+        let (_, messages) = session(vec![
+            notification(
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": "file:///a.lox", "text": "class Point {}\nvar p;" } }),
+            ),
+            request(1, "textDocument/completion", at(1, 0)),
+        ]);
+        let labels: Vec<(&str, u64)> = messages[1]["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| {
+                (
+                    item["label"].as_str().unwrap(),
+                    item["kind"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+
+        assert!(labels.contains(&("while", 14)));
+        assert!(labels.contains(&("Point", 7)));
+        assert!(labels.contains(&("p", 6)));
+        assert!(labels.contains(&("clock", 3)));
+    }
+
+    #[test]
+    fn semantic_tokens_are_relative_to_the_token_before() {
+        let text = "fun f(a) {\n  print \"é😀\" + a;\n}";
+        let (_, messages) = session(vec![
+            notification(
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": "file:///a.lox", "text": text } }),
+            ),
+            request(1, "textDocument/semanticTokens/full", at(0, 0)),
+        ]);
+
+        #[rustfmt::skip]
+        let expected_output = json!([
+            0, 0, 3, 0, 0, // fun
+            0, 4, 1, 5, 0, // f
+            0, 2, 1, 8, 0, // a
+            1, 2, 5, 0, 0, // print
+            0, 6, 5, 1, 0, // "é😀"
+            0, 6, 1, 3, 0, // +
+            0, 2, 1, 8, 0, // a
+        ]);
+        assert_eq!(messages[1]["result"]["data"], expected_output);
+    }
+
+    #[test]
+    fn exiting_without_shutting_down_is_an_error() {
+        let (exit_code, messages) = session(vec![notification("exit", Value::Null)]);
+
+        assert_eq!(exit_code, 1);
+        assert!(messages.is_empty());
+    }
+}
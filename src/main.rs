@@ -1,32 +1,729 @@
-mod ast;
-mod interpreter;
-mod parser;
-mod scanner;
-mod token;
+use std::io::Read;
+use std::path::PathBuf;
 
-use crate::interpreter::Interpreter;
+use rustlox::{Interpreter, SharedBuffer, Value};
 
 const EXIT_CODE_USAGE: i32 = 32;
+const EXIT_CODE_ERROR: i32 = 65;
+const EXIT_CODE_NO_INPUT: i32 = 66;
+const EXIT_CODE_RUNTIME_ERROR: i32 = 70;
+const EXIT_CODE_TEST_FAILURE: i32 = 1;
+const EXIT_CODE_UNFORMATTED: i32 = 1;
+const EXIT_CODE_LINTS: i32 = 1;
+
+// Commands run on a thread with room for deep recursion, most of which scripts may use.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+const SCRIPT_STACK_SIZE: usize = STACK_SIZE - 16 * 1024 * 1024;
+
+const USAGE: &str = "\
+Usage: rustlox [command] [options]
+
+Commands:
+  run <script> [args...]      Run a script, with the arguments after its name in 'args'
+  repl                        Start the interactive prompt
+  check [--types] <script>... Report errors in scripts without running them, and with
+                              --types also the errors of their type annotations
+  fmt [--check] <script>...   Format scripts in place, or with --check list the ones
+                              that aren't formatted
+  lint [--json] <script>...   Report likely mistakes in scripts, or with --json print
+                              them as JSON. A '// lint: disable <rule>...' comment
+                              turns rules off for its script
+  highlight [--html] <script> Print a script with its syntax highlighted for a terminal,
+                              or with --html as HTML for documentation
+  test [path...]              Run the .lox scripts under the paths (default: tests) and
+                              compare what they print with their '// expect: ' comments
+  lsp                         Serve the Language Server Protocol on standard input and
+                              output, for editors
+  help                        Show this message
+
+A script named '-' is read from standard input, and '-e <code>' runs the code given instead
+of a script. Without a command, 'rustlox <script>' runs the script and 'rustlox' starts the
+prompt.
+
+Scripts import modules found next to them, or else in the directories listed in RUSTLOX_PATH.
+
+'run --emit=ast-json <script>' prints the script's syntax tree as JSON instead of running it, and
+'--emit=tokens-json' prints its tokens.
+";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Run(Source, Vec<String>),
+    Repl,
+    Check(Vec<Source>, bool),
+    Fmt(Vec<Source>, bool),
+    Lint(Vec<Source>, bool),
+    Highlight(Source, bool),
+    Test(Vec<String>),
+    #[cfg(feature = "json")]
+    Emit(Source, EmitFormat),
+    #[cfg(feature = "lsp")]
+    Lsp,
+    Help,
+}
+
+// What `--emit` prints instead of running the script.
+#[cfg(feature = "json")]
+#[derive(Debug, PartialEq)]
+enum EmitFormat {
+    Tokens,
+    Ast,
+}
+
+// Where the source code of a script comes from.
+#[derive(Debug, PartialEq)]
+enum Source {
+    File(String),
+    Stdin,
+    Code(String),
+}
+
+impl Source {
+    fn from_argument(argument: &str) -> Source {
+        match argument {
+            "-" => Source::Stdin,
+            filename => Source::File(filename.to_string()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Source::File(filename) => filename,
+            Source::Stdin => "<stdin>",
+            Source::Code(_) => "<code>",
+        }
+    }
+
+    fn read(&self) -> Result<String, std::io::Error> {
+        match self {
+            Source::File(filename) => std::fs::read_to_string(filename),
+            Source::Stdin => {
+                let mut source_code = String::new();
+                std::io::stdin().read_to_string(&mut source_code)?;
+                Ok(source_code)
+            }
+            Source::Code(source_code) => Ok(source_code.clone()),
+        }
+    }
+}
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() > 2 {
-        eprintln!("Usage: rustlox [script]");
-        std::process::exit(EXIT_CODE_USAGE);
-    } else if args.len() == 2 {
-        let filename = &args[1];
-        let mut interpreter = Interpreter::new();
-        if let Err(error) = interpreter.run_file(filename) {
+    let commands = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run_command)
+        .expect("failed to start the thread running the command");
+    // A panic has already been reported by the thread that panicked.
+    if commands.join().is_err() {
+        std::process::exit(101);
+    }
+}
+
+fn run_command() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprint!("{}\n\n{}", message, USAGE);
+            std::process::exit(EXIT_CODE_USAGE);
+        }
+    };
+
+    let result = match command {
+        Command::Run(source, script_args) => run(&source, script_args),
+        Command::Repl => run_prompt(&mut interpreter()).map(|()| 0),
+        Command::Check(sources, types) => check(&sources, types),
+        Command::Fmt(sources, check_only) => fmt(&sources, check_only),
+        Command::Lint(sources, json) => lint(&sources, json),
+        Command::Highlight(source, html) => highlight(&source, html),
+        Command::Test(paths) => test(&paths),
+        #[cfg(feature = "json")]
+        Command::Emit(source, format) => emit(&source, format),
+        #[cfg(feature = "lsp")]
+        Command::Lsp => rustlox::lsp::serve(std::io::stdin().lock(), std::io::stdout().lock()),
+        Command::Help => {
+            print!("{}", USAGE);
+            Ok(0)
+        }
+    };
+
+    match result {
+        Ok(0) => {}
+        Ok(code) => std::process::exit(code),
+        Err(error) => {
             eprintln!("{}", error);
             let code = error.raw_os_error().unwrap_or(1);
             std::process::exit(code);
         }
-    } else {
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some(first) = args.first() else {
+        return Ok(Command::Repl);
+    };
+    let rest = &args[1..];
+    match first.as_str() {
+        "run" => parse_run_args(rest),
+        "repl" if rest.is_empty() => Ok(Command::Repl),
+        "repl" => Err("'repl' takes no arguments.".to_string()),
+        "check" => parse_check_args(rest),
+        "fmt" => parse_fmt_args(rest),
+        "lint" => parse_lint_args(rest),
+        "highlight" => parse_highlight_args(rest),
+        "test" if rest.is_empty() => Ok(Command::Test(vec!["tests".to_string()])),
+        "test" => Ok(Command::Test(rest.to_vec())),
+        #[cfg(feature = "lsp")]
+        "lsp" if rest.is_empty() => Ok(Command::Lsp),
+        #[cfg(feature = "lsp")]
+        "lsp" => Err("'lsp' takes no arguments.".to_string()),
+        #[cfg(not(feature = "lsp"))]
+        "lsp" => Err("'lsp' needs rustlox to be built with the 'lsp' feature.".to_string()),
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        _ => parse_run_args(args),
+    }
+}
+
+fn parse_run_args(args: &[String]) -> Result<Command, String> {
+    if let Some(format) = args
+        .first()
+        .and_then(|argument| argument.strip_prefix("--emit="))
+    {
+        return parse_emit_args(format, &args[1..]);
+    }
+    match args {
+        [] => Err("Expected a script to run.".to_string()),
+        [flag] if flag == "-e" => Err("'-e' needs the code to run.".to_string()),
+        [flag, code, script_args @ ..] if flag == "-e" => Ok(Command::Run(
+            Source::Code(code.clone()),
+            script_args.to_vec(),
+        )),
+        [script, ..] if script.starts_with('-') && script != "-" => {
+            Err(format!("Unknown option '{}'.", script))
+        }
+        [script, script_args @ ..] => Ok(Command::Run(
+            Source::from_argument(script),
+            script_args.to_vec(),
+        )),
+    }
+}
+
+#[cfg(feature = "json")]
+fn parse_emit_args(format: &str, args: &[String]) -> Result<Command, String> {
+    let format = match format {
+        "tokens-json" => EmitFormat::Tokens,
+        "ast-json" => EmitFormat::Ast,
+        _ => {
+            return Err(format!(
+                "Unknown format '{}'. Expected 'ast-json' or 'tokens-json'.",
+                format
+            ))
+        }
+    };
+    match args {
+        [flag, code] if flag == "-e" => Ok(Command::Emit(Source::Code(code.clone()), format)),
+        [script] if !script.starts_with('-') || script == "-" => {
+            Ok(Command::Emit(Source::from_argument(script), format))
+        }
+        _ => Err("'--emit' takes a single script.".to_string()),
+    }
+}
+
+#[cfg(not(feature = "json"))]
+fn parse_emit_args(_format: &str, _args: &[String]) -> Result<Command, String> {
+    Err("'--emit' needs rustlox to be built with the 'json' feature.".to_string())
+}
+
+fn parse_check_args(args: &[String]) -> Result<Command, String> {
+    let (types, scripts) = parse_flag_and_scripts(args, "--types")?;
+    if scripts.is_empty() {
+        return Err("'check' needs at least one script.".to_string());
+    }
+    Ok(Command::Check(scripts, types))
+}
+
+fn parse_fmt_args(args: &[String]) -> Result<Command, String> {
+    let (check_only, scripts) = parse_flag_and_scripts(args, "--check")?;
+    if scripts.is_empty() {
+        return Err("'fmt' needs at least one script.".to_string());
+    }
+    Ok(Command::Fmt(scripts, check_only))
+}
+
+fn parse_lint_args(args: &[String]) -> Result<Command, String> {
+    let (json, scripts) = parse_flag_and_scripts(args, "--json")?;
+    if json && cfg!(not(feature = "json")) {
+        return Err("'--json' needs rustlox to be built with the 'json' feature.".to_string());
+    }
+    if scripts.is_empty() {
+        return Err("'lint' needs at least one script.".to_string());
+    }
+    Ok(Command::Lint(scripts, json))
+}
+
+fn parse_highlight_args(args: &[String]) -> Result<Command, String> {
+    let (html, mut scripts) = parse_flag_and_scripts(args, "--html")?;
+    match (scripts.pop(), scripts.is_empty()) {
+        (Some(script), true) => Ok(Command::Highlight(script, html)),
+        _ => Err("'highlight' takes a single script.".to_string()),
+    }
+}
+
+// Splits the arguments of a command taking scripts into whether its one flag was given, which may
+// come before, after or between the scripts, and the scripts. Any other option is an error.
+fn parse_flag_and_scripts(args: &[String], flag: &str) -> Result<(bool, Vec<Source>), String> {
+    let mut has_flag = false;
+    let mut scripts = Vec::new();
+    for argument in args {
+        if argument == flag {
+            has_flag = true;
+        } else if argument.starts_with('-') && argument != "-" {
+            return Err(format!("Unknown option '{}'.", argument));
+        } else {
+            scripts.push(Source::from_argument(argument));
+        }
+    }
+    Ok((has_flag, scripts))
+}
+
+fn run(source: &Source, script_args: Vec<String>) -> Result<i32, std::io::Error> {
+    let mut interpreter = interpreter();
+    define_script_args(&mut interpreter, script_args);
+    if let Some(search_path) = std::env::var_os("RUSTLOX_PATH") {
+        interpreter.set_search_path(std::env::split_paths(&search_path).collect());
+    }
+    match source {
+        Source::File(filename) => interpreter.run_file(filename)?,
+        _ => interpreter.run(&source.read()?)?,
+    }
+    Ok(exit_code(&interpreter))
+}
+
+#[cfg(feature = "json")]
+fn emit(source: &Source, format: EmitFormat) -> Result<i32, std::io::Error> {
+    use std::io::Write;
+
+    let source_code = source.read()?;
+    let json = match format {
+        EmitFormat::Tokens => rustlox::json::tokens_to_json(&source_code),
+        EmitFormat::Ast => rustlox::json::ast_to_json(&source_code),
+    };
+    match json {
+        Ok(json) => {
+            let mut stdout = std::io::stdout().lock();
+            match writeln!(stdout, "{}", json).and_then(|()| stdout.flush()) {
+                // A reader like `head` may stop before the end, which isn't an error.
+                Err(error) if error.kind() != std::io::ErrorKind::BrokenPipe => Err(error),
+                _ => Ok(0),
+            }
+        }
+        Err(error) => {
+            eprintln!("{}: {}", source.name(), error);
+            Ok(EXIT_CODE_ERROR)
+        }
+    }
+}
+
+// Scripts read their arguments from the `args` list.
+fn define_script_args(interpreter: &mut Interpreter, script_args: Vec<String>) {
+    let script_args: Vec<Value> = script_args.into_iter().map(Value::from).collect();
+    interpreter.set_global("args", Value::from(script_args));
+}
+
+// Reads one of the scripts given to a command that takes several, reporting it with the script's
+// name if it can't be read so that the command can go on with the others.
+fn read_script(source: &Source) -> Option<String> {
+    match source.read() {
+        Ok(source_code) => Some(source_code),
+        Err(error) => {
+            eprintln!("{}: {}", source.name(), error);
+            None
+        }
+    }
+}
+
+// Each script is checked separately, and its errors are prefixed with its name.
+fn check(sources: &[Source], types: bool) -> Result<i32, std::io::Error> {
+    let mut unreadable = false;
+    let mut had_error = false;
+    for source in sources {
+        let Some(source_code) = read_script(source) else {
+            unreadable = true;
+            continue;
+        };
+        let diagnostics = SharedBuffer::new();
         let mut interpreter = Interpreter::new();
-        if let Err(error) = interpreter.run_prompt() {
-            eprintln!("{}", error);
-            let code = error.raw_os_error().unwrap_or(1);
-            std::process::exit(code);
+        interpreter.set_diagnostics(diagnostics.clone());
+        if types {
+            interpreter.check_types(&source_code)?;
+        } else {
+            interpreter.check(&source_code)?;
+        }
+        for line in diagnostics.contents().lines() {
+            eprintln!("{}: {}", source.name(), line);
+        }
+        had_error |= interpreter.had_error();
+    }
+    Ok(if unreadable {
+        EXIT_CODE_NO_INPUT
+    } else if had_error {
+        EXIT_CODE_ERROR
+    } else {
+        0
+    })
+}
+
+// Scripts are rewritten in place, except standard input, which is formatted to standard output.
+fn fmt(sources: &[Source], check_only: bool) -> Result<i32, std::io::Error> {
+    let mut unreadable = false;
+    let mut had_error = false;
+    let mut unformatted = false;
+    for source in sources {
+        let Some(source_code) = read_script(source) else {
+            unreadable = true;
+            continue;
+        };
+        let formatted = match rustlox::formatter::format(&source_code) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                eprintln!("{}: {}", source.name(), error);
+                had_error = true;
+                continue;
+            }
+        };
+
+        if check_only {
+            if formatted != source_code {
+                println!("{} is not formatted.", source.name());
+                unformatted = true;
+            }
+        } else if let Source::File(filename) = source {
+            if formatted != source_code {
+                std::fs::write(filename, formatted)?;
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+
+    Ok(if unreadable {
+        EXIT_CODE_NO_INPUT
+    } else if had_error {
+        EXIT_CODE_ERROR
+    } else if unformatted {
+        EXIT_CODE_UNFORMATTED
+    } else {
+        0
+    })
+}
+
+// Lints are printed to standard output, as `name:line: severity[rule]: message` or all together
+// as JSON, and errors that stop a script being linted to standard error.
+fn lint(sources: &[Source], json: bool) -> Result<i32, std::io::Error> {
+    let mut unreadable = false;
+    let mut had_error = false;
+    let mut lints = Vec::new();
+    for source in sources {
+        let Some(source_code) = read_script(source) else {
+            unreadable = true;
+            continue;
+        };
+        match rustlox::lint::lint(&source_code) {
+            Ok(script_lints) => {
+                lints.extend(script_lints.into_iter().map(|lint| (source.name(), lint)));
+            }
+            Err(error) => {
+                eprintln!("{}: {}", source.name(), error);
+                had_error = true;
+            }
+        }
+    }
+
+    if json {
+        #[cfg(feature = "json")]
+        println!(
+            "{}",
+            rustlox::json::lints_to_json(lints.iter().map(|(name, lint)| (*name, lint)))
+        );
+    } else {
+        for (name, lint) in &lints {
+            println!("{}:{}: {}", name, lint.line_number, lint);
+        }
+    }
+
+    Ok(if unreadable {
+        EXIT_CODE_NO_INPUT
+    } else if had_error {
+        EXIT_CODE_ERROR
+    } else if !lints.is_empty() {
+        EXIT_CODE_LINTS
+    } else {
+        0
+    })
+}
+
+fn highlight(source: &Source, html: bool) -> Result<i32, std::io::Error> {
+    let source_code = source.read()?;
+    let highlighted = if html {
+        rustlox::highlight::to_html(&source_code)
+    } else {
+        rustlox::highlight::to_ansi(&source_code)
+    };
+    match highlighted {
+        Ok(highlighted) => {
+            print!("{}", highlighted);
+            Ok(0)
         }
+        Err(error) => {
+            eprintln!("{}: {}", source.name(), error);
+            Ok(EXIT_CODE_ERROR)
+        }
+    }
+}
+
+fn test(paths: &[String]) -> Result<i32, std::io::Error> {
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    let passed = rustlox::test_runner::run_tests(&paths, &mut std::io::stdout())?;
+    Ok(if passed { 0 } else { EXIT_CODE_TEST_FAILURE })
+}
+
+fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_stack_size(SCRIPT_STACK_SIZE);
+    interpreter
+}
+
+fn exit_code(interpreter: &Interpreter) -> i32 {
+    if interpreter.had_error() {
+        EXIT_CODE_ERROR
+    } else if interpreter.had_runtime_error() {
+        EXIT_CODE_RUNTIME_ERROR
+    } else {
+        0
+    }
+}
+
+// The line editor only makes sense when a person is typing, so piped input is read plainly.
+fn run_prompt(interpreter: &mut Interpreter) -> Result<(), std::io::Error> {
+    #[cfg(feature = "repl")]
+    if std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return rustlox::repl::run(interpreter);
+    }
+    interpreter.run_prompt()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_args, Command, Source};
+
+    struct TestCase {
+        input: &'static str,
+        expected_output: Result<Command, String>,
+    }
+
+    #[test]
+    fn arguments_are_parsed_into_commands() {
+        let test_cases = vec![
+            TestCase {
+                input: "",
+                expected_output: Ok(Command::Repl),
+            },
+            TestCase {
+                input: "script.lox",
+                expected_output: Ok(Command::Run(Source::File("script.lox".to_string()), vec![])),
+            },
+            TestCase {
+                input: "run script.lox a -e",
+                expected_output: Ok(Command::Run(
+                    Source::File("script.lox".to_string()),
+                    vec!["a".to_string(), "-e".to_string()],
+                )),
+            },
+            TestCase {
+                input: "- a",
+                expected_output: Ok(Command::Run(Source::Stdin, vec!["a".to_string()])),
+            },
+            TestCase {
+                input: "-e print(1); b",
+                expected_output: Ok(Command::Run(
+                    Source::Code("print(1);".to_string()),
+                    vec!["b".to_string()],
+                )),
+            },
+            TestCase {
+                input: "check a.lox -",
+                expected_output: Ok(Command::Check(
+                    vec![Source::File("a.lox".to_string()), Source::Stdin],
+                    false,
+                )),
+            },
+            TestCase {
+                input: "check --types a.lox",
+                expected_output: Ok(Command::Check(
+                    vec![Source::File("a.lox".to_string())],
+                    true,
+                )),
+            },
+            TestCase {
+                input: "fmt --check a.lox",
+                expected_output: Ok(Command::Fmt(vec![Source::File("a.lox".to_string())], true)),
+            },
+            TestCase {
+                input: "fmt --check",
+                expected_output: Err("'fmt' needs at least one script.".to_string()),
+            },
+            TestCase {
+                input: "lint a.lox -",
+                expected_output: Ok(Command::Lint(
+                    vec![Source::File("a.lox".to_string()), Source::Stdin],
+                    false,
+                )),
+            },
+            TestCase {
+                input: "lint",
+                expected_output: Err("'lint' needs at least one script.".to_string()),
+            },
+            TestCase {
+                input: "test",
+                expected_output: Ok(Command::Test(vec!["tests".to_string()])),
+            },
+            TestCase {
+                input: "--verbose",
+                expected_output: Err("Unknown option '--verbose'.".to_string()),
+            },
+            TestCase {
+                input: "repl x",
+                expected_output: Err("'repl' takes no arguments.".to_string()),
+            },
+            TestCase {
+                input: "highlight a.lox --html",
+                expected_output: Ok(Command::Highlight(Source::File("a.lox".to_string()), true)),
+            },
+            TestCase {
+                input: "highlight -",
+                expected_output: Ok(Command::Highlight(Source::Stdin, false)),
+            },
+            TestCase {
+                input: "highlight a.lox b.lox",
+                expected_output: Err("'highlight' takes a single script.".to_string()),
+            },
+            TestCase {
+                input: "highlight --ansi a.lox",
+                expected_output: Err("Unknown option '--ansi'.".to_string()),
+            },
+            TestCase {
+                input: "check a.lox --types",
+                expected_output: Ok(Command::Check(
+                    vec![Source::File("a.lox".to_string())],
+                    true,
+                )),
+            },
+            TestCase {
+                input: "fmt a.lox --check b.lox",
+                expected_output: Ok(Command::Fmt(
+                    vec![
+                        Source::File("a.lox".to_string()),
+                        Source::File("b.lox".to_string()),
+                    ],
+                    true,
+                )),
+            },
+            TestCase {
+                input: "check a.lox --strict",
+                expected_output: Err("Unknown option '--strict'.".to_string()),
+            },
+        ];
+
+        for test_case in test_cases {
+            let args: Vec<String> = test_case
+                .input
+                .split_whitespace()
+                .map(String::from)
+                .collect();
+            assert_eq!(
+                parse_args(&args),
+                test_case.expected_output,
+                "{}",
+                test_case.input
+            );
+        }
+    }
+
+    #[cfg(feature = "lsp")]
+    #[test]
+    fn lsp_takes_no_arguments() {
+        let args = ["lsp".to_string()];
+        assert_eq!(parse_args(&args), Ok(Command::Lsp));
+
+        let args = ["lsp".to_string(), "--stdio".to_string()];
+        assert_eq!(
+            parse_args(&args),
+            Err("'lsp' takes no arguments.".to_string())
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn emit_takes_a_format_and_a_single_script() {
+        use crate::EmitFormat;
+
+        let test_cases = vec![
+            TestCase {
+                input: "run --emit=ast-json a.lox",
+                expected_output: Ok(Command::Emit(
+                    Source::File("a.lox".to_string()),
+                    EmitFormat::Ast,
+                )),
+            },
+            TestCase {
+                input: "--emit=tokens-json -e 1;",
+                expected_output: Ok(Command::Emit(
+                    Source::Code("1;".to_string()),
+                    EmitFormat::Tokens,
+                )),
+            },
+            TestCase {
+                input: "--emit=ast-json a.lox b",
+                expected_output: Err("'--emit' takes a single script.".to_string()),
+            },
+            TestCase {
+                input: "--emit=yaml a.lox",
+                expected_output: Err(
+                    "Unknown format 'yaml'. Expected 'ast-json' or 'tokens-json'.".to_string(),
+                ),
+            },
+        ];
+
+        for test_case in test_cases {
+            let args: Vec<String> = test_case
+                .input
+                .split_whitespace()
+                .map(String::from)
+                .collect();
+            assert_eq!(
+                parse_args(&args),
+                test_case.expected_output,
+                "{}",
+                test_case.input
+            );
+        }
+    }
+
+    #[test]
+    fn scripts_that_cannot_be_read_are_skipped() {
+        use crate::{check, fmt, lint, EXIT_CODE_NO_INPUT};
+
+        let path = std::env::temp_dir().join(format!("rustlox-fmt-{}.lox", std::process::id()));
+        std::fs::write(&path, "print  1;").unwrap();
+        let sources = [
+            Source::File("no-such-script.lox".to_string()),
+            Source::File(path.to_str().unwrap().to_string()),
+        ];
+
+        assert_eq!(check(&sources, false).unwrap(), EXIT_CODE_NO_INPUT);
+        assert_eq!(lint(&sources, false).unwrap(), EXIT_CODE_NO_INPUT);
+        assert_eq!(fmt(&sources, false).unwrap(), EXIT_CODE_NO_INPUT);
+        let formatted = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(formatted, "print 1;\n");
     }
 }
@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::symbol::Symbol;
+use crate::value::{Arity, Key, Map, NativeFunction, Value};
+
+// A method implemented in Rust, given the map it is called on.
+type Method = fn(&Rc<RefCell<Map>>, &[Value]) -> Result<Value, String>;
+
+/// The method of a map with the given name, bound to the map, if maps have one.
+pub fn method(map: &Rc<RefCell<Map>>, name: &Symbol) -> Option<Value> {
+    let (arity, method): (usize, Method) = match name.as_str() {
+        "keys" => (0, keys),
+        "values" => (0, values),
+        "has" => (1, has),
+        "delete" => (1, delete),
+        _ => return None,
+    };
+    let map = Rc::clone(map);
+    Some(Value::NativeFunction(Rc::new(NativeFunction {
+        name: name.clone(),
+        arity: Arity::Exact(arity),
+        function: Box::new(move |_, arguments| method(&map, arguments)),
+    })))
+}
+
+/// The value of a key, which must be in the map.
+pub fn get(map: &Rc<RefCell<Map>>, key: &Value) -> Result<Value, String> {
+    let key = Key::try_from(key)?;
+    map.borrow()
+        .get(&key)
+        .cloned()
+        .ok_or_else(|| format!("Undefined key '{}'.", key))
+}
+
+pub fn set(map: &Rc<RefCell<Map>>, key: &Value, value: Value) -> Result<(), String> {
+    map.borrow_mut().insert(Key::try_from(key)?, value);
+    Ok(())
+}
+
+/// A new map of the entries, where a later entry replaces an earlier one with the same key.
+pub fn from_entries(entries: Vec<(Value, Value)>) -> Result<Value, String> {
+    let mut map = Map::new();
+    for (key, value) in entries {
+        map.insert(Key::try_from(&key)?, value);
+    }
+    Ok(Value::Map(Rc::new(RefCell::new(map))))
+}
+
+/// Returns a list of the keys, in the order they were added. Lox has no `for ... in` loop, so this
+/// is how scripts iterate over a map, and since the list is a copy, the loop may change the map.
+fn keys(map: &Rc<RefCell<Map>>, _: &[Value]) -> Result<Value, String> {
+    let keys: Vec<Value> = map
+        .borrow()
+        .iter()
+        .map(|(key, _)| Value::from(key))
+        .collect();
+    Ok(Value::from(keys))
+}
+
+fn values(map: &Rc<RefCell<Map>>, _: &[Value]) -> Result<Value, String> {
+    let values: Vec<Value> = map
+        .borrow()
+        .iter()
+        .map(|(_, value)| value.clone())
+        .collect();
+    Ok(Value::from(values))
+}
+
+fn has(map: &Rc<RefCell<Map>>, arguments: &[Value]) -> Result<Value, String> {
+    let key = Key::try_from(&arguments[0])?;
+    Ok(Value::Bool(map.borrow().get(&key).is_some()))
+}
+
+/// Removes a key and its value, returning whether the key was in the map.
+fn delete(map: &Rc<RefCell<Map>>, arguments: &[Value]) -> Result<Value, String> {
+    let key = Key::try_from(&arguments[0])?;
+    Ok(Value::Bool(map.borrow_mut().remove(&key).is_some()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::{Key, Value};
+
+    #[test]
+    fn only_hashable_values_are_keys() {
+        assert_eq!(
+            Key::try_from(&Value::Number(-0.0)),
+            Key::try_from(&Value::Number(0.0))
+        );
+        assert_ne!(
+            Key::try_from(&Value::Number(1.0)),
+            Key::try_from(&Value::from("1"))
+        );
+        assert_eq!(
+            Key::try_from(&Value::Number(f64::NAN)),
+            Err("NaN can't be a map key.".to_string())
+        );
+        assert_eq!(
+            Key::try_from(&Value::from(vec![])),
+            Err("Map key must be nil, a boolean, a number or a string but got list.".to_string())
+        );
+    }
+}
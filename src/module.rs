@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::value::Module;
+
+/// Finds the files modules are imported from, and keeps track of the files being run and the
+/// modules already loaded, so that each module runs once and import cycles are caught.
+#[derive(Default)]
+pub struct Modules {
+    search_path: Vec<PathBuf>,
+    loaded: HashMap<PathBuf, Rc<Module>>,
+    // The files being run, each one importing the next, by their canonical path and as found.
+    running: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Modules {
+    pub fn set_search_path(&mut self, directories: Vec<PathBuf>) {
+        self.search_path = directories;
+    }
+
+    /// The file an import names, found relative to the directory of the file importing it, or
+    /// else in each directory of the search path in turn.
+    pub fn find(&self, path: &str) -> Option<PathBuf> {
+        let directory = match self.running.last() {
+            Some((_, file)) => file.parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => PathBuf::new(),
+        };
+        std::iter::once(directory)
+            .chain(self.search_path.iter().cloned())
+            .map(|directory| directory.join(path))
+            .find(|file| file.is_file())
+    }
+
+    pub fn loaded(&self, file: &Path) -> Option<Rc<Module>> {
+        let file = file.canonicalize().ok()?;
+        self.loaded.get(&file).cloned()
+    }
+
+    /// Marks a file as being run by the one running before it, failing with the chain of imports
+    /// that leads back to it when it is already running.
+    pub fn enter(&mut self, file: &Path) -> Result<(), String> {
+        let canonical = file
+            .canonicalize()
+            .map_err(|error| format!("Can't read '{}': {}.", file.display(), error))?;
+        if let Some(start) = self
+            .running
+            .iter()
+            .position(|(running, _)| *running == canonical)
+        {
+            let chain: Vec<String> = self.running[start..]
+                .iter()
+                .map(|(_, file)| file)
+                .chain(std::iter::once(&file.to_path_buf()))
+                .map(|file| file.display().to_string())
+                .collect();
+            return Err(format!("Import cycle: {}.", chain.join(" -> ")));
+        }
+        self.running.push((canonical, file.to_path_buf()));
+        Ok(())
+    }
+
+    /// Marks the file run last as finished, keeping the module it was loaded as, if any.
+    pub fn leave(&mut self, module: Option<&Rc<Module>>) {
+        let (file, _) = self.running.pop().expect("a file is being run");
+        if let Some(module) = module {
+            self.loaded.insert(file, Rc::clone(module));
+        }
+    }
+}
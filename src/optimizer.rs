@@ -0,0 +1,361 @@
+use crate::arithmetic;
+use crate::ast::{
+    ComparisonOperator, EqualityOperator, Literal, LogicalOperator, Node, ProductOperator,
+    Statement, SumOperator, UnaryOperator,
+};
+use crate::bitwise;
+use crate::fold::{fold_program, walk_node, Folder};
+use crate::symbol::Symbol;
+
+/// Folds the constant parts of expressions and drops operations that can't change their operand.
+/// An expression is only rewritten when the interpreter would give the same value for it, so
+/// anything that could fail at runtime, like `"a" - 1`, is left for the interpreter to report.
+pub struct Optimizer;
+
+impl Optimizer {
+    pub fn optimize(statements: Vec<Statement>) -> Vec<Statement> {
+        fold_program(&mut Optimizer, statements)
+    }
+}
+
+impl Folder for Optimizer {
+    fn fold_node(&mut self, node: Node) -> Node {
+        // The operands are folded first, so constants are folded from the bottom up.
+        match walk_node(self, node) {
+            Node::Grouping(expression) if literal(&expression).is_some() => *expression,
+            Node::Series(left, right) if literal(&left).is_some() => *right,
+            Node::Conditional(condition, then_value, else_value) => {
+                match literal(&condition).map(is_truthy) {
+                    Some(true) => *then_value,
+                    Some(false) => *else_value,
+                    None => Node::Conditional(condition, then_value, else_value),
+                }
+            }
+            Node::Logical(operator, left, right) => match literal(&left).map(is_truthy) {
+                // `and` stops at a falsey operand and `or` at a truthy one.
+                Some(truthy) if truthy == (operator == LogicalOperator::Or) => *left,
+                Some(_) => *right,
+                None => Node::Logical(operator, left, right),
+            },
+            Node::Equality(operator, left, right) => match (literal(&left), literal(&right)) {
+                (Some(left), Some(right)) => {
+                    boolean((left == right) == (operator == EqualityOperator::Equal))
+                }
+                _ => Node::Equality(operator, left, right),
+            },
+            // Numbers that aren't integers are left for the interpreter to report.
+            Node::Bitwise(operator, left, right) => match (number(&left), number(&right)) {
+                (Some(a), Some(b)) => match bitwise::bitwise(&operator, a, b) {
+                    Ok(result) => Node::Primary(Literal::Number(result)),
+                    Err(_) => Node::Bitwise(operator, left, right),
+                },
+                _ => Node::Bitwise(operator, left, right),
+            },
+            Node::Comparison(operator, left, right) => match (number(&left), number(&right)) {
+                (Some(left), Some(right)) => boolean(match operator {
+                    ComparisonOperator::Greater => left > right,
+                    ComparisonOperator::GreaterEqual => left >= right,
+                    ComparisonOperator::Less => left < right,
+                    ComparisonOperator::LessEqual => left <= right,
+                }),
+                _ => Node::Comparison(operator, left, right),
+            },
+            Node::Shift(operator, left, right) => match (number(&left), number(&right)) {
+                (Some(a), Some(b)) => match bitwise::shift(&operator, a, b) {
+                    Ok(result) => Node::Primary(Literal::Number(result)),
+                    Err(_) => Node::Shift(operator, left, right),
+                },
+                _ => Node::Shift(operator, left, right),
+            },
+            Node::Sum(operator, left, right) => fold_sum(operator, left, right),
+            Node::Product(operator, left, right) => fold_product(operator, left, right),
+            Node::Power(base, exponent) => match (number(&base), number(&exponent)) {
+                (Some(base), Some(exponent)) => Node::Primary(Literal::Number(base.powf(exponent))),
+                _ => Node::Power(base, exponent),
+            },
+            Node::Unary(operator, operand) => fold_unary(operator, operand),
+            node => node,
+        }
+    }
+}
+
+// `x + 0` is kept, because it turns `-0` into `0`.
+fn fold_sum(operator: SumOperator, left: Box<Node>, right: Box<Node>) -> Node {
+    match (&operator, literal(&left), literal(&right)) {
+        (SumOperator::Plus, Some(Literal::Number(a)), Some(Literal::Number(b))) => {
+            Node::Primary(Literal::Number(a + b))
+        }
+        (SumOperator::Plus, Some(Literal::String(a)), Some(Literal::String(b))) => {
+            let concatenated = format!("{}{}", a.as_str(), b.as_str());
+            Node::Primary(Literal::String(Symbol::intern(&concatenated)))
+        }
+        (SumOperator::Minus, Some(Literal::Number(a)), Some(Literal::Number(b))) => {
+            Node::Primary(Literal::Number(a - b))
+        }
+        (SumOperator::Minus, _, Some(Literal::Number(b))) if *b == 0.0 && is_number(&left) => *left,
+        _ => Node::Sum(operator, left, right),
+    }
+}
+
+fn fold_product(operator: ProductOperator, left: Box<Node>, right: Box<Node>) -> Node {
+    // Dividing by zero is left for the interpreter to report.
+    if let (Some(a), Some(b)) = (number(&left), number(&right)) {
+        if let Ok(result) = arithmetic::product(&operator, a, b) {
+            return Node::Primary(Literal::Number(result));
+        }
+    }
+    // `x % 1` and `x ~/ 1` are kept, because they drop the fraction of `x`.
+    match (&operator, number(&left), number(&right)) {
+        (ProductOperator::Star | ProductOperator::Slash, _, Some(b))
+            if b == 1.0 && is_number(&left) =>
+        {
+            *left
+        }
+        (ProductOperator::Star, Some(a), _) if a == 1.0 && is_number(&right) => *right,
+        _ => Node::Product(operator, left, right),
+    }
+}
+
+fn fold_unary(operator: UnaryOperator, operand: Box<Node>) -> Node {
+    match (&operator, literal(&operand)) {
+        (UnaryOperator::Bang, Some(literal)) => boolean(!is_truthy(literal)),
+        (UnaryOperator::Minus, Some(Literal::Number(number))) => {
+            Node::Primary(Literal::Number(-number))
+        }
+        (UnaryOperator::Tilde, Some(Literal::Number(number))) => match bitwise::not(*number) {
+            Ok(result) => Node::Primary(Literal::Number(result)),
+            Err(_) => Node::Unary(operator, operand),
+        },
+        _ => match *operand {
+            // Negating twice gives back any number, and not-ing twice any boolean.
+            Node::Unary(UnaryOperator::Minus, inner)
+                if operator == UnaryOperator::Minus && is_number(&inner) =>
+            {
+                *inner
+            }
+            Node::Unary(UnaryOperator::Bang, inner)
+                if operator == UnaryOperator::Bang && is_boolean(&inner) =>
+            {
+                *inner
+            }
+            operand => Node::Unary(operator, Box::new(operand)),
+        },
+    }
+}
+
+fn literal(node: &Node) -> Option<&Literal> {
+    match node {
+        Node::Primary(literal) => Some(literal),
+        _ => None,
+    }
+}
+
+fn number(node: &Node) -> Option<f64> {
+    match literal(node) {
+        Some(Literal::Number(number)) => Some(*number),
+        _ => None,
+    }
+}
+
+fn boolean(value: bool) -> Node {
+    Node::Primary(if value { Literal::True } else { Literal::False })
+}
+
+fn is_truthy(literal: &Literal) -> bool {
+    !matches!(literal, Literal::Nil | Literal::False)
+}
+
+// Whether the node can only evaluate to a number, if it doesn't fail.
+fn is_number(node: &Node) -> bool {
+    match node {
+        Node::Primary(Literal::Number(_))
+        | Node::Unary(UnaryOperator::Minus | UnaryOperator::Tilde, _)
+        | Node::Bitwise(_, _, _)
+        | Node::Shift(_, _, _)
+        | Node::Sum(SumOperator::Minus, _, _)
+        | Node::Product(_, _, _)
+        | Node::Power(_, _) => true,
+        Node::Sum(SumOperator::Plus, left, right) => is_number(left) || is_number(right),
+        Node::Grouping(expression) => is_number(expression),
+        _ => false,
+    }
+}
+
+// Whether the node can only evaluate to a boolean, if it doesn't fail.
+fn is_boolean(node: &Node) -> bool {
+    match node {
+        Node::Primary(Literal::True | Literal::False)
+        | Node::Equality(_, _, _)
+        | Node::Comparison(_, _, _)
+        | Node::Unary(UnaryOperator::Bang, _) => true,
+        Node::Grouping(expression) => is_boolean(expression),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fold::Folder;
+    use crate::interpreter::Interpreter;
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::printer::AstPrinter;
+    use crate::scanner::Scanner;
+
+    use super::Optimizer;
+
+    struct TestCase<'a> {
+        input: &'a str,
+        expected_output: &'a str,
+    }
+
+    #[test]
+    fn constant_expressions_are_folded() {
+        let test_cases = [
+            TestCase {
+                input: "1 + 2 * 3",
+                expected_output: "7",
+            },
+            TestCase {
+                input: "(1 + 2) * x",
+                expected_output: "(* 3 x)",
+            },
+            TestCase {
+                input: "!true",
+                expected_output: "false",
+            },
+            TestCase {
+                input: "-(4 / 2) <= 1 == !nil",
+                expected_output: "true",
+            },
+            TestCase {
+                input: "\"a\" + \"b\" + \"c\" == \"abc\"",
+                expected_output: "true",
+            },
+            TestCase {
+                input: "nil or x",
+                expected_output: "x",
+            },
+            TestCase {
+                input: "0 and x",
+                expected_output: "x",
+            },
+            TestCase {
+                input: "false and x",
+                expected_output: "false",
+            },
+            TestCase {
+                input: "1, x",
+                expected_output: "x",
+            },
+            TestCase {
+                input: "2 ** 3 % 5 ~/ 2",
+                expected_output: "1",
+            },
+            TestCase {
+                input: "1 < 2 ? x : y",
+                expected_output: "x",
+            },
+            TestCase {
+                input: "nil ? x : y",
+                expected_output: "y",
+            },
+            TestCase {
+                input: "(1 << 3 | 5) ^ ~0 & x",
+                expected_output: "(^ 13 (& -1 x))",
+            },
+        ];
+
+        for test_case in test_cases {
+            let tokens = Scanner::new().scan(test_case.input).unwrap();
+            let node = RecursiveDescentParser::new().parse(&tokens).unwrap();
+            let output = AstPrinter::print(&Optimizer.fold_node(node));
+            assert_eq!(output, test_case.expected_output, "{}", test_case.input);
+        }
+    }
+
+    #[test]
+    fn identities_are_only_simplified_when_they_cant_change_the_value() {
+        let test_cases = [
+            TestCase {
+                input: "-x - 0",
+                expected_output: "(- x)",
+            },
+            TestCase {
+                input: "1 * (x / 1)",
+                expected_output: "(group (/ x 1))",
+            },
+            TestCase {
+                input: "1 * (x * 2)",
+                expected_output: "(group (* x 2))",
+            },
+            TestCase {
+                input: "- -(x - 1)",
+                expected_output: "(group (- x 1))",
+            },
+            TestCase {
+                input: "!!(a < b)",
+                expected_output: "(group (< a b))",
+            },
+            // `x` may not be a number or a boolean, which would be an error or change its value.
+            TestCase {
+                input: "x * 1",
+                expected_output: "(* x 1)",
+            },
+            // The fraction of `x` is dropped.
+            TestCase {
+                input: "-x ~/ 1 + -x % 1",
+                expected_output: "(+ (~/ (- x) 1) (% (- x) 1))",
+            },
+            TestCase {
+                input: "- -x",
+                expected_output: "(- (- x))",
+            },
+            TestCase {
+                input: "- -(x & 1)",
+                expected_output: "(group (& x 1))",
+            },
+            TestCase {
+                input: "!!x",
+                expected_output: "(! (! x))",
+            },
+            // `-0 + 0` is `0`.
+            TestCase {
+                input: "-x + 0",
+                expected_output: "(+ (- x) 0)",
+            },
+        ];
+
+        for test_case in test_cases {
+            let tokens = Scanner::new().scan(test_case.input).unwrap();
+            let node = RecursiveDescentParser::new().parse(&tokens).unwrap();
+            let output = AstPrinter::print(&Optimizer.fold_node(node));
+            assert_eq!(output, test_case.expected_output, "{}", test_case.input);
+        }
+    }
+
+    #[test]
+    fn expressions_that_fail_are_not_folded() {
+        for input in [
+            "\"a\" - 1",
+            "-\"a\"",
+            "1 < nil",
+            "\"a\" + 1",
+            "true * 2",
+            "1.5 | 0",
+            "~0.5",
+            "1 >> 64",
+            "5 % 0",
+        ] {
+            let tokens = Scanner::new().scan(input).unwrap();
+            let node = RecursiveDescentParser::new().parse(&tokens).unwrap();
+            let expected_output = AstPrinter::print(&node);
+            assert_eq!(
+                AstPrinter::print(&Optimizer.fold_node(node)),
+                expected_output
+            );
+
+            assert!(Interpreter::new().eval_str(input).is_err(), "{}", input);
+        }
+    }
+}
@@ -1,23 +1,48 @@
 pub mod recursive_descent;
 
-use crate::ast::Node;
+use crate::ast::{Node, Statement};
+use crate::cst::{self, SyntaxNode};
 use crate::token::Token;
 
 pub trait Parse<'a> {
+    /// Parses a single expression spanning all of the tokens.
     fn parse(&mut self, tokens: &'a [Token]) -> Result<Node, Error>;
+
+    /// Parses a sequence of declarations into a syntax tree that keeps all of the tokens,
+    /// recovering after each error so that all of them are reported.
+    fn parse_syntax(&mut self, tokens: &'a [Token]) -> Result<SyntaxNode, Vec<Error>>;
+
+    /// Parses a sequence of declarations, recovering after each error so that all of them are
+    /// reported.
+    fn parse_program(&mut self, tokens: &'a [Token]) -> Result<Vec<Statement>, Vec<Error>> {
+        self.parse_syntax(tokens)
+            .map(|root| cst::lower::program(&root))
+    }
 }
 
 #[derive(Debug)]
 pub struct Error {
     pub line_number: i32,
     pub message: String,
+    /// Whether the tokens ran out before the construct being parsed was complete, meaning more
+    /// input could still make it valid.
+    pub at_end_of_input: bool,
 }
 
 impl Error {
     fn new(line_number: i32, message: &str) -> Error {
         Error {
-            line_number: line_number,
+            line_number,
+            message: message.to_string(),
+            at_end_of_input: false,
+        }
+    }
+
+    fn at_end_of_input(line_number: i32, message: &str) -> Error {
+        Error {
+            line_number,
             message: message.to_string(),
+            at_end_of_input: true,
         }
     }
 }
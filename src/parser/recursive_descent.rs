@@ -1,849 +1,1438 @@
 use super::{Error, Parse};
-use crate::ast::{
-    ComparisonOperator, EqualityOperator, Literal, Node, ProductOperator, SumOperator,
-    UnaryOperator,
-};
+use crate::ast::Node;
+use crate::cst::{self, Checkpoint, GreenNodeBuilder, SyntaxKind, SyntaxNode};
 use crate::token::{Token, TokenType};
 
+const MAX_ARGUMENTS: usize = 255;
+
+const COMPOUND_ASSIGNMENT_OPERATORS: [TokenType; 12] = [
+    TokenType::PlusEqual,
+    TokenType::MinusEqual,
+    TokenType::StarEqual,
+    TokenType::SlashEqual,
+    TokenType::PercentEqual,
+    TokenType::StarStarEqual,
+    TokenType::TildeSlashEqual,
+    TokenType::AmpersandEqual,
+    TokenType::PipeEqual,
+    TokenType::CaretEqual,
+    TokenType::LessLessEqual,
+    TokenType::GreaterGreaterEqual,
+];
+
+/// Parses tokens into a lossless syntax tree, from which the AST is derived. Trivia tokens are
+/// kept in the tree but otherwise skipped.
 pub struct RecursiveDescentParser<'a> {
     cursor: usize,
-    tokens: &'a [Token<'a>],
+    // The tokens the grammar is made of, leaving out trivia.
+    tokens: Vec<&'a Token<'a>>,
+    // All of the tokens, and the index in it of each token in `tokens`.
+    all_tokens: &'a [Token<'a>],
+    positions: Vec<usize>,
+    // How many of `all_tokens` have been added to the tree, and the line the last one ended on.
+    emitted: usize,
+    line_number: i32,
+    builder: GreenNodeBuilder,
+    function_depth: usize,
 }
 
-impl RecursiveDescentParser<'_> {
+impl Default for RecursiveDescentParser<'_> {
+    fn default() -> Self {
+        RecursiveDescentParser::new()
+    }
+}
+
+impl<'a> RecursiveDescentParser<'a> {
     pub fn new() -> Self {
         RecursiveDescentParser {
             cursor: 0,
-            tokens: &[],
+            tokens: Vec::new(),
+            all_tokens: &[],
+            positions: Vec::new(),
+            emitted: 0,
+            line_number: 1,
+            builder: GreenNodeBuilder::new(),
+            function_depth: 0,
         }
     }
 
-    fn parse_expression(&mut self) -> Result<Node, Error> {
-        let node = Node::Expression(Box::new(self.parse_series()?));
-        Ok(node)
+    fn reset(&mut self, tokens: &'a [Token<'a>]) {
+        self.cursor = 0;
+        self.positions = (0..tokens.len())
+            .filter(|&index| !tokens[index].token_type.is_trivia())
+            .collect();
+        self.tokens = self.positions.iter().map(|&index| &tokens[index]).collect();
+        self.all_tokens = tokens;
+        self.emitted = 0;
+        self.line_number = 1;
+        self.builder = GreenNodeBuilder::new();
+        self.function_depth = 0;
     }
 
-    fn parse_series(&mut self) -> Result<Node, Error> {
-        let mut node = self.parse_equality()?;
+    fn parse_declaration(&mut self) -> Result<(), Error> {
+        match self.tokens[self.cursor].token_type {
+            TokenType::Fun => {
+                self.start_node(SyntaxKind::FunctionDeclaration);
+                self.bump();
+                self.parse_function("function")?;
+                self.builder.finish_node();
+                Ok(())
+            }
+            TokenType::Class => self.parse_class_declaration(),
+            TokenType::Var => self.parse_var_declaration(),
+            TokenType::Import => self.parse_import_declaration(),
+            TokenType::Export => self.parse_export_declaration(),
+            _ => self.parse_statement(),
+        }
+    }
 
-        while self.cursor < self.tokens.len() {
-            match self.tokens[self.cursor].token_type {
-                TokenType::Comma => {
-                    self.cursor += 1;
-                    node = Node::Series(Box::new(node), Box::new(self.parse_equality()?));
+    // Parses `import "path" as name;`, or `import { a, b } from "path";` to import only some of
+    // what the module exports.
+    fn parse_import_declaration(&mut self) -> Result<(), Error> {
+        self.start_node(SyntaxKind::ImportDeclaration);
+        self.bump();
+        if self.advance_if(TokenType::LeftBrace) {
+            loop {
+                self.consume(TokenType::Identifier, "Expect name to import.")?;
+                if !self.advance_if(TokenType::Comma) {
+                    break;
                 }
-                _ => break,
             }
+            self.consume(TokenType::RightBrace, "Expect '}' after imported names.")?;
+            self.consume(TokenType::From, "Expect 'from' after imported names.")?;
+            self.consume(TokenType::String, "Expect module path after 'from'.")?;
+        } else {
+            self.consume(TokenType::String, "Expect module path after 'import'.")?;
+            self.consume(TokenType::As, "Expect 'as' after module path.")?;
+            self.consume(TokenType::Identifier, "Expect module name after 'as'.")?;
         }
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        self.builder.finish_node();
+        Ok(())
+    }
 
-        Ok(node)
+    fn parse_export_declaration(&mut self) -> Result<(), Error> {
+        self.start_node(SyntaxKind::ExportDeclaration);
+        self.bump();
+        if self.cursor >= self.tokens.len() {
+            return Err(self.end_of_input_error());
+        }
+        match self.tokens[self.cursor].token_type {
+            TokenType::Fun | TokenType::Class | TokenType::Var => self.parse_declaration()?,
+            _ => return Err(self.error_at_cursor("Expect 'var', 'fun' or 'class' after 'export'.")),
+        }
+        self.builder.finish_node();
+        Ok(())
     }
 
-    fn parse_equality(&mut self) -> Result<Node, Error> {
-        let mut node = self.parse_comparison()?;
+    // Parses what follows `fun` in a function declaration, which is all of a method.
+    fn parse_function(&mut self, kind: &str) -> Result<(), Error> {
+        self.consume(TokenType::Identifier, &format!("Expect {} name.", kind))?;
 
-        while self.cursor < self.tokens.len() {
-            match self.tokens[self.cursor].token_type {
-                TokenType::EqualEqual => {
-                    self.cursor += 1;
-                    node = Node::Equality(
-                        EqualityOperator::Equal,
-                        Box::new(node),
-                        Box::new(self.parse_comparison()?),
-                    );
+        self.start_node(SyntaxKind::ParameterList);
+        self.consume(
+            TokenType::LeftParen,
+            &format!("Expect '(' after {} name.", kind),
+        )?;
+        let mut parameters = 0;
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if parameters >= MAX_ARGUMENTS {
+                    return Err(self.error_at_cursor(&format!(
+                        "Can't have more than {} parameters.",
+                        MAX_ARGUMENTS
+                    )));
                 }
-                TokenType::BangEqual => {
-                    self.cursor += 1;
-                    node = Node::Equality(
-                        EqualityOperator::NotEqual,
-                        Box::new(node),
-                        Box::new(self.parse_comparison()?),
-                    );
+                self.consume(TokenType::Identifier, "Expect parameter name.")?;
+                self.parse_type_annotation()?;
+                parameters += 1;
+                if !self.advance_if(TokenType::Comma) {
+                    break;
                 }
-                _ => break,
             }
         }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        self.builder.finish_node();
+        self.parse_type_annotation()?;
 
-        Ok(node)
+        self.function_depth += 1;
+        let body = self.parse_block("Expect '{' before function body.");
+        self.function_depth -= 1;
+        body
     }
 
-    fn parse_comparison(&mut self) -> Result<Node, Error> {
-        let mut node = self.parse_sum()?;
+    fn parse_class_declaration(&mut self) -> Result<(), Error> {
+        self.start_node(SyntaxKind::ClassDeclaration);
+        self.bump();
+        self.consume(TokenType::Identifier, "Expect class name.")?;
 
-        while self.cursor < self.tokens.len() {
-            match self.tokens[self.cursor].token_type {
-                TokenType::Greater => {
-                    self.cursor += 1;
-                    node = Node::Comparison(
-                        ComparisonOperator::Greater,
-                        Box::new(node),
-                        Box::new(self.parse_sum()?),
-                    )
+        if self.advance_if(TokenType::Less) {
+            self.start_node(SyntaxKind::Variable);
+            self.consume(TokenType::Identifier, "Expect superclass name.")?;
+            self.builder.finish_node();
+        }
+
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+        while self.cursor < self.tokens.len() && !self.check(TokenType::RightBrace) {
+            self.start_node(SyntaxKind::Method);
+            self.parse_function("method")?;
+            self.builder.finish_node();
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn parse_var_declaration(&mut self) -> Result<(), Error> {
+        self.start_node(SyntaxKind::VarDeclaration);
+        self.bump();
+        self.consume(TokenType::Identifier, "Expect variable name.")?;
+        self.parse_type_annotation()?;
+
+        if self.advance_if(TokenType::Equal) {
+            self.parse_expression()?;
+        }
+        self.consume(
+            TokenType::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    // Parses the type a declaration is annotated with, if there is one.
+    fn parse_type_annotation(&mut self) -> Result<(), Error> {
+        if !self.check(TokenType::Colon) {
+            return Ok(());
+        }
+        self.start_node(SyntaxKind::TypeAnnotation);
+        self.bump();
+        self.consume(TokenType::Identifier, "Expect type name after ':'.")?;
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn parse_statement(&mut self) -> Result<(), Error> {
+        match self.tokens[self.cursor].token_type {
+            TokenType::Print => {
+                self.start_node(SyntaxKind::PrintStatement);
+                self.bump();
+                self.parse_expression()?;
+                self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
+            }
+            TokenType::LeftBrace => return self.parse_block("Expect '{' before block."),
+            TokenType::If => return self.parse_if_statement(),
+            TokenType::While => {
+                self.start_node(SyntaxKind::WhileStatement);
+                self.bump();
+                self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+                self.parse_expression()?;
+                self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+                self.parse_statement_at_cursor()?;
+            }
+            TokenType::For => return self.parse_for_statement(),
+            TokenType::Return => {
+                if self.function_depth == 0 {
+                    return Err(self.error_at_cursor("Can't return from top-level code."));
                 }
-                TokenType::GreaterEqual => {
-                    self.cursor += 1;
-                    node = Node::Comparison(
-                        ComparisonOperator::GreaterEqual,
-                        Box::new(node),
-                        Box::new(self.parse_sum()?),
-                    )
+                self.start_node(SyntaxKind::ReturnStatement);
+                self.bump();
+                if !self.check(TokenType::Semicolon) {
+                    self.parse_expression()?;
                 }
-                TokenType::Less => {
-                    self.cursor += 1;
-                    node = Node::Comparison(
-                        ComparisonOperator::Less,
-                        Box::new(node),
-                        Box::new(self.parse_sum()?),
-                    )
+                self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+            }
+            TokenType::Try => return self.parse_try_statement(),
+            TokenType::Break | TokenType::Continue => {
+                let keyword = self.tokens[self.cursor].lexeme;
+                self.start_node(match self.tokens[self.cursor].token_type {
+                    TokenType::Break => SyntaxKind::BreakStatement,
+                    _ => SyntaxKind::ContinueStatement,
+                });
+                self.bump();
+                self.advance_if(TokenType::Identifier);
+                self.consume(
+                    TokenType::Semicolon,
+                    &format!("Expect ';' after '{}'.", keyword),
+                )?;
+            }
+            TokenType::Identifier if self.check_next(TokenType::Colon) => {
+                self.start_node(SyntaxKind::LabelledStatement);
+                self.bump();
+                self.bump();
+                if self.cursor >= self.tokens.len() {
+                    return Err(self.end_of_input_error());
                 }
-                TokenType::LessEqual => {
-                    self.cursor += 1;
-                    node = Node::Comparison(
-                        ComparisonOperator::LessEqual,
-                        Box::new(node),
-                        Box::new(self.parse_sum()?),
-                    )
+                if !self.check(TokenType::While) && !self.check(TokenType::For) {
+                    return Err(self.error_at_cursor("Expect loop after label."));
                 }
-                _ => break,
+                self.parse_statement()?;
+            }
+            TokenType::Throw => {
+                self.start_node(SyntaxKind::ThrowStatement);
+                self.bump();
+                self.parse_expression()?;
+                self.consume(TokenType::Semicolon, "Expect ';' after thrown value.")?;
+            }
+            _ => {
+                self.start_node(SyntaxKind::ExpressionStatement);
+                self.parse_expression()?;
+                self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
             }
         }
+        self.builder.finish_node();
+        Ok(())
+    }
 
-        Ok(node)
+    // Parses the statement forming the body of a control flow statement, which may be missing
+    // because the input ended early.
+    fn parse_statement_at_cursor(&mut self) -> Result<(), Error> {
+        if self.cursor >= self.tokens.len() {
+            return Err(self.end_of_input_error());
+        }
+        self.parse_statement()
     }
 
-    fn parse_sum(&mut self) -> Result<Node, Error> {
-        let mut node = self.parse_product()?;
+    fn parse_block(&mut self, message: &str) -> Result<(), Error> {
+        self.start_node(SyntaxKind::Block);
+        self.consume(TokenType::LeftBrace, message)?;
+        while self.cursor < self.tokens.len() && !self.check(TokenType::RightBrace) {
+            self.parse_declaration()?;
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        self.builder.finish_node();
+        Ok(())
+    }
 
-        while self.cursor < self.tokens.len() {
-            match self.tokens[self.cursor].token_type {
-                TokenType::Plus => {
-                    self.cursor += 1;
-                    node = Node::Sum(
-                        SumOperator::Plus,
-                        Box::new(node),
-                        Box::new(self.parse_product()?),
-                    )
-                }
-                TokenType::Minus => {
-                    self.cursor += 1;
-                    node = Node::Sum(
-                        SumOperator::Minus,
-                        Box::new(node),
-                        Box::new(self.parse_product()?),
-                    )
-                }
-                _ => break,
+    fn parse_if_statement(&mut self) -> Result<(), Error> {
+        self.start_node(SyntaxKind::IfStatement);
+        self.bump();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
+        self.parse_expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+
+        self.parse_statement_at_cursor()?;
+        if self.advance_if(TokenType::Else) {
+            self.parse_statement_at_cursor()?;
+        }
+
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    fn parse_try_statement(&mut self) -> Result<(), Error> {
+        self.start_node(SyntaxKind::TryStatement);
+        self.bump();
+        self.parse_block("Expect '{' after 'try'.")?;
+
+        let has_catch = self.advance_if(TokenType::Catch);
+        if has_catch {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            self.consume(TokenType::Identifier, "Expect variable name.")?;
+            self.consume(TokenType::RightParen, "Expect ')' after catch variable.")?;
+            self.parse_block("Expect '{' after catch variable.")?;
+        }
+        if !has_catch || self.check(TokenType::Finally) {
+            self.consume(
+                TokenType::Finally,
+                "Expect 'catch' or 'finally' after try block.",
+            )?;
+            self.parse_block("Expect '{' after 'finally'.")?;
+        }
+
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    // The clauses of a for loop are kept as they are, and the interpreter scopes the initializer.
+    fn parse_for_statement(&mut self) -> Result<(), Error> {
+        self.start_node(SyntaxKind::ForStatement);
+        self.bump();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+
+        if self.check(TokenType::Var) {
+            self.parse_var_declaration()?;
+        } else if !self.advance_if(TokenType::Semicolon) {
+            self.start_node(SyntaxKind::ExpressionStatement);
+            self.parse_expression()?;
+            self.consume(TokenType::Semicolon, "Expect ';' after loop initializer.")?;
+            self.builder.finish_node();
+        }
+
+        if !self.check(TokenType::Semicolon) {
+            self.parse_expression()?;
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after loop condition.")?;
+
+        if !self.check(TokenType::RightParen) {
+            self.parse_expression()?;
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
+
+        self.parse_statement_at_cursor()?;
+        self.builder.finish_node();
+        Ok(())
+    }
+
+    // Expressions return the kind of the node they built, which tells assignment whether its
+    // target is a variable, a property or an element of a list.
+    fn parse_expression(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_series()
+    }
+
+    fn parse_series(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(
+            SyntaxKind::Series,
+            &[TokenType::Comma],
+            Self::parse_assignment,
+        )
+    }
+
+    fn parse_assignment(&mut self) -> Result<SyntaxKind, Error> {
+        let checkpoint = self.checkpoint();
+        let kind = self.parse_conditional()?;
+
+        let is_compound = self.cursor < self.tokens.len()
+            && COMPOUND_ASSIGNMENT_OPERATORS.contains(&self.tokens[self.cursor].token_type);
+        if self.check(TokenType::Equal) || is_compound {
+            let operator_line_number = self.tokens[self.cursor].line_number;
+            let assign_kind = match kind {
+                _ if is_compound => SyntaxKind::CompoundAssign,
+                SyntaxKind::Get => SyntaxKind::Set,
+                SyntaxKind::Index => SyntaxKind::SetIndex,
+                _ => SyntaxKind::Assign,
+            };
+            self.builder.start_node_at(checkpoint, assign_kind);
+            self.bump();
+            self.parse_assignment()?;
+            self.builder.finish_node();
+            return match kind {
+                SyntaxKind::Variable | SyntaxKind::Get | SyntaxKind::Index => Ok(assign_kind),
+                _ => Err(Error::new(
+                    operator_line_number,
+                    "Invalid assignment target.",
+                )),
+            };
+        }
+
+        Ok(kind)
+    }
+
+    // The branches of a conditional are parsed at the precedence of assignment and of conditionals,
+    // so that `a ? b : c ? d : e` chains to the right.
+    fn parse_conditional(&mut self) -> Result<SyntaxKind, Error> {
+        let checkpoint = self.checkpoint();
+        let kind = self.parse_or()?;
+
+        if self.check(TokenType::Question) {
+            self.builder
+                .start_node_at(checkpoint, SyntaxKind::Conditional);
+            self.bump();
+            self.parse_assignment()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional.",
+            )?;
+            self.parse_conditional()?;
+            self.builder.finish_node();
+            return Ok(SyntaxKind::Conditional);
+        }
+
+        Ok(kind)
+    }
+
+    fn parse_or(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(SyntaxKind::Logical, &[TokenType::Or], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(SyntaxKind::Logical, &[TokenType::And], Self::parse_bit_or)
+    }
+
+    // The bitwise operators bind looser than equality and comparison, as they do in C.
+    fn parse_bit_or(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(SyntaxKind::Binary, &[TokenType::Pipe], Self::parse_bit_xor)
+    }
+
+    fn parse_bit_xor(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(SyntaxKind::Binary, &[TokenType::Caret], Self::parse_bit_and)
+    }
+
+    fn parse_bit_and(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(
+            SyntaxKind::Binary,
+            &[TokenType::Ampersand],
+            Self::parse_equality,
+        )
+    }
+
+    fn parse_equality(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(
+            SyntaxKind::Binary,
+            &[TokenType::EqualEqual, TokenType::BangEqual],
+            Self::parse_comparison,
+        )
+    }
+
+    fn parse_comparison(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(
+            SyntaxKind::Binary,
+            &[
+                TokenType::Greater,
+                TokenType::GreaterEqual,
+                TokenType::Less,
+                TokenType::LessEqual,
+            ],
+            Self::parse_shift,
+        )
+    }
+
+    fn parse_shift(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(
+            SyntaxKind::Binary,
+            &[TokenType::LessLess, TokenType::GreaterGreater],
+            Self::parse_sum,
+        )
+    }
+
+    fn parse_sum(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(
+            SyntaxKind::Binary,
+            &[TokenType::Plus, TokenType::Minus],
+            Self::parse_product,
+        )
+    }
+
+    fn parse_product(&mut self) -> Result<SyntaxKind, Error> {
+        self.parse_binary(
+            SyntaxKind::Binary,
+            &[
+                TokenType::Star,
+                TokenType::Slash,
+                TokenType::Percent,
+                TokenType::TildeSlash,
+            ],
+            Self::parse_unary,
+        )
+    }
+
+    // Parses a left associative operation of one precedence level, wrapping the operands parsed
+    // so far in a new node at each operator.
+    fn parse_binary(
+        &mut self,
+        kind: SyntaxKind,
+        operators: &[TokenType],
+        parse_operand: fn(&mut Self) -> Result<SyntaxKind, Error>,
+    ) -> Result<SyntaxKind, Error> {
+        let checkpoint = self.checkpoint();
+        let mut node_kind = parse_operand(self)?;
+
+        while self.cursor < self.tokens.len()
+            && operators.contains(&self.tokens[self.cursor].token_type)
+        {
+            self.builder.start_node_at(checkpoint, kind);
+            self.bump();
+            parse_operand(self)?;
+            self.builder.finish_node();
+            node_kind = kind;
+        }
+
+        Ok(node_kind)
+    }
+
+    fn parse_unary(&mut self) -> Result<SyntaxKind, Error> {
+        if self.cursor >= self.tokens.len() {
+            return Err(self.end_of_input_error());
+        }
+
+        match self.tokens[self.cursor].token_type {
+            TokenType::Bang | TokenType::Minus | TokenType::Tilde => {
+                self.start_node(SyntaxKind::Unary);
+                self.bump();
+                self.parse_unary()?;
+                self.builder.finish_node();
+                Ok(SyntaxKind::Unary)
+            }
+            TokenType::PlusPlus | TokenType::MinusMinus => {
+                let operator = self.tokens[self.cursor];
+                self.start_node(SyntaxKind::Increment);
+                self.bump();
+                let kind = self.parse_unary()?;
+                self.builder.finish_node();
+                increment_target(operator, kind)
             }
+            _ => self.parse_power(),
+        }
+    }
+
+    // The exponent is parsed as a unary expression, which makes `**` right associative and lets
+    // it bind tighter than a minus before it but not after it, so `-2 ** -2` is `-(2 ** (-2))`.
+    fn parse_power(&mut self) -> Result<SyntaxKind, Error> {
+        let checkpoint = self.checkpoint();
+        let kind = self.parse_postfix()?;
+
+        if self.check(TokenType::StarStar) {
+            self.builder.start_node_at(checkpoint, SyntaxKind::Binary);
+            self.bump();
+            self.parse_unary()?;
+            self.builder.finish_node();
+            return Ok(SyntaxKind::Binary);
         }
 
-        Ok(node)
+        Ok(kind)
     }
 
-    fn parse_product(&mut self) -> Result<Node, Error> {
-        let mut node = self.parse_unary()?;
+    fn parse_postfix(&mut self) -> Result<SyntaxKind, Error> {
+        let checkpoint = self.checkpoint();
+        let kind = self.parse_call()?;
 
-        while self.cursor < self.tokens.len() {
-            match self.tokens[self.cursor].token_type {
-                TokenType::Star => {
-                    self.cursor += 1;
-                    node = Node::Product(
-                        ProductOperator::Star,
-                        Box::new(node),
-                        Box::new(self.parse_unary()?),
-                    )
-                }
-                TokenType::Slash => {
-                    self.cursor += 1;
-                    node = Node::Product(
-                        ProductOperator::Slash,
-                        Box::new(node),
-                        Box::new(self.parse_unary()?),
-                    )
-                }
-                _ => break,
+        if self.check(TokenType::PlusPlus) || self.check(TokenType::MinusMinus) {
+            let operator = self.tokens[self.cursor];
+            self.builder
+                .start_node_at(checkpoint, SyntaxKind::Increment);
+            self.bump();
+            self.builder.finish_node();
+            return increment_target(operator, kind);
+        }
+
+        Ok(kind)
+    }
+
+    fn parse_call(&mut self) -> Result<SyntaxKind, Error> {
+        let checkpoint = self.checkpoint();
+        let mut kind = self.parse_primary()?;
+
+        loop {
+            if self.check(TokenType::LeftParen) {
+                self.builder.start_node_at(checkpoint, SyntaxKind::Call);
+                self.start_node(SyntaxKind::ArgumentList);
+                self.bump();
+                self.parse_arguments()?;
+                self.builder.finish_node();
+                self.builder.finish_node();
+                kind = SyntaxKind::Call;
+            } else if self.check(TokenType::Dot) {
+                self.builder.start_node_at(checkpoint, SyntaxKind::Get);
+                self.bump();
+                self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                self.builder.finish_node();
+                kind = SyntaxKind::Get;
+            } else if self.check(TokenType::LeftBracket) {
+                kind = self.parse_subscript(checkpoint)?;
+            } else {
+                break;
             }
         }
 
-        Ok(node)
+        Ok(kind)
     }
 
-    fn parse_unary(&mut self) -> Result<Node, Error> {
-        let node = match self.tokens[self.cursor].token_type {
-            TokenType::Bang => {
-                self.cursor += 1;
-                Node::Unary(UnaryOperator::Bang, Box::new(self.parse_unary()?))
+    // Parses the brackets after an expression, which index it, or slice it when there is a colon
+    // between them. The node is only started once the brackets are closed, when its kind is known.
+    fn parse_subscript(&mut self, checkpoint: Checkpoint) -> Result<SyntaxKind, Error> {
+        self.bump();
+        let mut kind = SyntaxKind::Index;
+        if !self.check(TokenType::Colon) {
+            self.parse_expression()?;
+        }
+        if self.advance_if(TokenType::Colon) {
+            kind = SyntaxKind::Slice;
+            if !self.check(TokenType::RightBracket) {
+                self.parse_expression()?;
             }
-            TokenType::Minus => {
-                self.cursor += 1;
-                Node::Unary(UnaryOperator::Minus, Box::new(self.parse_unary()?))
+        }
+        self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+        self.builder.start_node_at(checkpoint, kind);
+        self.builder.finish_node();
+        Ok(kind)
+    }
+
+    // Arguments are parsed one precedence level below the series operator, so that commas separate
+    // arguments instead of chaining them into a single series expression.
+    fn parse_arguments(&mut self) -> Result<(), Error> {
+        if self.advance_if(TokenType::RightParen) {
+            return Ok(());
+        }
+
+        let mut arguments = 0;
+        loop {
+            if arguments >= MAX_ARGUMENTS {
+                return Err(self.error_at_cursor(&format!(
+                    "Can't have more than {} arguments.",
+                    MAX_ARGUMENTS
+                )));
             }
-            _ => self.parse_primary()?,
-        };
+            self.parse_assignment()?;
+            arguments += 1;
 
-        Ok(node)
+            if self.cursor >= self.tokens.len() {
+                return Err(self.end_of_input_error());
+            }
+
+            match self.tokens[self.cursor].token_type {
+                TokenType::Comma => self.bump(),
+                TokenType::RightParen => {
+                    self.bump();
+                    return Ok(());
+                }
+                _ => {
+                    return Err(Error::new(
+                        self.tokens[self.cursor].line_number,
+                        &format!("Unexpected token: {}", self.tokens[self.cursor].lexeme),
+                    ))
+                }
+            }
+        }
     }
 
-    fn parse_primary(&mut self) -> Result<Node, Error> {
-        match self.tokens[self.cursor].token_type {
-            TokenType::Nil => {
-                self.cursor += 1;
-                Ok(Node::Primary(Literal::Nil))
+    // Elements are separated by commas like arguments.
+    fn parse_elements(&mut self) -> Result<(), Error> {
+        if self.advance_if(TokenType::RightBracket) {
+            return Ok(());
+        }
+        loop {
+            self.parse_assignment()?;
+            if !self.advance_if(TokenType::Comma) {
+                break;
             }
-            TokenType::True => {
-                self.cursor += 1;
-                Ok(Node::Primary(Literal::True))
+        }
+        self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+        Ok(())
+    }
+
+    // Entries are separated by commas like elements, with a colon between each key and its value.
+    fn parse_entries(&mut self) -> Result<(), Error> {
+        if self.advance_if(TokenType::RightBrace) {
+            return Ok(());
+        }
+        loop {
+            self.parse_assignment()?;
+            self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+            self.parse_assignment()?;
+            if !self.advance_if(TokenType::Comma) {
+                break;
             }
-            TokenType::False => {
-                self.cursor += 1;
-                Ok(Node::Primary(Literal::False))
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+        Ok(())
+    }
+
+    fn parse_primary(&mut self) -> Result<SyntaxKind, Error> {
+        let kind = match self.tokens[self.cursor].token_type {
+            TokenType::Nil | TokenType::True | TokenType::False | TokenType::String => {
+                SyntaxKind::Literal
             }
             TokenType::Number => {
-                if let Ok(number) = self.tokens[self.cursor].lexeme.parse::<f64>() {
-                    self.cursor += 1;
-                    Ok(Node::Primary(Literal::Number(number)))
-                } else {
-                    Err(Error::new(
+                if self.tokens[self.cursor].lexeme.parse::<f64>().is_err() {
+                    return Err(Error::new(
                         self.tokens[self.cursor].line_number,
                         &format!(
                             "Failed to parse number: {:?}",
                             self.tokens[self.cursor].lexeme
                         ),
-                    ))
+                    ));
                 }
+                SyntaxKind::Literal
             }
-            TokenType::String => {
-                let string = self.tokens[self.cursor].lexeme.to_string();
-                self.cursor += 1;
-                Ok(Node::Primary(Literal::String(string)))
+            TokenType::Identifier => SyntaxKind::Variable,
+            TokenType::This => SyntaxKind::This,
+            TokenType::Super => {
+                self.start_node(SyntaxKind::Super);
+                self.bump();
+                self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+                self.consume(TokenType::Identifier, "Expect superclass method name.")?;
+                self.builder.finish_node();
+                return Ok(SyntaxKind::Super);
+            }
+            TokenType::LeftBracket => {
+                self.start_node(SyntaxKind::List);
+                self.bump();
+                self.parse_elements()?;
+                self.builder.finish_node();
+                return Ok(SyntaxKind::List);
+            }
+            // A brace starting a statement is parsed as a block before getting here, so one in an
+            // expression can only start a map.
+            TokenType::LeftBrace => {
+                self.start_node(SyntaxKind::Map);
+                self.bump();
+                self.parse_entries()?;
+                self.builder.finish_node();
+                return Ok(SyntaxKind::Map);
             }
             TokenType::LeftParen => {
-                self.cursor += 1;
-                let node = self.parse_expression()?;
-                if self.cursor < self.tokens.len()
-                    && self.tokens[self.cursor].token_type == TokenType::RightParen
-                {
-                    self.cursor += 1;
-                    Ok(node)
+                self.start_node(SyntaxKind::Grouping);
+                self.bump();
+                self.parse_expression()?;
+                return if self.cursor >= self.tokens.len() {
+                    Err(self.end_of_input_error())
+                } else if self.tokens[self.cursor].token_type == TokenType::RightParen {
+                    self.bump();
+                    self.builder.finish_node();
+                    Ok(SyntaxKind::Grouping)
                 } else {
                     Err(Error::new(
                         self.tokens[self.cursor - 1].line_number,
                         &format!("Unexpected token: {}", self.tokens[self.cursor - 1].lexeme),
                     ))
+                };
+            }
+            _ => {
+                return Err(Error::new(
+                    self.tokens[self.cursor].line_number,
+                    &format!("Unexpected token: {}", self.tokens[self.cursor].lexeme),
+                ))
+            }
+        };
+
+        self.start_node(kind);
+        self.bump();
+        self.builder.finish_node();
+        Ok(kind)
+    }
+
+    fn check(&self, token_type: TokenType) -> bool {
+        self.cursor < self.tokens.len() && self.tokens[self.cursor].token_type == token_type
+    }
+
+    fn check_next(&self, token_type: TokenType) -> bool {
+        self.tokens
+            .get(self.cursor + 1)
+            .is_some_and(|token| token.token_type == token_type)
+    }
+
+    fn advance_if(&mut self, token_type: TokenType) -> bool {
+        if self.check(token_type) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<&'a Token<'a>, Error> {
+        if self.cursor >= self.tokens.len() {
+            return Err(Error::at_end_of_input(
+                self.line_number_at_cursor(),
+                message,
+            ));
+        }
+        if self.tokens[self.cursor].token_type != token_type {
+            return Err(self.error_at_cursor(message));
+        }
+        self.bump();
+        Ok(self.tokens[self.cursor - 1])
+    }
+
+    // Adds the token at the cursor to the tree, after the trivia before it, and moves past it.
+    fn bump(&mut self) {
+        self.emit_trivia();
+        self.emit(&self.all_tokens[self.emitted]);
+        self.cursor += 1;
+    }
+
+    // Nodes start after the trivia before their first token, so that they begin where their
+    // source does.
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.emit_trivia();
+        self.builder.start_node(kind);
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.emit_trivia();
+        self.builder.checkpoint()
+    }
+
+    // Adds the tokens up to the one at the cursor to the tree, which are trivia unless some were
+    // skipped after an error. Tokens scanned without trivia get newlines put between them instead,
+    // so that the tree still tells which line each token is on.
+    fn emit_trivia(&mut self) {
+        let end = match self.positions.get(self.cursor) {
+            Some(&position) => position,
+            None => self.all_tokens.len(),
+        };
+        while self.emitted < end {
+            self.emit(&self.all_tokens[self.emitted]);
+        }
+
+        if let Some(token) = self.tokens.get(self.cursor) {
+            if token.line_number > self.line_number {
+                let newlines = "\n".repeat((token.line_number - self.line_number) as usize);
+                self.builder.token(TokenType::Whitespace, &newlines);
+                self.line_number = token.line_number;
+            }
+        }
+    }
+
+    fn emit(&mut self, token: &Token) {
+        // String tokens only hold what is between the quotes.
+        if token.token_type == TokenType::String {
+            self.builder
+                .token(token.token_type, &format!("\"{}\"", token.lexeme));
+        } else {
+            self.builder.token(token.token_type, token.lexeme);
+        }
+        self.emitted += 1;
+        self.line_number = token.line_number + token.lexeme.matches('\n').count() as i32;
+    }
+
+    fn line_number_at_cursor(&self) -> i32 {
+        match self.tokens.get(self.cursor) {
+            Some(token) => token.line_number,
+            None => self.tokens.last().map_or(1, |token| token.line_number),
+        }
+    }
+
+    fn error_at_cursor(&self, message: &str) -> Error {
+        let token = &self.tokens[self.cursor];
+        Error::new(
+            token.line_number,
+            &format!("Unexpected token '{}'. {}", token.lexeme, message),
+        )
+    }
+
+    fn end_of_input_error(&self) -> Error {
+        let line_number = self.tokens.last().map_or(1, |token| token.line_number);
+        Error::at_end_of_input(line_number, "Unexpected end of input")
+    }
+
+    // Skips tokens until the start of the next statement, so that parsing can resume after an
+    // error without reporting cascading errors.
+    fn synchronize(&mut self) {
+        while self.cursor < self.tokens.len() {
+            let token_type = self.tokens[self.cursor].token_type;
+            self.cursor += 1;
+            if token_type == TokenType::Semicolon {
+                return;
+            }
+            if let Some(token) = self.tokens.get(self.cursor) {
+                match token.token_type {
+                    TokenType::Class
+                    | TokenType::Fun
+                    | TokenType::Var
+                    | TokenType::For
+                    | TokenType::If
+                    | TokenType::While
+                    | TokenType::Print
+                    | TokenType::Return
+                    | TokenType::Try
+                    | TokenType::Throw
+                    | TokenType::Break
+                    | TokenType::Continue
+                    | TokenType::Import
+                    | TokenType::Export => return,
+                    _ => {}
                 }
             }
-            _ => Err(Error::new(
-                self.tokens[self.cursor].line_number,
-                &format!("Unexpected token: {}", self.tokens[self.cursor].lexeme),
-            )),
         }
     }
+
+    // Finishes the root node once everything has been parsed, taking the trivia after the last
+    // token with it.
+    fn finish_root(&mut self) -> SyntaxNode {
+        self.emit_trivia();
+        self.builder.finish_node();
+        SyntaxNode::new_root(std::mem::take(&mut self.builder).finish())
+    }
+}
+
+// Checks that the operand of `++` or `--` can be assigned to, like the target of an assignment.
+fn increment_target(operator: &Token, kind: SyntaxKind) -> Result<SyntaxKind, Error> {
+    match kind {
+        SyntaxKind::Variable | SyntaxKind::Get | SyntaxKind::Index => Ok(SyntaxKind::Increment),
+        _ => Err(Error::new(
+            operator.line_number,
+            &format!("Invalid operand for '{}'.", operator.lexeme),
+        )),
+    }
 }
 
 impl<'a> Parse<'a> for RecursiveDescentParser<'a> {
     fn parse(&mut self, tokens: &'a [Token]) -> Result<Node, Error> {
-        self.tokens = tokens;
-        self.cursor = 0;
+        self.reset(tokens);
+        self.builder.start_node(SyntaxKind::Program);
 
-        let node = self.parse_expression()?;
+        self.parse_expression()?;
         if self.cursor < self.tokens.len() {
             Err(Error::new(
                 self.tokens[self.cursor].line_number,
                 &format!("Unexpected token bruh: {}", self.tokens[self.cursor].lexeme),
             ))
         } else {
-            Ok(node)
+            Ok(cst::lower::expression(&self.finish_root()))
+        }
+    }
+
+    fn parse_syntax(&mut self, tokens: &'a [Token]) -> Result<SyntaxNode, Vec<Error>> {
+        self.reset(tokens);
+        self.builder.start_node(SyntaxKind::Program);
+
+        let mut errors = Vec::new();
+        while self.cursor < self.tokens.len() {
+            if let Err(error) = self.parse_declaration() {
+                errors.push(error);
+                self.function_depth = 0;
+                self.synchronize();
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(self.finish_root())
+        } else {
+            Err(errors)
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ast::{
-        ComparisonOperator, EqualityOperator, Literal, Node, ProductOperator, SumOperator,
-        UnaryOperator,
-    };
+    use crate::ast::{Depth, Node};
     use crate::parser::Parse;
-    use crate::token::{Token, TokenType};
+    use crate::printer::AstPrinter;
+    use crate::scanner::Scanner;
+    use crate::symbol::Symbol;
 
     use super::RecursiveDescentParser;
 
     struct TestCase<'a> {
-        input: &'a [Token<'a>],
-        expected_output: Node,
-    }
-
-    #[test]
-    fn can_parse_series() {
-        let input = &[
-            Token::new(TokenType::Number, "1", 1),
-            Token::new(TokenType::Comma, ",", 1),
-            Token::new(TokenType::Number, "2", 1),
-        ];
-        let expected_output = Node::Expression(Box::new(Node::Series(
-            Box::new(Node::Primary(Literal::Number(1.0))),
-            Box::new(Node::Primary(Literal::Number(2.0))),
-        )));
-        let output = RecursiveDescentParser::new().parse(input).unwrap();
-
-        assert_eq!(output, expected_output);
+        input: &'a str,
+        expected_output: &'a str,
     }
 
-    #[test]
-    fn series_is_left_associative() {
-        let input = &[
-            Token::new(TokenType::Number, "1", 1),
-            Token::new(TokenType::Comma, ",", 1),
-            Token::new(TokenType::Number, "2", 1),
-            Token::new(TokenType::Comma, ",", 1),
-            Token::new(TokenType::Number, "3", 1),
-        ];
-        let expected_output = Node::Expression(Box::new(Node::Series(
-            Box::new(Node::Series(
-                Box::new(Node::Primary(Literal::Number(1.0))),
-                Box::new(Node::Primary(Literal::Number(2.0))),
-            )),
-            Box::new(Node::Primary(Literal::Number(3.0))),
-        )));
-
-        let output = RecursiveDescentParser::new().parse(input).unwrap();
-        assert_eq!(output, expected_output);
+    fn check_expressions(test_cases: &[TestCase]) {
+        for test_case in test_cases {
+            let tokens = Scanner::new().scan(test_case.input).unwrap();
+            let output = RecursiveDescentParser::new().parse(&tokens).unwrap();
+            assert_eq!(AstPrinter::print(&output), test_case.expected_output);
+        }
     }
 
-    #[test]
-    fn can_parse_equality() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::EqualEqual, "==", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Equality(
-                    EqualityOperator::Equal,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::BangEqual, "!=", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Equality(
-                    EqualityOperator::NotEqual,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-        ];
-
+    fn check_programs(test_cases: &[TestCase]) {
         for test_case in test_cases {
+            let tokens = Scanner::new().scan(test_case.input).unwrap();
             let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
+                .parse_program(&tokens)
                 .unwrap();
-            assert_eq!(output, test_case.expected_output);
+            assert_eq!(
+                AstPrinter::print_program(&output),
+                test_case.expected_output
+            );
         }
     }
 
     #[test]
-    fn equality_is_left_associative() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::EqualEqual, "==", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::EqualEqual, "==", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Equality(
-                    EqualityOperator::Equal,
-                    Box::new(Node::Equality(
-                        EqualityOperator::Equal,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::BangEqual, "!=", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::BangEqual, "!=", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Equality(
-                    EqualityOperator::NotEqual,
-                    Box::new(Node::Equality(
-                        EqualityOperator::NotEqual,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-        ];
-
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
-        }
+    fn can_parse_binary_operators() {
+        check_expressions(&[
+            TestCase {
+                input: "1, 2",
+                expected_output: "(, 1 2)",
+            },
+            TestCase {
+                input: "a or b",
+                expected_output: "(or a b)",
+            },
+            TestCase {
+                input: "a and b",
+                expected_output: "(and a b)",
+            },
+            TestCase {
+                input: "1 == 2",
+                expected_output: "(== 1 2)",
+            },
+            TestCase {
+                input: "1 != 2",
+                expected_output: "(!= 1 2)",
+            },
+            TestCase {
+                input: "1 > 2",
+                expected_output: "(> 1 2)",
+            },
+            TestCase {
+                input: "1 >= 2",
+                expected_output: "(>= 1 2)",
+            },
+            TestCase {
+                input: "1 < 2",
+                expected_output: "(< 1 2)",
+            },
+            TestCase {
+                input: "1 <= 2",
+                expected_output: "(<= 1 2)",
+            },
+            TestCase {
+                input: "1 + 2",
+                expected_output: "(+ 1 2)",
+            },
+            TestCase {
+                input: "1 - 2",
+                expected_output: "(- 1 2)",
+            },
+            TestCase {
+                input: "1 * 2",
+                expected_output: "(* 1 2)",
+            },
+            TestCase {
+                input: "1 / 2",
+                expected_output: "(/ 1 2)",
+            },
+            TestCase {
+                input: "1 % 2",
+                expected_output: "(% 1 2)",
+            },
+            TestCase {
+                input: "1 ~/ 2",
+                expected_output: "(~/ 1 2)",
+            },
+            TestCase {
+                input: "1 ** 2",
+                expected_output: "(** 1 2)",
+            },
+        ]);
     }
 
     #[test]
-    fn can_parse_comparison() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Greater, ">", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Comparison(
-                    ComparisonOperator::Greater,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::GreaterEqual, ">=", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Comparison(
-                    ComparisonOperator::GreaterEqual,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Less, "<", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Comparison(
-                    ComparisonOperator::Less,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::LessEqual, "<=", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Comparison(
-                    ComparisonOperator::LessEqual,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-        ];
+    fn binary_operators_are_left_associative() {
+        check_expressions(&[
+            TestCase {
+                input: "1, 2, 3",
+                expected_output: "(, (, 1 2) 3)",
+            },
+            TestCase {
+                input: "a or b or c",
+                expected_output: "(or (or a b) c)",
+            },
+            TestCase {
+                input: "1 == 2 != 3",
+                expected_output: "(!= (== 1 2) 3)",
+            },
+            TestCase {
+                input: "1 > 2 <= 3",
+                expected_output: "(<= (> 1 2) 3)",
+            },
+            TestCase {
+                input: "1 - 2 + 3",
+                expected_output: "(+ (- 1 2) 3)",
+            },
+            TestCase {
+                input: "1 / 2 * 3",
+                expected_output: "(* (/ 1 2) 3)",
+            },
+            TestCase {
+                input: "1 % 2 ~/ 3",
+                expected_output: "(~/ (% 1 2) 3)",
+            },
+            TestCase {
+                input: "1 << 2 >> 3",
+                expected_output: "(>> (<< 1 2) 3)",
+            },
+        ]);
+    }
 
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
-        }
+    #[test]
+    fn bitwise_operators_have_the_precedence_they_have_in_c() {
+        check_expressions(&[
+            TestCase {
+                input: "a | b ^ c & d == e",
+                expected_output: "(| a (^ b (& c (== d e))))",
+            },
+            TestCase {
+                input: "a & b | c ^ d and e",
+                expected_output: "(and (| (& a b) (^ c d)) e)",
+            },
+            TestCase {
+                input: "a < b << 1 + 2",
+                expected_output: "(< a (<< b (+ 1 2)))",
+            },
+            TestCase {
+                input: "~a ** 2 & ~-b",
+                expected_output: "(& (~ (** a 2)) (~ (- b)))",
+            },
+        ]);
     }
 
     #[test]
-    fn comparison_is_left_associative() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Greater, ">", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::Greater, ">", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Comparison(
-                    ComparisonOperator::Greater,
-                    Box::new(Node::Comparison(
-                        ComparisonOperator::Greater,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::GreaterEqual, ">=", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::GreaterEqual, ">=", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Comparison(
-                    ComparisonOperator::GreaterEqual,
-                    Box::new(Node::Comparison(
-                        ComparisonOperator::GreaterEqual,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Less, "<", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::Less, "<", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Comparison(
-                    ComparisonOperator::Less,
-                    Box::new(Node::Comparison(
-                        ComparisonOperator::Less,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::LessEqual, "<=", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::LessEqual, "<=", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Comparison(
-                    ComparisonOperator::LessEqual,
-                    Box::new(Node::Comparison(
-                        ComparisonOperator::LessEqual,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-        ];
+    fn exponents_conditionals_and_assignments_are_right_associative() {
+        check_expressions(&[
+            TestCase {
+                input: "2 ** 3 ** -2",
+                expected_output: "(** 2 (** 3 (- 2)))",
+            },
+            TestCase {
+                input: "-a ** 2 * b",
+                expected_output: "(* (- (** a 2)) b)",
+            },
+            TestCase {
+                input: "a ? b : c ? d : e",
+                expected_output: "(?: a b (?: c d e))",
+            },
+            TestCase {
+                input: "a = b or c ? d = 1 : e",
+                expected_output: "(= a (?: (or b c) (= d 1) e))",
+            },
+            TestCase {
+                input: "a += b.c -= d[0] %= 2",
+                expected_output: "(+= a (-= (. c b) (%= ([] d 0) 2)))",
+            },
+            TestCase {
+                input: "a **= b ~/= 2 ** 3",
+                expected_output: "(**= a (~/= b (** 2 3)))",
+            },
+            TestCase {
+                input: "a &= b |= c ^= d <<= e >>= 1 | 2",
+                expected_output: "(&= a (|= b (^= c (<<= d (>>= e (| 1 2))))))",
+            },
+        ]);
+    }
 
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
+    #[test]
+    fn increments_come_before_or_after_what_they_assign() {
+        check_expressions(&[
+            TestCase {
+                input: "++a + b--",
+                expected_output: "(+ (pre++ a) (post-- b))",
+            },
+            TestCase {
+                input: "-a.b++ ** 2",
+                expected_output: "(- (** (post++ (. b a)) 2))",
+            },
+            TestCase {
+                input: "--a[0]",
+                expected_output: "(pre-- ([] a 0))",
+            },
+        ]);
+        for input in ["++1", "a()--", "a ? b : c += 1", "--(a)"] {
+            let tokens = Scanner::new().scan(input).unwrap();
+            assert!(
+                RecursiveDescentParser::new().parse(&tokens).is_err(),
+                "{}",
+                input
+            );
         }
     }
 
     #[test]
-    fn can_parse_sum() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Plus, "+", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Sum(
-                    SumOperator::Plus,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Minus, "-", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Sum(
-                    SumOperator::Minus,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-        ];
+    fn operators_bind_by_precedence() {
+        check_expressions(&[
+            TestCase {
+                input: "a = b = 1 or 2 and 3 == 4 < 5 + 6 * -7, c",
+                expected_output: "(, (= a (= b (or 1 (and 2 (== 3 (< 4 (+ 5 (* 6 (- 7))))))))) c)",
+            },
+            TestCase {
+                input: "(1 + 2) * 3",
+                expected_output: "(* (group (+ 1 2)) 3)",
+            },
+        ]);
+    }
 
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
-        }
+    #[test]
+    fn unary_is_right_associative() {
+        check_expressions(&[
+            TestCase {
+                input: "!!true",
+                expected_output: "(! (! true))",
+            },
+            TestCase {
+                input: "- -123.456",
+                expected_output: "(- (- 123.456))",
+            },
+        ]);
     }
 
     #[test]
-    fn sum_is_left_associative() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Plus, "+", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::Plus, "+", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Sum(
-                    SumOperator::Plus,
-                    Box::new(Node::Sum(
-                        SumOperator::Plus,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Minus, "-", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::Minus, "-", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Sum(
-                    SumOperator::Minus,
-                    Box::new(Node::Sum(
-                        SumOperator::Minus,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-        ];
+    fn can_parse_primary_expressions() {
+        check_expressions(&[
+            TestCase {
+                input: "nil",
+                expected_output: "nil",
+            },
+            TestCase {
+                input: "true",
+                expected_output: "true",
+            },
+            TestCase {
+                input: "false",
+                expected_output: "false",
+            },
+            TestCase {
+                input: "\"I am a string!\"",
+                expected_output: "\"I am a string!\"",
+            },
+            TestCase {
+                input: "123.456",
+                expected_output: "123.456",
+            },
+            TestCase {
+                input: "(nil)",
+                expected_output: "(group nil)",
+            },
+            TestCase {
+                input: "breakfast",
+                expected_output: "breakfast",
+            },
+        ]);
+    }
 
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
-        }
+    #[test]
+    fn can_parse_call() {
+        check_expressions(&[
+            TestCase {
+                input: "clock()",
+                expected_output: "(call clock)",
+            },
+            TestCase {
+                input: "max(1, 2)",
+                expected_output: "(call max 1 2)",
+            },
+            TestCase {
+                input: "f(a)(b, c)",
+                expected_output: "(call (call f a) b c)",
+            },
+        ]);
     }
 
     #[test]
-    fn product_is_left_associative() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Star, "*", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::Star, "*", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Product(
-                    ProductOperator::Star,
-                    Box::new(Node::Product(
-                        ProductOperator::Star,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Slash, "/", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                    Token::new(TokenType::Slash, "/", 1),
-                    Token::new(TokenType::Number, "3", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Product(
-                    ProductOperator::Slash,
-                    Box::new(Node::Product(
-                        ProductOperator::Slash,
-                        Box::new(Node::Primary(Literal::Number(1.0))),
-                        Box::new(Node::Primary(Literal::Number(2.0))),
-                    )),
-                    Box::new(Node::Primary(Literal::Number(3.0))),
-                ))),
-            },
-        ];
+    fn can_parse_lists_and_subscripts() {
+        check_expressions(&[
+            TestCase {
+                input: "[]",
+                expected_output: "(list)",
+            },
+            TestCase {
+                input: "[1, [a], f()]",
+                expected_output: "(list 1 (list a) (call f))",
+            },
+            TestCase {
+                input: "a[0][b](c)",
+                expected_output: "(call ([] ([] a 0) b) c)",
+            },
+            TestCase {
+                input: "a[:], a[1:], a[:-1]",
+                expected_output: "(, (, (slice a () ()) (slice a 1 ())) (slice a () (- 1)))",
+            },
+            TestCase {
+                input: "a.b[c] = d[e] = 1",
+                expected_output: "([]= (. b a) c ([]= d e 1))",
+            },
+        ]);
 
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
+        for input in ["[1, 2", "a[1", "a[]", "[1 2]", "a[1:2] = 3"] {
+            let tokens = Scanner::new().scan(input).unwrap();
+            assert!(
+                RecursiveDescentParser::new().parse(&tokens).is_err(),
+                "{}",
+                input
+            );
         }
     }
 
     #[test]
-    fn can_parse_product() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Star, "*", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Product(
-                    ProductOperator::Star,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Number, "1", 1),
-                    Token::new(TokenType::Slash, "/", 1),
-                    Token::new(TokenType::Number, "2", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Product(
-                    ProductOperator::Slash,
-                    Box::new(Node::Primary(Literal::Number(1.0))),
-                    Box::new(Node::Primary(Literal::Number(2.0))),
-                ))),
-            },
-        ];
+    fn braces_start_maps_in_expressions_and_blocks_in_statements() {
+        check_expressions(&[
+            TestCase {
+                input: "{}",
+                expected_output: "(map)",
+            },
+            TestCase {
+                input: "{\"a\": 1, b: {c: d}}[e]",
+                expected_output: "([] (map \"a\" 1 b (map c d)) e)",
+            },
+        ]);
+        check_programs(&[TestCase {
+            input: "{} var m = {}; { m[1] = {}; }",
+            expected_output: "(block)\n(var m (map))\n(block (; ([]= m 1 (map))))\n",
+        }]);
 
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
+        for input in ["{1}", "{1: 2", "{1: 2,}", "{: 2}"] {
+            let tokens = Scanner::new().scan(input).unwrap();
+            assert!(
+                RecursiveDescentParser::new().parse(&tokens).is_err(),
+                "{}",
+                input
+            );
         }
     }
 
     #[test]
-    fn can_parse_unary() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Bang, "!", 1),
-                    Token::new(TokenType::True, "true", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Unary(
-                    UnaryOperator::Bang,
-                    Box::new(Node::Primary(Literal::True)),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Minus, "-", 1),
-                    Token::new(TokenType::Number, "123.456", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Unary(
-                    UnaryOperator::Minus,
-                    Box::new(Node::Primary(Literal::Number(123.456))),
-                ))),
-            },
-        ];
+    fn nodes_keep_their_line_numbers() {
+        let tokens = Scanner::new().scan("a =\n  f\n  (\n  b)").unwrap();
+        let expected_output = Node::Expression(Box::new(Node::Assign(
+            Symbol::intern("a"),
+            Box::new(Node::Call(
+                Box::new(Node::Variable(Symbol::intern("f"), Depth::default(), 2)),
+                vec![Node::Variable(Symbol::intern("b"), Depth::default(), 4)],
+                3,
+            )),
+            Depth::default(),
+            1,
+        )));
+        let output = RecursiveDescentParser::new().parse(&tokens).unwrap();
 
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
-        }
+        assert_eq!(output, expected_output);
     }
 
     #[test]
-    fn unary_is_right_associative() {
-        let test_cases = [
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Bang, "!", 1),
-                    Token::new(TokenType::Bang, "!", 1),
-                    Token::new(TokenType::True, "true", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Unary(
-                    UnaryOperator::Bang,
-                    Box::new(Node::Unary(
-                        UnaryOperator::Bang,
-                        Box::new(Node::Primary(Literal::True)),
-                    )),
-                ))),
-            },
-            TestCase {
-                input: &[
-                    Token::new(TokenType::Minus, "-", 1),
-                    Token::new(TokenType::Minus, "-", 1),
-                    Token::new(TokenType::Number, "123.456", 1),
-                ],
-                expected_output: Node::Expression(Box::new(Node::Unary(
-                    UnaryOperator::Minus,
-                    Box::new(Node::Unary(
-                        UnaryOperator::Minus,
-                        Box::new(Node::Primary(Literal::Number(123.456))),
-                    )),
-                ))),
-            },
-        ];
+    fn incomplete_expression_is_an_error() {
+        let tokens = Scanner::new().scan("1 +").unwrap();
+        let output = RecursiveDescentParser::new().parse(&tokens);
 
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
-        }
+        assert!(output.is_err());
     }
 
     #[test]
-    fn can_parse_keywords() {
-        let test_cases = [
+    fn can_parse_declarations() {
+        check_programs(&[
             TestCase {
-                input: &[Token::new(TokenType::Nil, "nil", 1)],
-                expected_output: Node::Expression(Box::new(Node::Primary(Literal::Nil))),
+                input: "var a = b = 1; print a or b;",
+                expected_output: "(var a (= b 1))\n(print (or a b))\n",
             },
             TestCase {
-                input: &[Token::new(TokenType::True, "true", 1)],
-                expected_output: Node::Expression(Box::new(Node::Primary(Literal::True))),
+                input: "fun f(a) { if (a) return a; else { a; } } while (f(1)) {}",
+                expected_output:
+                    "(fun f (a) (if a (return a) (block (; a))))\n(while (call f 1) (block))\n",
             },
-            TestCase {
-                input: &[Token::new(TokenType::False, "false", 1)],
-                expected_output: Node::Expression(Box::new(Node::Primary(Literal::False))),
-            },
-        ];
-
-        for test_case in test_cases {
-            let output = RecursiveDescentParser::new()
-                .parse(test_case.input)
-                .unwrap();
-            assert_eq!(output, test_case.expected_output);
-        }
+        ]);
     }
 
     #[test]
-    fn can_parse_string_literal() {
-        let input = &[Token::new(TokenType::String, "I am a string!", 1)];
-        let expected_output = Node::Expression(Box::new(Node::Primary(Literal::String(
-            String::from("I am a string!"),
-        ))));
-        let output = RecursiveDescentParser::new().parse(input).unwrap();
-
-        assert_eq!(output, expected_output);
+    fn for_loops_keep_their_clauses() {
+        check_programs(&[TestCase {
+            input: "for (var i = 0; i; i = nil) {} for (;;) {}",
+            expected_output: "(for (var i 0) i (= i nil) (block))\n(for () () () (block))\n",
+        }]);
     }
 
     #[test]
-    fn can_parse_number_literal() {
-        let input = &[Token::new(TokenType::Number, "123.456", 1)];
-        let expected_output = Node::Expression(Box::new(Node::Primary(Literal::Number(123.456))));
-        let output = RecursiveDescentParser::new().parse(input).unwrap();
+    fn unfinished_declarations_are_errors_at_end_of_input() {
+        for input in ["var a = 1", "print (1", "fun f() {", "if (true)", "while (a", "try {}"] {
+            let tokens = Scanner::new().scan(input).unwrap();
+            let errors = RecursiveDescentParser::new()
+                .parse_program(&tokens)
+                .unwrap_err();
 
-        assert_eq!(output, expected_output);
+            assert!(errors[0].at_end_of_input, "{}", input);
+        }
     }
 
     #[test]
-    fn can_parse_parenthetical_expression() {
-        let input = &[
-            Token::new(TokenType::LeftParen, "(", 1),
-            Token::new(TokenType::Nil, "nil", 1),
-            Token::new(TokenType::RightParen, ")", 1),
-        ];
-        let expected_output = Node::Expression(Box::new(Node::Expression(Box::new(
-            Node::Primary(Literal::Nil),
-        ))));
-        let output = RecursiveDescentParser::new().parse(input).unwrap();
+    fn parsing_recovers_after_an_error() {
+        let tokens = Scanner::new()
+            .scan("var = 1;\nprint 2;\n1 = 2;\nreturn 3;")
+            .unwrap();
+        let errors = RecursiveDescentParser::new()
+            .parse_program(&tokens)
+            .unwrap_err();
+        let messages: Vec<&str> = errors.iter().map(|error| error.message.as_str()).collect();
 
-        assert_eq!(output, expected_output);
+        assert_eq!(
+            messages,
+            [
+                "Unexpected token '='. Expect variable name.",
+                "Invalid assignment target.",
+                "Unexpected token 'return'. Can't return from top-level code.",
+            ]
+        );
+        assert!(errors.iter().all(|error| !error.at_end_of_input));
     }
 }
@@ -0,0 +1,757 @@
+use crate::ast::{
+    AssignOperator, BitwiseOperator, Catch, Class, ComparisonOperator, EqualityOperator, Fixity,
+    Function, Imports, IncrementOperator, Literal, LogicalOperator, Node, ProductOperator,
+    ShiftOperator, Statement, SumOperator, UnaryOperator,
+};
+use crate::symbol::Symbol;
+use crate::visit::Visitor;
+
+/// Prints the AST in a parenthesized Lisp form, with each operation followed by its operands,
+/// like `(* (- 123) (group 45.67))`. Strings are quoted so that they can't be mistaken for
+/// variables.
+#[derive(Default)]
+pub struct AstPrinter {
+    output: String,
+}
+
+impl AstPrinter {
+    pub fn print(node: &Node) -> String {
+        let mut printer = AstPrinter::default();
+        printer.visit_node(node);
+        printer.output
+    }
+
+    /// Prints each statement on a line of its own.
+    pub fn print_program(statements: &[Statement]) -> String {
+        let mut printer = AstPrinter::default();
+        for statement in statements {
+            printer.visit_statement(statement);
+            printer.output.push('\n');
+        }
+        printer.output
+    }
+
+    fn block(&mut self, head: &str, statements: &[Statement]) {
+        self.output.push('(');
+        self.output.push_str(head);
+        for statement in statements {
+            self.output.push(' ');
+            self.visit_statement(statement);
+        }
+        self.output.push(')');
+    }
+
+    fn parenthesize(&mut self, name: &str, nodes: &[&Node]) {
+        self.output.push('(');
+        self.output.push_str(name);
+        for node in nodes {
+            self.output.push(' ');
+            self.visit_node(node);
+        }
+        self.output.push(')');
+    }
+
+    fn jump(&mut self, keyword: &str, label: Option<&Symbol>) {
+        match label {
+            Some(label) => self
+                .output
+                .push_str(&format!("({} {})", keyword, label.as_str())),
+            None => self.output.push_str(&format!("({})", keyword)),
+        }
+    }
+}
+
+impl Visitor for AstPrinter {
+    fn visit_expression_statement(&mut self, expression: &Node, _line_number: i32) {
+        self.parenthesize(";", &[expression]);
+    }
+
+    fn visit_print(&mut self, expression: &Node, _line_number: i32) {
+        self.parenthesize("print", &[expression]);
+    }
+
+    fn visit_var(
+        &mut self,
+        name: &Symbol,
+        annotation: Option<&Symbol>,
+        initializer: Option<&Node>,
+        _line_number: i32,
+    ) {
+        let head = format!("var {}", annotated(name.as_str(), annotation));
+        match initializer {
+            Some(initializer) => self.parenthesize(&head, &[initializer]),
+            None => self.parenthesize(&head, &[]),
+        }
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) {
+        self.block("block", statements);
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Node,
+        then_branch: &Statement,
+        else_branch: Option<&Statement>,
+    ) {
+        self.output.push_str("(if ");
+        self.visit_node(condition);
+        self.output.push(' ');
+        self.visit_statement(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.output.push(' ');
+            self.visit_statement(else_branch);
+        }
+        self.output.push(')');
+    }
+
+    fn visit_while(&mut self, condition: &Node, body: &Statement) {
+        self.output.push_str("(while ");
+        self.visit_node(condition);
+        self.output.push(' ');
+        self.visit_statement(body);
+        self.output.push(')');
+    }
+
+    // Omitted clauses are printed as `()`, keeping the others in their places.
+    fn visit_for(
+        &mut self,
+        initializer: Option<&Statement>,
+        condition: Option<&Node>,
+        increment: Option<&Node>,
+        body: &Statement,
+    ) {
+        self.output.push_str("(for ");
+        match initializer {
+            Some(initializer) => self.visit_statement(initializer),
+            None => self.output.push_str("()"),
+        }
+        for clause in [condition, increment] {
+            self.output.push(' ');
+            match clause {
+                Some(clause) => self.visit_node(clause),
+                None => self.output.push_str("()"),
+            }
+        }
+        self.output.push(' ');
+        self.visit_statement(body);
+        self.output.push(')');
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        let parameters: Vec<String> = function
+            .parameters
+            .iter()
+            .zip(&function.parameter_types)
+            .map(|(parameter, annotation)| annotated(parameter.as_str(), annotation.as_ref()))
+            .collect();
+        let head = format!(
+            "fun {} {}",
+            function.name.as_str(),
+            annotated(
+                &format!("({})", parameters.join(" ")),
+                function.return_type.as_ref()
+            )
+        );
+        self.block(&head, &function.body);
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        self.output.push_str("(class ");
+        self.output.push_str(class.name.as_str());
+        if let Some(superclass) = &class.superclass {
+            self.output.push_str(" < ");
+            self.visit_node(superclass);
+        }
+        for method in &class.methods {
+            self.output.push(' ');
+            self.visit_method(method);
+        }
+        self.output.push(')');
+    }
+
+    fn visit_method(&mut self, method: &Function) {
+        self.visit_function(method);
+    }
+
+    fn visit_return(&mut self, value: Option<&Node>, _line_number: i32) {
+        match value {
+            Some(value) => self.parenthesize("return", &[value]),
+            None => self.parenthesize("return", &[]),
+        }
+    }
+
+    // Imported names are listed in parentheses after the path, like the parameters of a function.
+    fn visit_import(&mut self, path: &Symbol, imports: &Imports, _line_number: i32) {
+        self.output.push_str("(import ");
+        write_literal(&mut self.output, &Literal::String(path.clone()));
+        match imports {
+            Imports::Module(name) => {
+                self.output.push_str(" as ");
+                self.output.push_str(name.as_str());
+            }
+            Imports::Names(names) => {
+                let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+                self.output.push_str(&format!(" ({})", names.join(" ")));
+            }
+        }
+        self.output.push(')');
+    }
+
+    fn visit_export(&mut self, declaration: &Statement) {
+        self.output.push_str("(export ");
+        self.visit_statement(declaration);
+        self.output.push(')');
+    }
+
+    fn visit_try(&mut self, body: &Statement, catch: Option<&Catch>, finally: Option<&Statement>) {
+        self.output.push_str("(try ");
+        self.visit_statement(body);
+        if let Some(catch) = catch {
+            self.output
+                .push_str(&format!(" (catch {} ", catch.name.as_str()));
+            self.visit_statement(&catch.body);
+            self.output.push(')');
+        }
+        if let Some(finally) = finally {
+            self.output.push_str(" (finally ");
+            self.visit_statement(finally);
+            self.output.push(')');
+        }
+        self.output.push(')');
+    }
+
+    fn visit_throw(&mut self, value: &Node, _line_number: i32) {
+        self.parenthesize("throw", &[value]);
+    }
+
+    fn visit_labelled(&mut self, label: &Symbol, body: &Statement) {
+        self.output.push_str(&format!("(label {} ", label.as_str()));
+        self.visit_statement(body);
+        self.output.push(')');
+    }
+
+    fn visit_break(&mut self, label: Option<&Symbol>, _line_number: i32) {
+        self.jump("break", label);
+    }
+
+    fn visit_continue(&mut self, label: Option<&Symbol>, _line_number: i32) {
+        self.jump("continue", label);
+    }
+
+    fn visit_grouping(&mut self, expression: &Node) {
+        self.parenthesize("group", &[expression]);
+    }
+
+    fn visit_series(&mut self, left: &Node, right: &Node) {
+        self.parenthesize(",", &[left, right]);
+    }
+
+    fn visit_assign(&mut self, name: &Symbol, value: &Node, _line_number: i32) {
+        self.parenthesize(&format!("= {}", name.as_str()), &[value]);
+    }
+
+    fn visit_compound_assign(
+        &mut self,
+        operator: &AssignOperator,
+        target: &Node,
+        value: &Node,
+        _line_number: i32,
+    ) {
+        self.parenthesize(operator.as_str(), &[target, value]);
+    }
+
+    fn visit_increment(
+        &mut self,
+        operator: &IncrementOperator,
+        fixity: &Fixity,
+        operand: &Node,
+        _line_number: i32,
+    ) {
+        self.parenthesize(&increment(operator, fixity), &[operand]);
+    }
+
+    fn visit_conditional(&mut self, condition: &Node, then_value: &Node, else_value: &Node) {
+        self.parenthesize("?:", &[condition, then_value, else_value]);
+    }
+
+    fn visit_logical(&mut self, operator: &LogicalOperator, left: &Node, right: &Node) {
+        self.parenthesize(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_bitwise(&mut self, operator: &BitwiseOperator, left: &Node, right: &Node) {
+        self.parenthesize(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_equality(&mut self, operator: &EqualityOperator, left: &Node, right: &Node) {
+        self.parenthesize(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_comparison(&mut self, operator: &ComparisonOperator, left: &Node, right: &Node) {
+        self.parenthesize(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_shift(&mut self, operator: &ShiftOperator, left: &Node, right: &Node) {
+        self.parenthesize(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_sum(&mut self, operator: &SumOperator, left: &Node, right: &Node) {
+        self.parenthesize(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_product(&mut self, operator: &ProductOperator, left: &Node, right: &Node) {
+        self.parenthesize(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_power(&mut self, base: &Node, exponent: &Node) {
+        self.parenthesize("**", &[base, exponent]);
+    }
+
+    fn visit_unary(&mut self, operator: &UnaryOperator, operand: &Node) {
+        self.parenthesize(operator.as_str(), &[operand]);
+    }
+
+    fn visit_call(&mut self, callee: &Node, arguments: &[Node], _line_number: i32) {
+        let mut nodes = vec![callee];
+        nodes.extend(arguments);
+        self.parenthesize("call", &nodes);
+    }
+
+    fn visit_get(&mut self, object: &Node, name: &Symbol, _line_number: i32) {
+        self.parenthesize(&format!(". {}", name.as_str()), &[object]);
+    }
+
+    fn visit_set(&mut self, object: &Node, name: &Symbol, value: &Node, _line_number: i32) {
+        self.parenthesize(&format!(".= {}", name.as_str()), &[object, value]);
+    }
+
+    fn visit_list(&mut self, elements: &[Node]) {
+        let elements: Vec<&Node> = elements.iter().collect();
+        self.parenthesize("list", &elements);
+    }
+
+    // Each key is followed by its value.
+    fn visit_map(&mut self, entries: &[(Node, Node)], _line_number: i32) {
+        let entries: Vec<&Node> = entries
+            .iter()
+            .flat_map(|(key, value)| [key, value])
+            .collect();
+        self.parenthesize("map", &entries);
+    }
+
+    fn visit_index(&mut self, object: &Node, index: &Node, _line_number: i32) {
+        self.parenthesize("[]", &[object, index]);
+    }
+
+    fn visit_set_index(&mut self, object: &Node, index: &Node, value: &Node, _line_number: i32) {
+        self.parenthesize("[]=", &[object, index, value]);
+    }
+
+    // An omitted bound is written `()`, like an omitted clause of a `for` loop.
+    fn visit_slice(
+        &mut self,
+        object: &Node,
+        start: Option<&Node>,
+        end: Option<&Node>,
+        _line_number: i32,
+    ) {
+        self.output.push_str("(slice ");
+        self.visit_node(object);
+        for bound in [start, end] {
+            self.output.push(' ');
+            match bound {
+                Some(bound) => self.visit_node(bound),
+                None => self.output.push_str("()"),
+            }
+        }
+        self.output.push(')');
+    }
+
+    fn visit_this(&mut self, _line_number: i32) {
+        self.output.push_str("this");
+    }
+
+    fn visit_super(&mut self, method: &Symbol, _line_number: i32) {
+        self.output.push_str("(super ");
+        self.output.push_str(method.as_str());
+        self.output.push(')');
+    }
+
+    fn visit_variable(&mut self, name: &Symbol, _line_number: i32) {
+        self.output.push_str(name.as_str());
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) {
+        write_literal(&mut self.output, literal);
+    }
+}
+
+// A declared name followed by the type it is annotated with, if any.
+fn annotated(name: &str, annotation: Option<&Symbol>) -> String {
+    match annotation {
+        Some(annotation) => format!("{}: {}", name, annotation.as_str()),
+        None => name.to_string(),
+    }
+}
+
+/// Prints an expression in reverse Polish notation, with each operation after its operands, like
+/// `1 2 + 4 3 - *` for `(1 + 2) * (4 - 3)`. Negation is written `neg` to tell it apart from
+/// subtraction, calls are written `call/N` with their number of arguments, and getting or setting
+/// a property `b` is written `.b` or `.b=`. Likewise list literals are written `list/N`, map
+/// literals `map/N` after each key and its value, getting or setting an element `[]` or `[]=`, and
+/// slicing `[:]`, with `_` for an omitted bound. An increment or decrement is written `pre++` or
+/// `post++` by where its operator is, and a conditional `?:` after its condition and branches.
+#[derive(Default)]
+pub struct RpnPrinter {
+    output: String,
+}
+
+impl RpnPrinter {
+    pub fn print(node: &Node) -> String {
+        let mut printer = RpnPrinter::default();
+        printer.visit_node(node);
+        printer.output
+    }
+
+    fn operation(&mut self, operator: &str, operands: &[&Node]) {
+        for operand in operands {
+            self.visit_node(operand);
+        }
+        self.word(operator);
+    }
+
+    fn word(&mut self, word: &str) {
+        if !self.output.is_empty() {
+            self.output.push(' ');
+        }
+        self.output.push_str(word);
+    }
+}
+
+impl Visitor for RpnPrinter {
+    fn visit_series(&mut self, left: &Node, right: &Node) {
+        self.operation(",", &[left, right]);
+    }
+
+    fn visit_assign(&mut self, name: &Symbol, value: &Node, _line_number: i32) {
+        self.word(name.as_str());
+        self.operation("=", &[value]);
+    }
+
+    fn visit_compound_assign(
+        &mut self,
+        operator: &AssignOperator,
+        target: &Node,
+        value: &Node,
+        _line_number: i32,
+    ) {
+        self.operation(operator.as_str(), &[target, value]);
+    }
+
+    fn visit_increment(
+        &mut self,
+        operator: &IncrementOperator,
+        fixity: &Fixity,
+        operand: &Node,
+        _line_number: i32,
+    ) {
+        self.operation(&increment(operator, fixity), &[operand]);
+    }
+
+    fn visit_conditional(&mut self, condition: &Node, then_value: &Node, else_value: &Node) {
+        self.operation("?:", &[condition, then_value, else_value]);
+    }
+
+    fn visit_logical(&mut self, operator: &LogicalOperator, left: &Node, right: &Node) {
+        self.operation(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_bitwise(&mut self, operator: &BitwiseOperator, left: &Node, right: &Node) {
+        self.operation(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_equality(&mut self, operator: &EqualityOperator, left: &Node, right: &Node) {
+        self.operation(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_comparison(&mut self, operator: &ComparisonOperator, left: &Node, right: &Node) {
+        self.operation(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_shift(&mut self, operator: &ShiftOperator, left: &Node, right: &Node) {
+        self.operation(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_sum(&mut self, operator: &SumOperator, left: &Node, right: &Node) {
+        self.operation(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_product(&mut self, operator: &ProductOperator, left: &Node, right: &Node) {
+        self.operation(operator.as_str(), &[left, right]);
+    }
+
+    fn visit_power(&mut self, base: &Node, exponent: &Node) {
+        self.operation("**", &[base, exponent]);
+    }
+
+    fn visit_unary(&mut self, operator: &UnaryOperator, operand: &Node) {
+        let operator = match operator {
+            UnaryOperator::Bang => "!",
+            UnaryOperator::Minus => "neg",
+            UnaryOperator::Tilde => "~",
+        };
+        self.operation(operator, &[operand]);
+    }
+
+    fn visit_call(&mut self, callee: &Node, arguments: &[Node], _line_number: i32) {
+        let mut operands = vec![callee];
+        operands.extend(arguments);
+        self.operation(&format!("call/{}", arguments.len()), &operands);
+    }
+
+    fn visit_get(&mut self, object: &Node, name: &Symbol, _line_number: i32) {
+        self.operation(&format!(".{}", name.as_str()), &[object]);
+    }
+
+    fn visit_set(&mut self, object: &Node, name: &Symbol, value: &Node, _line_number: i32) {
+        self.operation(&format!(".{}=", name.as_str()), &[object, value]);
+    }
+
+    fn visit_list(&mut self, elements: &[Node]) {
+        let elements: Vec<&Node> = elements.iter().collect();
+        self.operation(&format!("list/{}", elements.len()), &elements);
+    }
+
+    fn visit_map(&mut self, entries: &[(Node, Node)], _line_number: i32) {
+        let operands: Vec<&Node> = entries
+            .iter()
+            .flat_map(|(key, value)| [key, value])
+            .collect();
+        self.operation(&format!("map/{}", entries.len()), &operands);
+    }
+
+    fn visit_index(&mut self, object: &Node, index: &Node, _line_number: i32) {
+        self.operation("[]", &[object, index]);
+    }
+
+    fn visit_set_index(&mut self, object: &Node, index: &Node, value: &Node, _line_number: i32) {
+        self.operation("[]=", &[object, index, value]);
+    }
+
+    fn visit_slice(
+        &mut self,
+        object: &Node,
+        start: Option<&Node>,
+        end: Option<&Node>,
+        _line_number: i32,
+    ) {
+        self.visit_node(object);
+        for bound in [start, end] {
+            match bound {
+                Some(bound) => self.visit_node(bound),
+                None => self.word("_"),
+            }
+        }
+        self.word("[:]");
+    }
+
+    fn visit_this(&mut self, _line_number: i32) {
+        self.word("this");
+    }
+
+    fn visit_super(&mut self, method: &Symbol, _line_number: i32) {
+        self.word(&format!("super.{}", method.as_str()));
+    }
+
+    fn visit_variable(&mut self, name: &Symbol, _line_number: i32) {
+        self.word(name.as_str());
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) {
+        let mut word = String::new();
+        write_literal(&mut word, literal);
+        self.word(&word);
+    }
+}
+
+fn write_literal(output: &mut String, literal: &Literal) {
+    match literal {
+        Literal::Nil => output.push_str("nil"),
+        Literal::True => output.push_str("true"),
+        Literal::False => output.push_str("false"),
+        Literal::Number(number) => output.push_str(&number.to_string()),
+        Literal::String(string) => {
+            output.push('"');
+            output.push_str(string.as_str());
+            output.push('"');
+        }
+    }
+}
+
+// Increments and decrements are told apart by where their operator is, like `pre++` or `post--`.
+fn increment(operator: &IncrementOperator, fixity: &Fixity) -> String {
+    match fixity {
+        Fixity::Prefix => format!("pre{}", operator.as_str()),
+        Fixity::Postfix => format!("post{}", operator.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::scanner::Scanner;
+
+    use super::{AstPrinter, RpnPrinter};
+
+    struct TestCase<'a> {
+        input: &'a str,
+        expected_output: &'a str,
+    }
+
+    fn parse(input: &str) -> crate::ast::Node {
+        let tokens = Scanner::new().scan(input).unwrap();
+        RecursiveDescentParser::new().parse(&tokens).unwrap()
+    }
+
+    #[test]
+    fn expressions_are_printed_as_s_expressions() {
+        let test_cases = [
+            TestCase {
+                input: "-123 * (45.67)",
+                expected_output: "(* (- 123) (group 45.67))",
+            },
+            TestCase {
+                input: "a = b or !c, \"d\"",
+                expected_output: "(, (= a (or b (! c))) \"d\")",
+            },
+            TestCase {
+                input: "f(1)(g(), nil)",
+                expected_output: "(call (call f 1) (call g) nil)",
+            },
+            TestCase {
+                input: "a[0] = [b[1:], b[:-1], []]",
+                expected_output: "([]= a 0 (list (slice b 1 ()) (slice b () (- 1)) (list)))",
+            },
+            TestCase {
+                input: "{\"a\": {}, 1: b[\"c\"]}",
+                expected_output: "(map \"a\" (map) 1 ([] b \"c\"))",
+            },
+            TestCase {
+                input: "a.b += c ? -d ** 2 : e++ % --f",
+                expected_output: "(+= (. b a) (?: c (- (** d 2)) (% (post++ e) (pre-- f))))",
+            },
+            TestCase {
+                input: "~a | b ^ c & d << 1",
+                expected_output: "(| (~ a) (^ b (& c (<< d 1))))",
+            },
+        ];
+
+        for test_case in test_cases {
+            let output = AstPrinter::print(&parse(test_case.input));
+            assert_eq!(output, test_case.expected_output);
+        }
+    }
+
+    #[test]
+    fn expressions_are_printed_in_reverse_polish_notation() {
+        let test_cases = [
+            TestCase {
+                input: "(1 + 2) * (4 - 3)",
+                expected_output: "1 2 + 4 3 - *",
+            },
+            TestCase {
+                input: "-123 * (45.67)",
+                expected_output: "123 neg 45.67 *",
+            },
+            TestCase {
+                input: "a = b or !c, \"d\"",
+                expected_output: "a b c ! or = \"d\" ,",
+            },
+            TestCase {
+                input: "f(1)(g(), nil)",
+                expected_output: "f 1 call/1 g call/0 nil call/2",
+            },
+            TestCase {
+                input: "a.b.c = this.d(super.e)",
+                expected_output: "a .b this .d super.e call/1 .c=",
+            },
+            TestCase {
+                input: "a[0] = [b[1:], b[i], []]",
+                expected_output: "a 0 b 1 _ [:] b i [] list/0 list/3 []=",
+            },
+            TestCase {
+                input: "{\"a\": {}, 1: b}",
+                expected_output: "\"a\" map/0 1 b map/2",
+            },
+            TestCase {
+                input: "a[0] *= b ? c++ : 2 ** 3 ~/ --d",
+                expected_output: "a 0 [] b c post++ 2 3 ** d pre-- ~/ ?: *=",
+            },
+            TestCase {
+                input: "~a | b ^ c & d >> 1",
+                expected_output: "a ~ b c d 1 >> & ^ |",
+            },
+        ];
+
+        for test_case in test_cases {
+            let output = RpnPrinter::print(&parse(test_case.input));
+            assert_eq!(output, test_case.expected_output);
+        }
+    }
+
+    #[test]
+    fn statements_are_printed_one_per_line() {
+        let test_cases = [
+            TestCase {
+                input: "var a; var b = 1; print a; a;",
+                expected_output: "(var a)\n(var b 1)\n(print a)\n(; a)\n",
+            },
+            TestCase {
+                input: "if (a) {} else if (b) print 1; while (true) {}",
+                expected_output: "(if a (block) (if b (print 1)))\n(while true (block))\n",
+            },
+            TestCase {
+                input: "for (;;) {} for (i = 0; i; i = nil) {}",
+                expected_output: "(for () () () (block))\n(for (; (= i 0)) i (= i nil) (block))\n",
+            },
+            TestCase {
+                input: "fun f(a, b) { return; } fun g() { return 1; }",
+                expected_output: "(fun f (a b) (return))\n(fun g () (return 1))\n",
+            },
+            TestCase {
+                input: "var a: Number; fun f(a: String, b): Bool {}",
+                expected_output: "(var a: Number)\n(fun f (a: String b): Bool)\n",
+            },
+            TestCase {
+                input: "class A < B { m(a) { this.a = super.m; } } class C {}",
+                expected_output: "(class A < B (fun m (a) (; (.= a this (super m)))))\n(class C)\n",
+            },
+            TestCase {
+                input: "import \"a.lox\" as a; import { b, c } from \"c\"; export fun d() {}",
+                expected_output:
+                    "(import \"a.lox\" as a)\n(import \"c\" (b c))\n(export (fun d ()))\n",
+            },
+            TestCase {
+                input: "try { throw 1; } catch (e) {} try {} finally { print e; }",
+                expected_output: "(try (block (throw 1)) (catch e (block)))\n(try (block) (finally (block (print e))))\n",
+            },
+            TestCase {
+                input: "a: while (true) for (;;) { break a; continue; }",
+                expected_output: "(label a (while true (for () () () (block (break a) (continue)))))\n",
+            },
+        ];
+
+        for test_case in test_cases {
+            let tokens = Scanner::new().scan(test_case.input).unwrap();
+            let statements = RecursiveDescentParser::new()
+                .parse_program(&tokens)
+                .unwrap();
+
+            assert_eq!(
+                AstPrinter::print_program(&statements),
+                test_case.expected_output
+            );
+        }
+    }
+}
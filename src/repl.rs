@@ -0,0 +1,151 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Config, Context, EditMode, Editor, Helper};
+
+use crate::interpreter::Interpreter;
+use crate::token::TokenType;
+
+const HISTORY_FILENAME: &str = ".rustlox_history";
+const MAX_HISTORY_SIZE: usize = 1000;
+
+/// Runs the interactive prompt on a terminal with emacs-style line editing, history saved in
+/// `~/.rustlox_history`, and tab completion of keywords and globals.
+pub fn run(interpreter: &mut Interpreter) -> Result<(), std::io::Error> {
+    let config = Config::builder()
+        .edit_mode(EditMode::Emacs)
+        .max_history_size(MAX_HISTORY_SIZE)
+        .map_err(std::io::Error::other)?
+        .auto_add_history(false)
+        .build();
+    let mut editor: Editor<LoxHelper, DefaultHistory> =
+        Editor::with_config(config).map_err(into_io_error)?;
+    editor.set_helper(Some(LoxHelper::default()));
+
+    let history_path = history_path();
+    if let Some(history_path) = &history_path {
+        // A missing or unreadable history file just means starting without history.
+        let _ = editor.load_history(history_path);
+    }
+
+    let result = interpreter.run_prompt_with(|interpreter, prompt| {
+        if let Some(helper) = editor.helper_mut() {
+            helper.globals = interpreter.global_names();
+        }
+        match editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    editor.add_history_entry(line.as_str()).map_err(into_io_error)?;
+                }
+                Ok(Some(line))
+            }
+            Err(ReadlineError::Eof) => Ok(None),
+            Err(error) => Err(into_io_error(error)),
+        }
+    });
+
+    if let Some(history_path) = &history_path {
+        // Losing the history isn't worth failing the session over.
+        let _ = editor.save_history(history_path);
+    }
+    result
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILENAME))
+}
+
+fn into_io_error(error: ReadlineError) -> std::io::Error {
+    match error {
+        ReadlineError::Io(error) => error,
+        ReadlineError::Interrupted => std::io::Error::from(ErrorKind::Interrupted),
+        error => std::io::Error::other(error),
+    }
+}
+
+#[derive(Default)]
+struct LoxHelper {
+    globals: Vec<String>,
+}
+
+impl Completer for LoxHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, words) = complete_word(line, pos, &self.globals);
+        let candidates = words
+            .into_iter()
+            .map(|word| Pair {
+                display: word.clone(),
+                replacement: word,
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for LoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LoxHelper {}
+
+impl Validator for LoxHelper {}
+
+impl Helper for LoxHelper {}
+
+/// Finds the identifier ending at `pos` and returns where it starts, along with the keywords and
+/// globals it is a prefix of, sorted and without duplicates.
+fn complete_word(line: &str, pos: usize, globals: &[String]) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, character)| character.is_alphanumeric() || *character == '_')
+        .last()
+        .map_or(pos, |(index, _)| index);
+    let prefix = &line[start..pos];
+    if prefix.is_empty() {
+        return (pos, Vec::new());
+    }
+
+    let mut words: Vec<String> = TokenType::KEYWORDS
+        .iter()
+        .map(|keyword| keyword.to_string())
+        .chain(globals.iter().cloned())
+        .filter(|word| word.starts_with(prefix))
+        .collect();
+    words.sort();
+    words.dedup();
+    (start, words)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repl::complete_word;
+
+    #[test]
+    fn completes_keywords_and_globals_at_the_cursor() {
+        let globals = vec!["print_all".to_string(), "clock".to_string()];
+
+        assert_eq!(
+            complete_word("var x = pr", 10, &globals),
+            (8, vec!["print".to_string(), "print_all".to_string()])
+        );
+        assert_eq!(
+            complete_word("cl + 1", 2, &globals),
+            (0, vec!["class".to_string(), "clock".to_string()])
+        );
+        assert_eq!(complete_word("1 + ", 4, &globals), (4, vec![]));
+    }
+}
@@ -0,0 +1,634 @@
+use std::collections::HashMap;
+
+use crate::ast::{Catch, Class, Function, Imports, Node, Statement};
+use crate::symbol::Symbol;
+use crate::visit::{walk_node, Visitor};
+
+/// Checks how the variables of a program are scoped without running it, and records in each use
+/// of a variable how many scopes out it was declared. Variables declared at the top level are
+/// globals and may be redeclared or used before they are defined, but locals are checked
+/// statically.
+#[derive(Default)]
+pub struct Resolver {
+    // One map per enclosing block or function, innermost last, recording whether each local has
+    // finished being initialized and the occurrence that declared it, which `this` and `super`
+    // don't have.
+    scopes: Vec<HashMap<Symbol, (bool, Option<usize>)>>,
+    // The first declaration of each global.
+    globals: HashMap<Symbol, usize>,
+    // References that weren't to a local, resolved once every global has been declared.
+    global_references: Vec<(usize, Symbol)>,
+    occurrences: Vec<Occurrence>,
+    function_type: FunctionType,
+    class_type: ClassType,
+    // The loops of the function being resolved, innermost last, with the label of each one that
+    // has one and the occurrence that declared it.
+    loops: Vec<Option<(Symbol, usize)>>,
+    errors: Vec<Error>,
+}
+
+/// What an identifier of a program stands for. The resolver records one for each identifier, in
+/// the order they appear in the source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Occurrence {
+    Declaration(DeclarationKind),
+    /// A use of a variable, with the index of the occurrence that declared it, or `None` when it
+    /// isn't declared by the program, like a native function.
+    Reference(Option<usize>),
+    /// The name of a property, which is only looked up when the program runs.
+    Property,
+    /// The name of a type in an annotation, with the index of the class declaration it names, or
+    /// `None` for a built-in type like `Number`.
+    Type(Option<usize>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeclarationKind {
+    Variable,
+    Function,
+    Parameter,
+    Class,
+    Method,
+    Label,
+}
+
+impl DeclarationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeclarationKind::Variable => "variable",
+            DeclarationKind::Function => "function",
+            DeclarationKind::Parameter => "parameter",
+            DeclarationKind::Class => "class",
+            DeclarationKind::Method => "method",
+            DeclarationKind::Label => "label",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum FunctionType {
+    #[default]
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum ClassType {
+    #[default]
+    None,
+    Class,
+    Subclass,
+}
+
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver::default()
+    }
+
+    /// Resolves a whole program, returning every error found.
+    pub fn resolve(&mut self, statements: &[Statement]) -> Result<(), Vec<Error>> {
+        self.scopes.clear();
+        self.globals.clear();
+        self.global_references.clear();
+        self.occurrences.clear();
+        self.loops.clear();
+        self.errors.clear();
+        for statement in statements {
+            self.visit_statement(statement);
+        }
+        for (index, name) in std::mem::take(&mut self.global_references) {
+            let declaration = self.globals.get(&name).copied();
+            self.occurrences[index] = match self.occurrences[index] {
+                Occurrence::Type(_) => Occurrence::Type(declaration),
+                _ => Occurrence::Reference(declaration),
+            };
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// The identifiers of the program last resolved, in source order.
+    pub fn occurrences(&self) -> &[Occurrence] {
+        &self.occurrences
+    }
+
+    fn declare(&mut self, name: &Symbol, kind: DeclarationKind, line_number: i32) {
+        let index = self.occur(Occurrence::Declaration(kind));
+        let Some(scope) = self.scopes.last_mut() else {
+            self.globals.entry(name.clone()).or_insert(index);
+            return;
+        };
+        if scope.insert(name.clone(), (false, Some(index))).is_some() {
+            self.error(
+                line_number,
+                "Already a variable with this name in this scope.",
+            );
+        }
+    }
+
+    fn define(&mut self, name: &Symbol) {
+        if let Some(scope) = self.scopes.last_mut() {
+            if let Some((initialized, _)) = scope.get_mut(name) {
+                *initialized = true;
+            }
+        }
+    }
+
+    fn reference(&mut self, name: &Symbol) {
+        let local = self.local(name);
+        let index = self.occur(Occurrence::Reference(local));
+        if local.is_none() {
+            self.global_references.push((index, name.clone()));
+        }
+    }
+
+    // Type names are looked up like variables, but aren't checked until types are.
+    fn type_name(&mut self, annotation: Option<&Symbol>) {
+        let Some(name) = annotation else {
+            return;
+        };
+        let local = self.local(name);
+        let index = self.occur(Occurrence::Type(local));
+        if local.is_none() {
+            self.global_references.push((index, name.clone()));
+        }
+    }
+
+    fn local(&self, name: &Symbol) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).map(|(_, index)| *index))
+            .flatten()
+    }
+
+    // How many scopes out a variable is declared. A global counts as declared just outside the
+    // outermost scope, which is where the program's globals are when it runs.
+    fn depth(&self, name: &Symbol) -> usize {
+        self.scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(name))
+            .unwrap_or(self.scopes.len())
+    }
+
+    // Binds `this` or `super` in a new scope, matching the scope the interpreter binds it in.
+    fn begin_implicit_scope(&mut self, name: &str) {
+        let scope = HashMap::from([(Symbol::intern(name), (true, None))]);
+        self.scopes.push(scope);
+    }
+
+    fn occur(&mut self, occurrence: Occurrence) -> usize {
+        self.occurrences.push(occurrence);
+        self.occurrences.len() - 1
+    }
+
+    fn resolve_function(&mut self, function: &Function, function_type: FunctionType) {
+        let enclosing = std::mem::replace(&mut self.function_type, function_type);
+        let enclosing_loops = std::mem::take(&mut self.loops);
+        self.scopes.push(HashMap::new());
+        for (parameter, annotation) in function.parameters.iter().zip(&function.parameter_types) {
+            self.declare(parameter, DeclarationKind::Parameter, function.line_number);
+            self.define(parameter);
+            self.type_name(annotation.as_ref());
+        }
+        self.type_name(function.return_type.as_ref());
+        for statement in &function.body {
+            self.visit_statement(statement);
+        }
+        self.scopes.pop();
+        self.loops = enclosing_loops;
+        self.function_type = enclosing;
+    }
+
+    // Checks that a `break` or `continue` is in a loop, and in one with the label it names.
+    fn jump(&mut self, keyword: &str, label: Option<&Symbol>, line_number: i32) {
+        if self.loops.is_empty() {
+            self.error(line_number, &format!("Can't {} outside a loop.", keyword));
+        }
+        let Some(label) = label else {
+            return;
+        };
+        let declaration = self.loops.iter().rev().find_map(|labelled| match labelled {
+            Some((name, index)) if name == label => Some(*index),
+            _ => None,
+        });
+        self.occur(Occurrence::Reference(declaration));
+        if declaration.is_none() && !self.loops.is_empty() {
+            self.error(line_number, &format!("Undefined label '{}'.", label));
+        }
+    }
+
+    fn error(&mut self, line_number: i32, message: &str) {
+        self.errors.push(Error::new(line_number, message));
+    }
+}
+
+impl Visitor for Resolver {
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::Assign(name, _, depth, _) | Node::Variable(name, depth, _) => {
+                depth.set(self.depth(name))
+            }
+            Node::This(depth, _) => depth.set(self.depth(&Symbol::intern("this"))),
+            Node::Super(_, depth, _) => depth.set(self.depth(&Symbol::intern("super"))),
+            _ => {}
+        }
+        walk_node(self, node);
+    }
+
+    fn visit_var(
+        &mut self,
+        name: &Symbol,
+        annotation: Option<&Symbol>,
+        initializer: Option<&Node>,
+        line_number: i32,
+    ) {
+        self.declare(name, DeclarationKind::Variable, line_number);
+        self.type_name(annotation);
+        if let Some(initializer) = initializer {
+            self.visit_node(initializer);
+        }
+        self.define(name);
+    }
+
+    // The variable of a catch clause is scoped to its block.
+    fn visit_try(&mut self, body: &Statement, catch: Option<&Catch>, finally: Option<&Statement>) {
+        self.visit_statement(body);
+        if let Some(catch) = catch {
+            self.scopes.push(HashMap::new());
+            self.declare(&catch.name, DeclarationKind::Variable, catch.line_number);
+            self.define(&catch.name);
+            self.visit_statement(&catch.body);
+            self.scopes.pop();
+        }
+        if let Some(finally) = finally {
+            self.visit_statement(finally);
+        }
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) {
+        self.scopes.push(HashMap::new());
+        for statement in statements {
+            self.visit_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn visit_while(&mut self, condition: &Node, body: &Statement) {
+        self.visit_node(condition);
+        self.loops.push(None);
+        self.visit_statement(body);
+        self.loops.pop();
+    }
+
+    fn visit_for(
+        &mut self,
+        initializer: Option<&Statement>,
+        condition: Option<&Node>,
+        increment: Option<&Node>,
+        body: &Statement,
+    ) {
+        self.scopes.push(HashMap::new());
+        if let Some(initializer) = initializer {
+            self.visit_statement(initializer);
+        }
+        if let Some(condition) = condition {
+            self.visit_node(condition);
+        }
+        if let Some(increment) = increment {
+            self.visit_node(increment);
+        }
+        self.loops.push(None);
+        self.visit_statement(body);
+        self.loops.pop();
+        self.scopes.pop();
+    }
+
+    // A labelled loop is entered twice, once with its label and once without, which is the same
+    // to the `break` and `continue` statements in it.
+    fn visit_labelled(&mut self, label: &Symbol, body: &Statement) {
+        let index = self.occur(Occurrence::Declaration(DeclarationKind::Label));
+        self.loops.push(Some((label.clone(), index)));
+        self.visit_statement(body);
+        self.loops.pop();
+    }
+
+    fn visit_break(&mut self, label: Option<&Symbol>, line_number: i32) {
+        self.jump("break", label, line_number);
+    }
+
+    fn visit_continue(&mut self, label: Option<&Symbol>, line_number: i32) {
+        self.jump("continue", label, line_number);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        // Defined before its body is resolved so that it can call itself.
+        self.declare(
+            &function.name,
+            DeclarationKind::Function,
+            function.line_number,
+        );
+        self.define(&function.name);
+        self.resolve_function(function, FunctionType::Function);
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        self.declare(&class.name, DeclarationKind::Class, class.line_number);
+        self.define(&class.name);
+
+        let enclosing = std::mem::replace(&mut self.class_type, ClassType::Class);
+        if let Some(superclass) = &class.superclass {
+            if matches!(superclass, Node::Variable(name, ..) if *name == class.name) {
+                self.error(class.line_number, "A class can't inherit from itself.");
+            }
+            self.visit_node(superclass);
+            self.class_type = ClassType::Subclass;
+            self.begin_implicit_scope("super");
+        }
+        self.begin_implicit_scope("this");
+        for method in &class.methods {
+            self.occur(Occurrence::Declaration(DeclarationKind::Method));
+            let function_type = if method.name.as_str() == "init" {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Method
+            };
+            self.resolve_function(method, function_type);
+        }
+        self.scopes.pop();
+        if class.superclass.is_some() {
+            self.scopes.pop();
+        }
+        self.class_type = enclosing;
+    }
+
+    fn visit_return(&mut self, value: Option<&Node>, line_number: i32) {
+        if let Some(value) = value {
+            if self.function_type == FunctionType::Initializer {
+                self.error(line_number, "Can't return a value from an initializer.");
+            }
+            self.visit_node(value);
+        }
+    }
+
+    // Modules are only imported and exported from by their top level, so their globals are known
+    // before anything in them runs.
+    fn visit_import(&mut self, _path: &Symbol, imports: &Imports, line_number: i32) {
+        if !self.scopes.is_empty() {
+            self.error(line_number, "Can only import at the top level.");
+        }
+        let names = match imports {
+            Imports::Module(name) => std::slice::from_ref(name),
+            Imports::Names(names) => names.as_slice(),
+        };
+        for name in names {
+            self.declare(name, DeclarationKind::Variable, line_number);
+            self.define(name);
+        }
+    }
+
+    fn visit_export(&mut self, declaration: &Statement) {
+        if !self.scopes.is_empty() {
+            let line_number = match declaration {
+                Statement::Var(.., line_number) => *line_number,
+                Statement::Function(function) => function.line_number,
+                Statement::Class(class) => class.line_number,
+                _ => unreachable!("only declarations are exported"),
+            };
+            self.error(line_number, "Can only export at the top level.");
+        }
+        self.visit_statement(declaration);
+    }
+
+    fn visit_assign(&mut self, name: &Symbol, value: &Node, _line_number: i32) {
+        self.reference(name);
+        self.visit_node(value);
+    }
+
+    fn visit_get(&mut self, object: &Node, _name: &Symbol, _line_number: i32) {
+        self.visit_node(object);
+        self.occur(Occurrence::Property);
+    }
+
+    fn visit_set(&mut self, object: &Node, _name: &Symbol, value: &Node, _line_number: i32) {
+        self.visit_node(object);
+        self.occur(Occurrence::Property);
+        self.visit_node(value);
+    }
+
+    fn visit_this(&mut self, line_number: i32) {
+        if self.class_type == ClassType::None {
+            self.error(line_number, "Can't use 'this' outside of a class.");
+        }
+    }
+
+    fn visit_super(&mut self, _method: &Symbol, line_number: i32) {
+        match self.class_type {
+            ClassType::None => self.error(line_number, "Can't use 'super' outside of a class."),
+            ClassType::Class => self.error(
+                line_number,
+                "Can't use 'super' in a class with no superclass.",
+            ),
+            ClassType::Subclass => {}
+        }
+        self.occur(Occurrence::Property);
+    }
+
+    fn visit_variable(&mut self, name: &Symbol, line_number: i32) {
+        let being_initialized = self
+            .scopes
+            .last()
+            .is_some_and(|scope| scope.get(name).is_some_and(|(initialized, _)| !initialized));
+        if being_initialized {
+            self.error(
+                line_number,
+                "Can't read local variable in its own initializer.",
+            );
+        }
+        self.reference(name);
+    }
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub line_number: i32,
+    pub message: String,
+}
+
+impl Error {
+    fn new(line_number: i32, message: &str) -> Error {
+        Error {
+            line_number,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::resolver::{DeclarationKind, Occurrence, Resolver};
+    use crate::scanner::Scanner;
+
+    struct TestCase {
+        input: &'static str,
+        expected_output: Vec<(i32, &'static str)>,
+    }
+
+    #[test]
+    fn locals_are_checked_statically() {
+        let test_cases = vec![
+            TestCase {
+                input: "var a = 1; var a = a; { var b = a; { var b = 2; } }",
+                expected_output: vec![],
+            },
+            TestCase {
+                input: "{\nvar a = 1;\nvar a = 2;\n}",
+                expected_output: vec![(3, "Already a variable with this name in this scope.")],
+            },
+            TestCase {
+                input: "fun f(a,\n a) {}",
+                expected_output: vec![(1, "Already a variable with this name in this scope.")],
+            },
+            TestCase {
+                input: "var a = 1;\n{\n  var a = a + 1;\n}",
+                expected_output: vec![(3, "Can't read local variable in its own initializer.")],
+            },
+            TestCase {
+                input: "fun f() { fun g() { return f; } var g = 1; }",
+                expected_output: vec![(1, "Already a variable with this name in this scope.")],
+            },
+            TestCase {
+                input: "class A { init() { return; fun f() { return 1; } } }\nclass B < A { m() { return super.m(this); } }",
+                expected_output: vec![],
+            },
+            TestCase {
+                input: "class A < A {}\nthis;\nfun f() { super.f(); }\nclass B { m() { super.m(); } }\nclass C { init() { return 1; } }",
+                expected_output: vec![
+                    (1, "A class can't inherit from itself."),
+                    (2, "Can't use 'this' outside of a class."),
+                    (3, "Can't use 'super' outside of a class."),
+                    (4, "Can't use 'super' in a class with no superclass."),
+                    (5, "Can't return a value from an initializer."),
+                ],
+            },
+            TestCase {
+                input: "import \"a.lox\" as a;\nexport var b = a;\n{\n  import { c } from \"c.lox\";\n  print c;\n  export fun d() {}\n}",
+                expected_output: vec![
+                    (4, "Can only import at the top level."),
+                    (6, "Can only export at the top level."),
+                ],
+            },
+            TestCase {
+                input: "break;
+while (true) {
+  fun f() { continue; }
+  a: for (;;) b: while (nil) continue a;
+  break b;
+}",
+                expected_output: vec![
+                    (1, "Can't break outside a loop."),
+                    (3, "Can't continue outside a loop."),
+                    (5, "Undefined label 'b'."),
+                ],
+            },
+        ];
+
+        for test_case in test_cases {
+            let tokens = Scanner::new().scan(test_case.input).unwrap();
+            let statements = RecursiveDescentParser::new()
+                .parse_program(&tokens)
+                .unwrap();
+            let errors = match Resolver::new().resolve(&statements) {
+                Ok(()) => vec![],
+                Err(errors) => errors
+                    .into_iter()
+                    .map(|error| (error.line_number, error.message))
+                    .collect(),
+            };
+            let expected_output: Vec<(i32, String)> = test_case
+                .expected_output
+                .into_iter()
+                .map(|(line_number, message)| (line_number, message.to_string()))
+                .collect();
+            assert_eq!(errors, expected_output, "{}", test_case.input);
+        }
+    }
+
+    #[test]
+    fn identifiers_are_recorded_in_source_order() {
+        let tokens = Scanner::new()
+            .scan("var a = clock; class B < C { m(d) { { var a = d; } return a.e = f(); } } var C;")
+            .unwrap();
+        let statements = RecursiveDescentParser::new()
+            .parse_program(&tokens)
+            .unwrap();
+        let mut resolver = Resolver::new();
+        resolver.resolve(&statements).unwrap();
+
+        assert_eq!(
+            resolver.occurrences(),
+            [
+                Occurrence::Declaration(DeclarationKind::Variable),
+                Occurrence::Reference(None),
+                Occurrence::Declaration(DeclarationKind::Class),
+                Occurrence::Reference(Some(11)),
+                Occurrence::Declaration(DeclarationKind::Method),
+                Occurrence::Declaration(DeclarationKind::Parameter),
+                Occurrence::Declaration(DeclarationKind::Variable),
+                Occurrence::Reference(Some(5)),
+                Occurrence::Reference(Some(0)),
+                Occurrence::Property,
+                Occurrence::Reference(None),
+                Occurrence::Declaration(DeclarationKind::Variable),
+            ]
+        );
+
+        let tokens = Scanner::new()
+            .scan("fun f(a: A, b: Number): A {} class A {}")
+            .unwrap();
+        let statements = RecursiveDescentParser::new()
+            .parse_program(&tokens)
+            .unwrap();
+        resolver.resolve(&statements).unwrap();
+
+        assert_eq!(
+            resolver.occurrences(),
+            [
+                Occurrence::Declaration(DeclarationKind::Function),
+                Occurrence::Declaration(DeclarationKind::Parameter),
+                Occurrence::Type(Some(6)),
+                Occurrence::Declaration(DeclarationKind::Parameter),
+                Occurrence::Type(None),
+                Occurrence::Type(Some(6)),
+                Occurrence::Declaration(DeclarationKind::Class),
+            ]
+        );
+
+        let tokens = Scanner::new()
+            .scan("a: while (a) { b: for (;;) break a; continue b; }")
+            .unwrap();
+        let statements = RecursiveDescentParser::new()
+            .parse_program(&tokens)
+            .unwrap();
+        let _ = resolver.resolve(&statements);
+
+        assert_eq!(
+            resolver.occurrences(),
+            [
+                Occurrence::Declaration(DeclarationKind::Label),
+                Occurrence::Reference(None),
+                Occurrence::Declaration(DeclarationKind::Label),
+                Occurrence::Reference(Some(0)),
+                Occurrence::Reference(None),
+            ]
+        );
+    }
+}
@@ -4,48 +4,157 @@ pub struct Scanner<'a> {
     cursor: usize,
     lexeme_start: usize,
     line_number: i32,
+    keep_trivia: bool,
     pub tokens: Vec<Token<'a>>,
 }
 
+impl Default for Scanner<'_> {
+    fn default() -> Self {
+        Scanner::new()
+    }
+}
+
 impl<'a> Scanner<'a> {
     pub fn new() -> Scanner<'a> {
         Scanner {
             cursor: 0,
             lexeme_start: 0,
             line_number: 1,
+            keep_trivia: false,
             tokens: Vec::new(),
         }
     }
 
+    /// A scanner that also produces `Whitespace` and `Comment` tokens, for tools that need to
+    /// preserve the source exactly.
+    pub fn with_trivia() -> Scanner<'a> {
+        Scanner {
+            keep_trivia: true,
+            ..Scanner::new()
+        }
+    }
+
     // [TODO]: Improve the API of Scanner.scan so that it returns a Vec<Token> without re-allocating
     // the entire backing array.
     pub fn scan(&mut self, source_code: &'a str) -> Result<Vec<Token<'a>>, Error> {
         self.tokens = Vec::new();
 
         let characters: Vec<char> = source_code.chars().collect();
+        // The byte offset of each character, and of the end of the source, for slicing lexemes.
+        let offsets: Vec<usize> = source_code
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(source_code.len()))
+            .collect();
         'scan: while self.cursor < characters.len() {
+            let next = characters.get(self.cursor + 1).copied();
             match characters[self.cursor] {
                 '(' => self.add_token(TokenType::LeftParen, "("),
                 ')' => self.add_token(TokenType::RightParen, ")"),
                 '{' => self.add_token(TokenType::LeftBrace, "{"),
                 '}' => self.add_token(TokenType::RightBrace, "}"),
+                '[' => self.add_token(TokenType::LeftBracket, "["),
+                ']' => self.add_token(TokenType::RightBracket, "]"),
                 ';' => self.add_token(TokenType::Semicolon, ";"),
                 ',' => self.add_token(TokenType::Comma, ","),
+                ':' => self.add_token(TokenType::Colon, ":"),
                 '.' => self.add_token(TokenType::Dot, "."),
-                '+' => self.add_token(TokenType::Plus, "+"),
-                '-' => self.add_token(TokenType::Minus, "-"),
-                '*' => self.add_token(TokenType::Star, "*"),
+                '?' => self.add_token(TokenType::Question, "?"),
+                '&' => match next {
+                    Some('=') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::AmpersandEqual, "&=")
+                    }
+                    _ => self.add_token(TokenType::Ampersand, "&"),
+                },
+                '|' => match next {
+                    Some('=') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::PipeEqual, "|=")
+                    }
+                    _ => self.add_token(TokenType::Pipe, "|"),
+                },
+                '^' => match next {
+                    Some('=') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::CaretEqual, "^=")
+                    }
+                    _ => self.add_token(TokenType::Caret, "^"),
+                },
+                '+' => match next {
+                    Some('+') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::PlusPlus, "++")
+                    }
+                    Some('=') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::PlusEqual, "+=")
+                    }
+                    _ => self.add_token(TokenType::Plus, "+"),
+                },
+                '-' => match next {
+                    Some('-') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::MinusMinus, "--")
+                    }
+                    Some('=') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::MinusEqual, "-=")
+                    }
+                    _ => self.add_token(TokenType::Minus, "-"),
+                },
+                '*' => match next {
+                    Some('*') if characters.get(self.cursor + 2) == Some(&'=') => {
+                        self.cursor += 2;
+                        self.add_token(TokenType::StarStarEqual, "**=")
+                    }
+                    Some('*') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::StarStar, "**")
+                    }
+                    Some('=') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::StarEqual, "*=")
+                    }
+                    _ => self.add_token(TokenType::Star, "*"),
+                },
+                '%' => match next {
+                    Some('=') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::PercentEqual, "%=")
+                    }
+                    _ => self.add_token(TokenType::Percent, "%"),
+                },
+                // Integer division is written `~/`, since `//` starts a comment.
+                '~' if next == Some('/') && characters.get(self.cursor + 2) == Some(&'=') => {
+                    self.cursor += 2;
+                    self.add_token(TokenType::TildeSlashEqual, "~/=")
+                }
+                '~' if next == Some('/') => {
+                    self.cursor += 1;
+                    self.add_token(TokenType::TildeSlash, "~/")
+                }
+                '~' => self.add_token(TokenType::Tilde, "~"),
+                '/' if next == Some('=') => {
+                    self.cursor += 1;
+                    self.add_token(TokenType::SlashEqual, "/=")
+                }
                 '/' => {
-                    if self.cursor + 1 < characters.len() && characters[self.cursor + 1] == '/' {
-                        'line_comment: loop {
+                    if next == Some('/') {
+                        // The newline ending the comment is left to be scanned as whitespace.
+                        while self.cursor < characters.len() && characters[self.cursor] != '\n' {
                             self.cursor += 1;
-                            if self.cursor >= characters.len() || characters[self.cursor] == '\n' {
-                                self.cursor += 1;
-                                self.lexeme_start = self.cursor;
-                                self.line_number += 1;
-                                break 'line_comment;
-                            }
                         }
+                        if self.keep_trivia {
+                            let comment =
+                                &source_code[offsets[self.lexeme_start]..offsets[self.cursor]];
+                            self.tokens.push(Token::new(
+                                TokenType::Comment,
+                                comment,
+                                self.line_number,
+                            ));
+                        }
+                        self.lexeme_start = self.cursor;
                     } else {
                         self.add_token(TokenType::Slash, "/")
                     }
@@ -66,52 +175,75 @@ impl<'a> Scanner<'a> {
                         self.add_token(TokenType::Equal, "=")
                     }
                 }
-                '>' => {
-                    if self.cursor + 1 < characters.len() && characters[self.cursor + 1] == '=' {
+                '>' => match next {
+                    Some('=') => {
                         self.cursor += 1;
                         self.add_token(TokenType::GreaterEqual, ">=")
-                    } else {
-                        self.add_token(TokenType::Greater, ">")
                     }
-                }
-                '<' => {
-                    if self.cursor + 1 < characters.len() && characters[self.cursor + 1] == '=' {
+                    Some('>') if characters.get(self.cursor + 2) == Some(&'=') => {
+                        self.cursor += 2;
+                        self.add_token(TokenType::GreaterGreaterEqual, ">>=")
+                    }
+                    Some('>') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::GreaterGreater, ">>")
+                    }
+                    _ => self.add_token(TokenType::Greater, ">"),
+                },
+                '<' => match next {
+                    Some('=') => {
                         self.cursor += 1;
                         self.add_token(TokenType::LessEqual, "<=")
-                    } else {
-                        self.add_token(TokenType::Less, "<")
                     }
-                }
+                    Some('<') if characters.get(self.cursor + 2) == Some(&'=') => {
+                        self.cursor += 2;
+                        self.add_token(TokenType::LessLessEqual, "<<=")
+                    }
+                    Some('<') => {
+                        self.cursor += 1;
+                        self.add_token(TokenType::LessLess, "<<")
+                    }
+                    _ => self.add_token(TokenType::Less, "<"),
+                },
                 '"' => 'string_literal: loop {
                     self.cursor += 1;
 
+                    if self.cursor >= characters.len() || characters[self.cursor] == '\n' {
+                        return Err(Error::new(self.line_number, "Unterminated string"));
+                    }
+
                     if characters[self.cursor] == '"' {
-                        let string_literal = &source_code[self.lexeme_start + 1..self.cursor];
+                        let string_literal =
+                            &source_code[offsets[self.lexeme_start + 1]..offsets[self.cursor]];
                         self.add_token(TokenType::String, string_literal);
                         break 'string_literal;
                     }
-
-                    if self.cursor >= characters.len() || characters[self.cursor] == '\n' {
-                        return Err(Error::new(self.line_number, "Unterminated string"));
-                    }
                 },
-                ' ' | '\r' | '\t' => {
-                    // Ignore whitespace.
-                    self.cursor += 1;
-                    self.lexeme_start = self.cursor;
-                }
-                '\n' => {
-                    self.cursor += 1;
+                ' ' | '\r' | '\t' | '\n' => {
+                    let line_number = self.line_number;
+                    while self.cursor < characters.len()
+                        && matches!(characters[self.cursor], ' ' | '\r' | '\t' | '\n')
+                    {
+                        if characters[self.cursor] == '\n' {
+                            self.line_number += 1;
+                        }
+                        self.cursor += 1;
+                    }
+                    if self.keep_trivia {
+                        let whitespace =
+                            &source_code[offsets[self.lexeme_start]..offsets[self.cursor]];
+                        self.tokens
+                            .push(Token::new(TokenType::Whitespace, whitespace, line_number));
+                    }
                     self.lexeme_start = self.cursor;
-                    self.line_number += 1;
                 }
                 _ => {
-                    if characters[self.cursor].is_digit(10) {
+                    if characters[self.cursor].is_ascii_digit() {
                         'number_literal: loop {
                             self.cursor += 1;
 
                             if self.cursor < characters.len()
-                                && characters[self.cursor].is_digit(10)
+                                && characters[self.cursor].is_ascii_digit()
                             {
                                 continue 'number_literal;
                             }
@@ -121,7 +253,7 @@ impl<'a> Scanner<'a> {
                                     self.cursor += 1;
 
                                     if self.cursor < characters.len()
-                                        && characters[self.cursor].is_digit(10)
+                                        && characters[self.cursor].is_ascii_digit()
                                     {
                                         continue 'number_decimal_loop;
                                     }
@@ -134,7 +266,8 @@ impl<'a> Scanner<'a> {
                         }
 
                         self.cursor -= 1;
-                        let number_literal = &source_code[self.lexeme_start..self.cursor + 1];
+                        let number_literal =
+                            &source_code[offsets[self.lexeme_start]..offsets[self.cursor + 1]];
                         self.add_token(TokenType::Number, number_literal);
 
                         continue 'scan;
@@ -151,8 +284,15 @@ impl<'a> Scanner<'a> {
                                 continue 'identifier_literal;
                             }
 
-                            let identifier_literal = &source_code[self.lexeme_start..self.cursor];
-                            let token_type = TokenType::from_str(identifier_literal);
+                            let identifier_literal =
+                                &source_code[offsets[self.lexeme_start]..offsets[self.cursor]];
+                            let token_type = match TokenType::from_str(identifier_literal) {
+                                TokenType::As | TokenType::From if !self.in_import() => {
+                                    TokenType::Identifier
+                                }
+                                token_type => token_type,
+                            };
+                            self.cursor -= 1;
                             self.add_token(token_type, identifier_literal);
                             break 'identifier_literal;
                         }
@@ -176,6 +316,17 @@ impl<'a> Scanner<'a> {
         self.cursor += 1;
         self.lexeme_start = self.cursor;
     }
+
+    // `as` and `from` are only keywords within an import declaration, so that they can still name
+    // variables everywhere else.
+    fn in_import(&self) -> bool {
+        self.tokens
+            .iter()
+            .rev()
+            .map(|token| token.token_type)
+            .find(|token_type| matches!(token_type, TokenType::Import | TokenType::Semicolon))
+            == Some(TokenType::Import)
+    }
 }
 
 #[derive(Debug)]
@@ -187,7 +338,7 @@ pub struct Error {
 impl Error {
     fn new(line_number: i32, message: &str) -> Error {
         Error {
-            line_number: line_number,
+            line_number,
             message: message.to_string(),
         }
     }
@@ -221,6 +372,14 @@ mod tests {
                 input: "}",
                 expected_output: vec![Token::new(TokenType::RightBrace, "}", 1)],
             },
+            TestCase {
+                input: "[",
+                expected_output: vec![Token::new(TokenType::LeftBracket, "[", 1)],
+            },
+            TestCase {
+                input: "]",
+                expected_output: vec![Token::new(TokenType::RightBracket, "]", 1)],
+            },
             TestCase {
                 input: ";",
                 expected_output: vec![Token::new(TokenType::Semicolon, ";", 1)],
@@ -229,6 +388,10 @@ mod tests {
                 input: ",",
                 expected_output: vec![Token::new(TokenType::Comma, ",", 1)],
             },
+            TestCase {
+                input: ":",
+                expected_output: vec![Token::new(TokenType::Colon, ":", 1)],
+            },
             TestCase {
                 input: ".",
                 expected_output: vec![Token::new(TokenType::Dot, ".", 1)],
@@ -364,4 +527,114 @@ mod tests {
             assert_eq!(output, test_case.expected_output);
         }
     }
+
+    #[test]
+    fn identifiers_are_terminated_by_punctuation() {
+        let output = Scanner::new().scan("clock()").unwrap();
+        let expected_output = vec![
+            Token::new(TokenType::Identifier, "clock", 1),
+            Token::new(TokenType::LeftParen, "(", 1),
+            Token::new(TokenType::RightParen, ")", 1),
+        ];
+
+        assert_eq!(output, expected_output);
+    }
+
+    #[test]
+    fn operators_are_scanned_greedily() {
+        let output = Scanner::new().scan("a++-- -=**=**%=%~/=~/ /=/?").unwrap();
+        let expected_output = vec![
+            Token::new(TokenType::Identifier, "a", 1),
+            Token::new(TokenType::PlusPlus, "++", 1),
+            Token::new(TokenType::MinusMinus, "--", 1),
+            Token::new(TokenType::MinusEqual, "-=", 1),
+            Token::new(TokenType::StarStarEqual, "**=", 1),
+            Token::new(TokenType::StarStar, "**", 1),
+            Token::new(TokenType::PercentEqual, "%=", 1),
+            Token::new(TokenType::Percent, "%", 1),
+            Token::new(TokenType::TildeSlashEqual, "~/=", 1),
+            Token::new(TokenType::TildeSlash, "~/", 1),
+            Token::new(TokenType::SlashEqual, "/=", 1),
+            Token::new(TokenType::Slash, "/", 1),
+            Token::new(TokenType::Question, "?", 1),
+        ];
+
+        assert_eq!(output, expected_output);
+    }
+
+    #[test]
+    fn as_and_from_are_only_keywords_in_imports() {
+        let output = Scanner::new()
+            .scan("import {a} from \"b\"; var from = as;")
+            .unwrap();
+        let expected_output = vec![
+            Token::new(TokenType::Import, "import", 1),
+            Token::new(TokenType::LeftBrace, "{", 1),
+            Token::new(TokenType::Identifier, "a", 1),
+            Token::new(TokenType::RightBrace, "}", 1),
+            Token::new(TokenType::From, "from", 1),
+            Token::new(TokenType::String, "b", 1),
+            Token::new(TokenType::Semicolon, ";", 1),
+            Token::new(TokenType::Var, "var", 1),
+            Token::new(TokenType::Identifier, "from", 1),
+            Token::new(TokenType::Equal, "=", 1),
+            Token::new(TokenType::Identifier, "as", 1),
+            Token::new(TokenType::Semicolon, ";", 1),
+        ];
+
+        assert_eq!(output, expected_output);
+    }
+
+    #[test]
+    fn bitwise_operators_are_scanned() {
+        let output = Scanner::new().scan("&|^~~/<<<=<>>>=>&=|=^=<<=>>=").unwrap();
+        let expected_output = vec![
+            Token::new(TokenType::Ampersand, "&", 1),
+            Token::new(TokenType::Pipe, "|", 1),
+            Token::new(TokenType::Caret, "^", 1),
+            Token::new(TokenType::Tilde, "~", 1),
+            Token::new(TokenType::TildeSlash, "~/", 1),
+            Token::new(TokenType::LessLess, "<<", 1),
+            Token::new(TokenType::LessEqual, "<=", 1),
+            Token::new(TokenType::Less, "<", 1),
+            Token::new(TokenType::GreaterGreater, ">>", 1),
+            Token::new(TokenType::GreaterEqual, ">=", 1),
+            Token::new(TokenType::Greater, ">", 1),
+            Token::new(TokenType::AmpersandEqual, "&=", 1),
+            Token::new(TokenType::PipeEqual, "|=", 1),
+            Token::new(TokenType::CaretEqual, "^=", 1),
+            Token::new(TokenType::LessLessEqual, "<<=", 1),
+            Token::new(TokenType::GreaterGreaterEqual, ">>=", 1),
+        ];
+
+        assert_eq!(output, expected_output);
+    }
+
+    #[test]
+    fn trivia_is_only_kept_when_asked_for() {
+        let input = "// café\nvar é =\t\"ü\"; // trailing  \n";
+        let output = Scanner::with_trivia().scan(input).unwrap();
+        let expected_output = vec![
+            Token::new(TokenType::Comment, "// café", 1),
+            Token::new(TokenType::Whitespace, "\n", 1),
+            Token::new(TokenType::Var, "var", 2),
+            Token::new(TokenType::Whitespace, " ", 2),
+            Token::new(TokenType::Identifier, "é", 2),
+            Token::new(TokenType::Whitespace, " ", 2),
+            Token::new(TokenType::Equal, "=", 2),
+            Token::new(TokenType::Whitespace, "\t", 2),
+            Token::new(TokenType::String, "ü", 2),
+            Token::new(TokenType::Semicolon, ";", 2),
+            Token::new(TokenType::Whitespace, " ", 2),
+            Token::new(TokenType::Comment, "// trailing  ", 2),
+            Token::new(TokenType::Whitespace, "\n", 2),
+        ];
+
+        assert_eq!(output, expected_output);
+        let tokens: Vec<_> = expected_output
+            .into_iter()
+            .filter(|token| !token.token_type.is_trivia())
+            .collect();
+        assert_eq!(Scanner::new().scan(input).unwrap(), tokens);
+    }
 }
@@ -0,0 +1,364 @@
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::interpreter::Interpreter;
+use crate::value::{Arity, Value};
+
+/// Registers the core standard library as globals of the interpreter.
+pub fn define_globals(interpreter: &mut Interpreter) {
+    interpreter.define_native("clock", Arity::Exact(0), clock);
+    interpreter.define_native("str", Arity::Exact(1), str);
+    interpreter.define_native("num", Arity::Exact(1), num);
+    interpreter.define_native("len", Arity::Exact(1), len);
+    interpreter.define_native("type", Arity::Exact(1), type_);
+    interpreter.define_native("input", Arity::Variadic, input);
+    interpreter.define_native("assert", Arity::Variadic, assert);
+
+    interpreter.define_native("abs", Arity::Exact(1), |_, arguments| {
+        math("abs", arguments, f64::abs)
+    });
+    interpreter.define_native("floor", Arity::Exact(1), |_, arguments| {
+        math("floor", arguments, f64::floor)
+    });
+    interpreter.define_native("ceil", Arity::Exact(1), |_, arguments| {
+        math("ceil", arguments, f64::ceil)
+    });
+    interpreter.define_native("round", Arity::Exact(1), |_, arguments| {
+        math("round", arguments, f64::round)
+    });
+    interpreter.define_native("sqrt", Arity::Exact(1), |_, arguments| {
+        math("sqrt", arguments, f64::sqrt)
+    });
+    interpreter.define_native("sin", Arity::Exact(1), |_, arguments| {
+        math("sin", arguments, f64::sin)
+    });
+    interpreter.define_native("cos", Arity::Exact(1), |_, arguments| {
+        math("cos", arguments, f64::cos)
+    });
+    interpreter.define_native("tan", Arity::Exact(1), |_, arguments| {
+        math("tan", arguments, f64::tan)
+    });
+    interpreter.define_native("exp", Arity::Exact(1), |_, arguments| {
+        math("exp", arguments, f64::exp)
+    });
+    interpreter.define_native("log", Arity::Exact(1), |_, arguments| {
+        math("log", arguments, f64::ln)
+    });
+    interpreter.define_native("pow", Arity::Exact(2), pow);
+    interpreter.define_native("min", Arity::Variadic, |_, arguments| {
+        fold_numbers("min", arguments, f64::min)
+    });
+    interpreter.define_native("max", Arity::Variadic, |_, arguments| {
+        fold_numbers("max", arguments, f64::max)
+    });
+
+    interpreter.define_native("substr", Arity::Exact(3), substr);
+    interpreter.define_native("upper", Arity::Exact(1), upper);
+    interpreter.define_native("lower", Arity::Exact(1), lower);
+    interpreter.define_native("trim", Arity::Exact(1), trim);
+    interpreter.define_native("index_of", Arity::Exact(2), index_of);
+    interpreter.define_native("replace", Arity::Exact(3), replace);
+    interpreter.define_native("split", Arity::Exact(2), split);
+}
+
+fn clock(_: &mut Interpreter, _: &[Value]) -> Result<Value, String> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|error| error.to_string())?;
+    Ok(Value::Number(elapsed.as_secs_f64()))
+}
+
+fn str(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::String(string) => Ok(Value::String(Rc::clone(string))),
+        value => Ok(Value::String(Rc::from(value.to_string()))),
+    }
+}
+
+fn num(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::Number(number) => Ok(Value::Number(*number)),
+        Value::String(string) => match string.trim().parse::<f64>() {
+            Ok(number) => Ok(Value::Number(number)),
+            Err(_) => Err(format!("Cannot convert \"{}\" to a number.", string)),
+        },
+        value => Err(format!("Cannot convert {} to a number.", value.type_name())),
+    }
+}
+
+fn len(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    if let Value::List(list) = &arguments[0] {
+        return Ok(Value::Number(list.borrow().len() as f64));
+    }
+    if let Value::Map(map) = &arguments[0] {
+        return Ok(Value::Number(map.borrow().len() as f64));
+    }
+    let string = expect_string("len", &arguments[0])?;
+    Ok(Value::Number(string.chars().count() as f64))
+}
+
+fn type_(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::String(Rc::from(arguments[0].type_name())))
+}
+
+/// Reads a line from the interpreter's input without its line terminator, returning nil at the
+/// end of the input. An optional argument is written as a prompt first.
+fn input(interpreter: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    if arguments.len() > 1 {
+        return Err(format!(
+            "Expected at most 1 arguments but got {}.",
+            arguments.len()
+        ));
+    }
+    if let Some(prompt) = arguments.first() {
+        let output = interpreter.output();
+        write!(output, "{}", prompt).map_err(|error| error.to_string())?;
+        output.flush().map_err(|error| error.to_string())?;
+    }
+
+    let mut line = String::new();
+    match interpreter.input().read_line(&mut line) {
+        Ok(0) => Ok(Value::Nil),
+        Ok(_) => {
+            let line = line.trim_end_matches(['\n', '\r']);
+            Ok(Value::String(Rc::from(line)))
+        }
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+fn assert(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    match arguments {
+        [condition] if condition.is_truthy() => Ok(Value::Nil),
+        [condition, _] if condition.is_truthy() => Ok(Value::Nil),
+        [_] => Err("Assertion failed.".to_string()),
+        [_, message] => Err(format!("Assertion failed: {}", message)),
+        _ => Err(format!(
+            "Expected 1 or 2 arguments but got {}.",
+            arguments.len()
+        )),
+    }
+}
+
+fn math(name: &str, arguments: &[Value], function: fn(f64) -> f64) -> Result<Value, String> {
+    let number = expect_number(name, &arguments[0])?;
+    Ok(Value::Number(function(number)))
+}
+
+fn pow(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let base = expect_number("pow", &arguments[0])?;
+    let exponent = expect_number("pow", &arguments[1])?;
+    Ok(Value::Number(base.powf(exponent)))
+}
+
+fn fold_numbers(
+    name: &str,
+    arguments: &[Value],
+    function: fn(f64, f64) -> f64,
+) -> Result<Value, String> {
+    let (first, rest) = arguments
+        .split_first()
+        .ok_or_else(|| format!("{}() expects at least 1 argument.", name))?;
+    let mut result = expect_number(name, first)?;
+    for argument in rest {
+        result = function(result, expect_number(name, argument)?);
+    }
+    Ok(Value::Number(result))
+}
+
+/// Returns the characters of a string from `start` up to but not including `end`.
+fn substr(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let string = expect_string("substr", &arguments[0])?;
+    let start = expect_index("substr", &arguments[1])?;
+    let end = expect_index("substr", &arguments[2])?;
+    let length = string.chars().count();
+    // The range is reported as it was passed, since indexes too large for a `usize` saturate.
+    if start > end || end > length {
+        return Err(format!(
+            "substr() range {}..{} is out of bounds for a string of length {}.",
+            arguments[1], arguments[2], length
+        ));
+    }
+
+    let substring: String = string.chars().skip(start).take(end - start).collect();
+    Ok(Value::String(Rc::from(substring)))
+}
+
+fn upper(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let string = expect_string("upper", &arguments[0])?;
+    Ok(Value::String(Rc::from(string.to_uppercase())))
+}
+
+fn lower(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let string = expect_string("lower", &arguments[0])?;
+    Ok(Value::String(Rc::from(string.to_lowercase())))
+}
+
+fn trim(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let string = expect_string("trim", &arguments[0])?;
+    Ok(Value::String(Rc::from(string.trim())))
+}
+
+/// Returns the character index of the first occurrence of a substring, or -1 if there is none.
+fn index_of(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let string = expect_string("index_of", &arguments[0])?;
+    let needle = expect_string("index_of", &arguments[1])?;
+    let index = match string.find(needle) {
+        Some(byte_index) => string[..byte_index].chars().count() as f64,
+        None => -1.0,
+    };
+    Ok(Value::Number(index))
+}
+
+fn replace(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let string = expect_string("replace", &arguments[0])?;
+    let from = expect_string("replace", &arguments[1])?;
+    let to = expect_string("replace", &arguments[2])?;
+    Ok(Value::String(Rc::from(string.replace(from, to))))
+}
+
+/// Returns a list of the parts of a string between occurrences of a separator, or of its
+/// characters if the separator is empty.
+fn split(_: &mut Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let string = expect_string("split", &arguments[0])?;
+    let separator = expect_string("split", &arguments[1])?;
+    let parts: Vec<Value> = if separator.is_empty() {
+        string
+            .chars()
+            .map(|character| Value::from(character.to_string()))
+            .collect()
+    } else {
+        string.split(separator).map(Value::from).collect()
+    };
+    Ok(Value::from(parts))
+}
+
+fn expect_number(name: &str, value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Number(number) => Ok(*number),
+        _ => Err(format!(
+            "{}() expects a number but got {}.",
+            name,
+            value.type_name()
+        )),
+    }
+}
+
+fn expect_string<'a>(name: &str, value: &'a Value) -> Result<&'a str, String> {
+    match value {
+        Value::String(string) => Ok(string),
+        _ => Err(format!(
+            "{}() expects a string but got {}.",
+            name,
+            value.type_name()
+        )),
+    }
+}
+
+fn expect_index(name: &str, value: &Value) -> Result<usize, String> {
+    let number = expect_number(name, value)?;
+    if number < 0.0 || number.fract() != 0.0 {
+        return Err(format!(
+            "{}() expects a non-negative integer index but got {}.",
+            name, number
+        ));
+    }
+    Ok(number as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::Interpreter;
+    use crate::stdlib;
+    use crate::value::Value;
+
+    #[test]
+    fn string_helpers_work_on_characters() {
+        let mut interpreter = Interpreter::new();
+        let string = Value::String("crème brûlée".into());
+
+        assert_eq!(
+            stdlib::len(&mut interpreter, std::slice::from_ref(&string)),
+            Ok(Value::Number(12.0))
+        );
+        assert_eq!(
+            stdlib::substr(
+                &mut interpreter,
+                &[string.clone(), Value::Number(6.0), Value::Number(12.0)]
+            ),
+            Ok(Value::String("brûlée".into()))
+        );
+        assert_eq!(
+            stdlib::index_of(&mut interpreter, &[string, Value::String("b".into())]),
+            Ok(Value::Number(6.0))
+        );
+        assert_eq!(
+            stdlib::substr(
+                &mut interpreter,
+                &[Value::from("abc"), Value::Number(1e30), Value::Number(1e30)]
+            ),
+            Err(
+                "substr() range 1000000000000000000000000000000..1000000000000000000000000000000 \
+                 is out of bounds for a string of length 3."
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn split_returns_a_list_of_parts() {
+        fn parts(string: &str, separator: &str) -> Vec<Value> {
+            let arguments = [Value::from(string), Value::from(separator)];
+            match stdlib::split(&mut Interpreter::new(), &arguments) {
+                Ok(Value::List(list)) => list.borrow().clone(),
+                result => panic!("split() returned {:?}", result),
+            }
+        }
+
+        assert_eq!(
+            parts("a, b,, c", ", "),
+            vec![Value::from("a"), Value::from("b,"), Value::from("c")]
+        );
+        assert_eq!(parts("", ","), vec![Value::from("")]);
+        assert_eq!(
+            parts("añb", ""),
+            vec![Value::from("a"), Value::from("ñ"), Value::from("b")]
+        );
+        assert_eq!(
+            stdlib::split(&mut Interpreter::new(), &[Value::from("a"), Value::Nil]),
+            Err("split() expects a string but got nil.".to_string())
+        );
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        let mut interpreter = Interpreter::new();
+
+        assert_eq!(
+            stdlib::str(&mut interpreter, &[Value::Number(2.5)]),
+            Ok(Value::String("2.5".into()))
+        );
+        assert_eq!(
+            stdlib::num(&mut interpreter, &[Value::String(" 2.5 ".into())]),
+            Ok(Value::Number(2.5))
+        );
+        assert!(stdlib::num(&mut interpreter, &[Value::String("sushi".into())]).is_err());
+    }
+
+    #[test]
+    fn assert_reports_its_message() {
+        let mut interpreter = Interpreter::new();
+
+        assert_eq!(
+            stdlib::assert(&mut interpreter, &[Value::Bool(true)]),
+            Ok(Value::Nil)
+        );
+        assert_eq!(
+            stdlib::assert(
+                &mut interpreter,
+                &[Value::Nil, Value::String("out of sushi".into())]
+            ),
+            Err("Assertion failed: out of sushi".to_string())
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::new());
+}
+
+/// An interned string. Every `Symbol` for the same text shares one allocation, so comparing and
+/// hashing symbols only looks at the pointer, never at the characters.
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    pub fn intern(string: &str) -> Symbol {
+        INTERNER.with(|interner| interner.borrow_mut().intern(string))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Shares the interned text without copying it.
+    pub fn as_rc(&self) -> Rc<str> {
+        Rc::clone(&self.0)
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Rc::as_ptr(&self.0) as *const u8).hash(state);
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+struct Interner {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    fn new() -> Interner {
+        Interner {
+            strings: HashSet::new(),
+        }
+    }
+
+    fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(interned) = self.strings.get(string) {
+            return Symbol(Rc::clone(interned));
+        }
+
+        let interned: Rc<str> = Rc::from(string);
+        self.strings.insert(Rc::clone(&interned));
+        Symbol(interned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::symbol::Symbol;
+
+    #[test]
+    fn interning_the_same_string_shares_storage() {
+        let first = Symbol::intern("breakfast");
+        let second = Symbol::intern(&String::from("breakfast"));
+
+        assert_eq!(first, second);
+        assert!(Rc::ptr_eq(&first.0, &second.0));
+    }
+
+    #[test]
+    fn interning_different_strings_yields_different_symbols() {
+        let first = Symbol::intern("breakfast");
+        let second = Symbol::intern("brunch");
+
+        assert_ne!(first, second);
+        assert_eq!(first.as_str(), "breakfast");
+        assert_eq!(second.as_str(), "brunch");
+    }
+}
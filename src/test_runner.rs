@@ -0,0 +1,158 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::interpreter::Interpreter;
+use crate::io::SharedBuffer;
+
+const EXPECT_OUTPUT: &str = "// expect: ";
+const EXPECT_ERROR: &str = "// expect error: ";
+const EXPECT_RUNTIME_ERROR: &str = "// expect runtime error: ";
+
+/// Runs every `.lox` script under the paths, which may be scripts or directories, and reports
+/// each result to `output`. Returns whether every script passed.
+pub fn run_tests(paths: &[PathBuf], output: &mut dyn Write) -> Result<bool, std::io::Error> {
+    let mut scripts = Vec::new();
+    for path in paths {
+        collect_scripts(path, &mut scripts)?;
+    }
+    scripts.sort();
+
+    let mut failed = 0;
+    for script in &scripts {
+        let source_code = std::fs::read_to_string(script)?;
+        let failures = run_test(&source_code)?;
+        if failures.is_empty() {
+            writeln!(output, "PASS {}", script.display())?;
+        } else {
+            failed += 1;
+            writeln!(output, "FAIL {}", script.display())?;
+            for failure in failures {
+                writeln!(output, "     {}", failure)?;
+            }
+        }
+    }
+
+    writeln!(
+        output,
+        "{} passed, {} failed.",
+        scripts.len() - failed,
+        failed
+    )?;
+    Ok(failed == 0)
+}
+
+fn collect_scripts(path: &Path, scripts: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+    if !path.is_dir() {
+        scripts.push(path.to_path_buf());
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() || path.extension().is_some_and(|extension| extension == "lox") {
+            collect_scripts(&path, scripts)?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs a test script in a fresh interpreter and compares what it prints against the
+/// expectations written in its comments:
+///
+/// - `// expect: <line>` for each line of output,
+/// - `// expect error: <message>` for an error reported on the comment's line before running,
+/// - `// expect runtime error: <message>` for the runtime error that stops the script.
+///
+/// Returns a description of each mismatch.
+pub fn run_test(source_code: &str) -> Result<Vec<String>, std::io::Error> {
+    let mut expected_output = Vec::new();
+    let mut expected_diagnostics = Vec::new();
+    for (index, line) in source_code.lines().enumerate() {
+        let line_number = index + 1;
+        if let Some((_, text)) = line.split_once(EXPECT_OUTPUT) {
+            expected_output.push(text.to_string());
+        } else if let Some((_, message)) = line.split_once(EXPECT_ERROR) {
+            expected_diagnostics.push(format!("[line {}] Error: {}", line_number, message));
+        } else if let Some((_, message)) = line.split_once(EXPECT_RUNTIME_ERROR) {
+            expected_diagnostics.push(message.to_string());
+            expected_diagnostics.push(format!("[line {}]", line_number));
+        }
+    }
+
+    let output = SharedBuffer::new();
+    let diagnostics = SharedBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+    interpreter.set_diagnostics(diagnostics.clone());
+    interpreter.set_input(std::io::empty());
+    interpreter.run(source_code)?;
+
+    let mut failures = Vec::new();
+    compare(
+        "output",
+        &expected_output,
+        &output.contents(),
+        &mut failures,
+    );
+    compare(
+        "error",
+        &expected_diagnostics,
+        &diagnostics.contents(),
+        &mut failures,
+    );
+    Ok(failures)
+}
+
+fn compare(kind: &str, expected: &[String], actual: &str, failures: &mut Vec<String>) {
+    let actual: Vec<&str> = actual.lines().collect();
+    for index in 0..expected.len().max(actual.len()) {
+        match (expected.get(index), actual.get(index)) {
+            (Some(expected), Some(actual)) if expected == actual => {}
+            (Some(expected), Some(actual)) => failures.push(format!(
+                "Expected {} '{}' but got '{}'.",
+                kind, expected, actual
+            )),
+            (Some(expected), None) => {
+                failures.push(format!("Missing expected {} '{}'.", kind, expected))
+            }
+            (None, Some(actual)) => failures.push(format!("Unexpected {} '{}'.", kind, actual)),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_runner::run_test;
+
+    struct TestCase {
+        input: &'static str,
+        expected_output: Vec<&'static str>,
+    }
+
+    #[test]
+    fn scripts_are_checked_against_their_expectations() {
+        let test_cases = vec![
+            TestCase {
+                input: "print 1 + 2; // expect: 3\nprint \"a\"; // expect: a\n",
+                expected_output: vec![],
+            },
+            TestCase {
+                input: "print 1; // expect: 2\nprint 3;\n",
+                expected_output: vec!["Expected output '2' but got '1'.", "Unexpected output '3'."],
+            },
+            TestCase {
+                input: "print 1; // expect: 1\nprint nil + 1; // expect runtime error: Operands must be two numbers or two strings.\n// expect: 2\n",
+                expected_output: vec!["Missing expected output '2'."],
+            },
+            TestCase {
+                input: "var a = 1;\n{ var b = 1; var b = 2; } // expect error: Already a variable with this name in this scope.\n",
+                expected_output: vec![],
+            },
+        ];
+
+        for test_case in test_cases {
+            let failures = run_test(test_case.input).unwrap();
+            assert_eq!(failures, test_case.expected_output, "{}", test_case.input);
+        }
+    }
+}
@@ -5,13 +5,38 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Semicolon,
     Comma,
+    Colon,
     Dot,
     Plus,
     Minus,
     Star,
     Slash,
+    Percent,
+    StarStar,
+    TildeSlash,
+    PlusPlus,
+    MinusMinus,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    StarStarEqual,
+    TildeSlashEqual,
+    AmpersandEqual,
+    PipeEqual,
+    CaretEqual,
+    LessLessEqual,
+    GreaterGreaterEqual,
+    Question,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
     Bang,
     BangEqual,
     Equal,
@@ -20,6 +45,8 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
 
     // Literals and Keywords
     Nil,
@@ -38,12 +65,40 @@ pub enum TokenType {
     This,
     Super,
     Print,
+    Import,
+    Export,
+    As,
+    From,
+    Try,
+    Catch,
+    Finally,
+    Throw,
+    Break,
+    Continue,
     String,
     Number,
     Identifier,
+
+    // Trivia, only kept when the scanner is asked to
+    Whitespace,
+    Comment,
 }
 
 impl TokenType {
+    /// Every reserved word recognized by `TokenType::from_str`. The scanner only treats `as` and
+    /// `from` as keywords within an import declaration.
+    pub const KEYWORDS: [&'static str; 26] = [
+        "nil", "true", "false", "and", "or", "if", "else", "for", "while", "var", "fun", "return",
+        "class", "this", "super", "print", "import", "export", "as", "from", "try", "catch",
+        "finally", "throw", "break", "continue",
+    ];
+
+    /// Whether tokens of this type only separate other tokens, without meaning anything.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, TokenType::Whitespace | TokenType::Comment)
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(identifier_literal: &str) -> Self {
         match identifier_literal {
             "nil" => TokenType::Nil,
@@ -62,6 +117,16 @@ impl TokenType {
             "this" => TokenType::This,
             "super" => TokenType::Super,
             "print" => TokenType::Print,
+            "import" => TokenType::Import,
+            "export" => TokenType::Export,
+            "as" => TokenType::As,
+            "from" => TokenType::From,
+            "try" => TokenType::Try,
+            "catch" => TokenType::Catch,
+            "finally" => TokenType::Finally,
+            "throw" => TokenType::Throw,
+            "break" => TokenType::Break,
+            "continue" => TokenType::Continue,
             _ => TokenType::Identifier,
         }
     }
@@ -75,7 +140,7 @@ pub struct Token<'a> {
 }
 
 impl<'a> Token<'a> {
-    pub fn new(token_type: TokenType, lexeme: &'a str, line_number: i32) -> Token {
+    pub fn new(token_type: TokenType, lexeme: &'a str, line_number: i32) -> Token<'a> {
         Token {
             token_type,
             lexeme,
@@ -83,3 +148,15 @@ impl<'a> Token<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::token::TokenType;
+
+    #[test]
+    fn keywords_are_not_identifiers() {
+        for keyword in TokenType::KEYWORDS {
+            assert_ne!(TokenType::from_str(keyword), TokenType::Identifier, "{}", keyword);
+        }
+    }
+}
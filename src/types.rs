@@ -0,0 +1,1004 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::{
+    AssignOperator, Class, Function, Imports, Literal, LogicalOperator, Node, Statement,
+    SumOperator, UnaryOperator,
+};
+use crate::symbol::Symbol;
+
+/// The type of a value as far as the checker can tell. `Any` is consistent with every other
+/// type in both directions, so code without annotations is never rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Any,
+    Nil,
+    Bool,
+    Number,
+    String,
+    /// A list, whose elements could be anything.
+    List,
+    /// A map, whose keys and values could be anything that can be stored there.
+    Map,
+    /// A function with its signature, or any function at all for the `Function` annotation.
+    Function(Option<Rc<Signature>>),
+    /// A class itself, which is called to make instances.
+    Class(Rc<ClassType>),
+    /// An instance of a class, which is what a class name means in an annotation.
+    Instance(Rc<ClassType>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Signature {
+    pub parameters: Vec<Type>,
+    pub returns: Type,
+}
+
+/// A class declared by the program. Classes are told apart by identity, not by name.
+#[derive(Debug)]
+pub struct ClassType {
+    pub name: Symbol,
+    pub superclass: Option<Rc<ClassType>>,
+    pub methods: RefCell<HashMap<Symbol, Rc<Signature>>>,
+}
+
+impl ClassType {
+    fn find_method(&self, name: &Symbol) -> Option<Rc<Signature>> {
+        match self.methods.borrow().get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self.superclass.as_ref()?.find_method(name),
+        }
+    }
+
+    fn is_subclass_of(&self, other: &ClassType) -> bool {
+        self == other
+            || self
+                .superclass
+                .as_ref()
+                .is_some_and(|superclass| superclass.is_subclass_of(other))
+    }
+}
+
+impl PartialEq for ClassType {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Type {
+    /// Whether a value of this type may be used where the other type is expected. A subclass's
+    /// instances may be used for its superclass's, but `nil` is only a `Nil` or `Any`.
+    pub fn is_assignable_to(&self, expected: &Type) -> bool {
+        match (self, expected) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Function(_) | Type::Class(_), Type::Function(None)) => true,
+            (Type::Function(None), Type::Function(_)) => true,
+            (Type::Function(Some(actual)), Type::Function(Some(expected))) => {
+                actual.parameters.len() == expected.parameters.len()
+                    && actual
+                        .parameters
+                        .iter()
+                        .zip(&expected.parameters)
+                        .all(|(actual, expected)| expected.is_assignable_to(actual))
+                    && actual.returns.is_assignable_to(&expected.returns)
+            }
+            (Type::Class(actual), Type::Class(expected))
+            | (Type::Instance(actual), Type::Instance(expected)) => actual.is_subclass_of(expected),
+            (actual, expected) => actual == expected,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Any => write!(f, "Any"),
+            Type::Nil => write!(f, "Nil"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Number => write!(f, "Number"),
+            Type::String => write!(f, "String"),
+            Type::List => write!(f, "List"),
+            Type::Map => write!(f, "Map"),
+            Type::Function(_) => write!(f, "Function"),
+            Type::Class(class) => write!(f, "class {}", class.name),
+            Type::Instance(class) => write!(f, "{}", class.name),
+        }
+    }
+}
+
+/// Checks the types of a program that has been resolved, using its annotations and what can be
+/// inferred from literals and operators. Anything unannotated is `Any`, so only code whose
+/// types are known can be rejected.
+pub struct TypeChecker {
+    // One map per enclosing block or function, innermost last, with the globals first.
+    scopes: Vec<HashMap<Symbol, Type>>,
+    // What the function being checked returns, and the class of the method being checked.
+    return_type: Option<Type>,
+    class: Option<Rc<ClassType>>,
+    line_number: i32,
+    errors: Vec<Error>,
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        TypeChecker::new()
+    }
+}
+
+impl TypeChecker {
+    pub fn new() -> TypeChecker {
+        TypeChecker {
+            scopes: vec![HashMap::new()],
+            return_type: None,
+            class: None,
+            line_number: 1,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Checks a whole program, returning every error found in line order.
+    pub fn check(&mut self, statements: &[Statement]) -> Result<(), Vec<Error>> {
+        self.scopes = vec![HashMap::new()];
+        self.errors.clear();
+        let declarations = self.declare_globals(statements);
+        for (statement, declaration) in statements.iter().zip(declarations) {
+            match (unexported(statement), declaration) {
+                (Statement::Function(function), Some(Type::Function(Some(signature)))) => {
+                    self.function_body(function, &signature, signature.returns.clone());
+                }
+                (Statement::Class(class), Some(Type::Class(class_type))) => {
+                    self.define(&class.name, Type::Class(Rc::clone(&class_type)));
+                    self.class_body(class, class_type);
+                }
+                (statement, _) => self.statement(statement),
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            self.errors.sort_by_key(|error| error.line_number);
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    // Globals may be used before they are declared, so the top-level functions and classes are
+    // given their types before anything is checked. Classes come first, for the annotations of
+    // the functions and methods to name.
+    fn declare_globals(&mut self, statements: &[Statement]) -> Vec<Option<Type>> {
+        let mut declarations: Vec<Option<Type>> = statements
+            .iter()
+            .map(|statement| match unexported(statement) {
+                Statement::Class(class) => {
+                    let class_type = Type::Class(self.class_type(class));
+                    self.define(&class.name, class_type.clone());
+                    Some(class_type)
+                }
+                _ => None,
+            })
+            .collect();
+        for (statement, declaration) in statements.iter().zip(&mut declarations) {
+            match (unexported(statement), declaration) {
+                (Statement::Function(function), declaration) => {
+                    let function_type = Type::Function(Some(Rc::new(self.signature(function))));
+                    self.define(&function.name, function_type.clone());
+                    *declaration = Some(function_type);
+                }
+                (Statement::Class(class), Some(Type::Class(class_type))) => {
+                    self.declare_methods(class, class_type);
+                }
+                _ => {}
+            }
+        }
+        declarations
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(node, line_number) | Statement::Print(node, line_number) => {
+                self.line_number = *line_number;
+                self.node(node);
+            }
+            Statement::Var(name, annotation, initializer, line_number) => {
+                self.line_number = *line_number;
+                let declared = self.annotation(annotation.as_ref());
+                if let Some(initializer) = initializer {
+                    let actual = self.node(initializer);
+                    self.expect_variable(name, &actual, &declared);
+                }
+                self.define(name, declared);
+            }
+            Statement::Block(statements) => {
+                self.scopes.push(HashMap::new());
+                self.statements(statements);
+                self.scopes.pop();
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.node(condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While(condition, body) => {
+                self.node(condition);
+                self.statement(body);
+            }
+            Statement::For(initializer, condition, increment, body) => {
+                self.scopes.push(HashMap::new());
+                if let Some(initializer) = initializer {
+                    self.statement(initializer);
+                }
+                if let Some(condition) = condition {
+                    self.node(condition);
+                }
+                if let Some(increment) = increment {
+                    self.node(increment);
+                }
+                self.statement(body);
+                self.scopes.pop();
+            }
+            Statement::Function(function) => {
+                let signature = Rc::new(self.signature(function));
+                self.define(&function.name, Type::Function(Some(Rc::clone(&signature))));
+                self.function_body(function, &signature, signature.returns.clone());
+            }
+            Statement::Class(class) => {
+                let class_type = self.class_type(class);
+                self.define(&class.name, Type::Class(Rc::clone(&class_type)));
+                self.declare_methods(class, &class_type);
+                self.class_body(class, class_type);
+            }
+            Statement::Return(value, line_number) => {
+                self.line_number = *line_number;
+                let actual = match value {
+                    Some(value) => self.node(value),
+                    None => Type::Nil,
+                };
+                if let Some(expected) = &self.return_type {
+                    if !actual.is_assignable_to(expected) {
+                        let message =
+                            format!("Expected {} to be returned but found {}.", expected, actual);
+                        self.error(&message);
+                    }
+                }
+            }
+            // What is imported is only known once the program runs.
+            Statement::Import(_, imports, _) => match imports {
+                Imports::Module(name) => self.define(name, Type::Any),
+                Imports::Names(names) => {
+                    for name in names {
+                        self.define(name, Type::Any);
+                    }
+                }
+            },
+            Statement::Export(declaration) => self.statement(declaration),
+            Statement::Try(body, catch, finally) => {
+                self.statement(body);
+                // Anything can be thrown.
+                if let Some(catch) = catch {
+                    self.scopes.push(HashMap::new());
+                    self.define(&catch.name, Type::Any);
+                    self.statement(&catch.body);
+                    self.scopes.pop();
+                }
+                if let Some(finally) = finally {
+                    self.statement(finally);
+                }
+            }
+            Statement::Throw(value, line_number) => {
+                self.line_number = *line_number;
+                self.node(value);
+            }
+            Statement::Labelled(_, body) => self.statement(body),
+            Statement::Break(..) | Statement::Continue(..) => {}
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    // Makes the type of a class, without its methods, which may name the class in annotations.
+    fn class_type(&mut self, class: &Class) -> Rc<ClassType> {
+        self.line_number = class.line_number;
+        let superclass = match class
+            .superclass
+            .as_ref()
+            .map(|superclass| self.node(superclass))
+        {
+            Some(Type::Class(superclass)) => Some(superclass),
+            Some(Type::Any) | None => None,
+            Some(_) => {
+                self.error("Superclass must be a class.");
+                None
+            }
+        };
+        Rc::new(ClassType {
+            name: class.name.clone(),
+            superclass,
+            methods: RefCell::new(HashMap::new()),
+        })
+    }
+
+    fn declare_methods(&mut self, class: &Class, class_type: &Rc<ClassType>) {
+        for method in &class.methods {
+            let mut signature = self.signature(method);
+            // An initializer always returns its instance, whatever it is annotated with.
+            if method.name.as_str() == "init" {
+                signature.returns = Type::Instance(Rc::clone(class_type));
+            }
+            class_type
+                .methods
+                .borrow_mut()
+                .insert(method.name.clone(), Rc::new(signature));
+        }
+    }
+
+    fn class_body(&mut self, class: &Class, class_type: Rc<ClassType>) {
+        let enclosing = self.class.replace(Rc::clone(&class_type));
+        for method in &class.methods {
+            let signature = class_type
+                .find_method(&method.name)
+                .expect("methods are declared before their bodies are checked");
+            // The only return an initializer may have is a bare `return;`.
+            let returns = match method.name.as_str() {
+                "init" => Type::Any,
+                _ => signature.returns.clone(),
+            };
+            self.function_body(method, &signature, returns);
+        }
+        self.class = enclosing;
+    }
+
+    fn signature(&mut self, function: &Function) -> Signature {
+        self.line_number = function.line_number;
+        let parameters = function
+            .parameter_types
+            .iter()
+            .map(|annotation| self.annotation(annotation.as_ref()))
+            .collect();
+        Signature {
+            parameters,
+            returns: self.annotation(function.return_type.as_ref()),
+        }
+    }
+
+    fn function_body(&mut self, function: &Function, signature: &Signature, returns: Type) {
+        let enclosing = self.return_type.replace(returns.clone());
+        self.scopes.push(HashMap::new());
+        for (parameter, parameter_type) in function.parameters.iter().zip(&signature.parameters) {
+            self.define(parameter, parameter_type.clone());
+        }
+        self.statements(&function.body);
+        self.scopes.pop();
+        self.return_type = enclosing;
+
+        if !Type::Nil.is_assignable_to(&returns) && !always_returns(&function.body) {
+            self.line_number = function.line_number;
+            self.error(&format!(
+                "'{}' can end without returning {}.",
+                function.name, returns
+            ));
+        }
+    }
+
+    fn node(&mut self, node: &Node) -> Type {
+        match node {
+            Node::Expression(expression) | Node::Grouping(expression) => self.node(expression),
+            Node::Series(left, right) => {
+                self.node(left);
+                self.node(right)
+            }
+            Node::Assign(name, value, _, line_number) => {
+                self.line_number = *line_number;
+                let actual = self.node(value);
+                let declared = self.lookup(name);
+                self.expect_variable(name, &actual, &declared);
+                actual
+            }
+            Node::CompoundAssign(operator, target, value, line_number) => {
+                let target = self.node(target);
+                let value = self.node(value);
+                self.line_number = *line_number;
+                match operator {
+                    AssignOperator::PlusEqual => self.plus(target, value),
+                    _ => {
+                        self.number_operands(&target, &value);
+                        Type::Number
+                    }
+                }
+            }
+            Node::Increment(_, _, operand, line_number) => {
+                let operand = self.node(operand);
+                self.line_number = *line_number;
+                if !operand.is_assignable_to(&Type::Number) {
+                    self.error("Operand must be a number.");
+                }
+                Type::Number
+            }
+            // Either branch may be the result, so it only has a type when both do.
+            Node::Conditional(condition, then_value, else_value) => {
+                self.node(condition);
+                let then_value = self.node(then_value);
+                let else_value = self.node(else_value);
+                if then_value == else_value {
+                    then_value
+                } else {
+                    Type::Any
+                }
+            }
+            // Either operand may be the result, so it only has a type when both do.
+            Node::Logical(LogicalOperator::And | LogicalOperator::Or, left, right) => {
+                let left = self.node(left);
+                let right = self.node(right);
+                if left == right {
+                    left
+                } else {
+                    Type::Any
+                }
+            }
+            Node::Equality(_, left, right) => {
+                self.node(left);
+                self.node(right);
+                Type::Bool
+            }
+            Node::Comparison(_, left, right) => {
+                self.numbers(left, right);
+                Type::Bool
+            }
+            Node::Sum(SumOperator::Plus, left, right) => {
+                let left = self.node(left);
+                let right = self.node(right);
+                self.plus(left, right)
+            }
+            Node::Sum(SumOperator::Minus, left, right)
+            | Node::Product(_, left, right)
+            | Node::Power(left, right)
+            | Node::Bitwise(_, left, right)
+            | Node::Shift(_, left, right) => {
+                self.numbers(left, right);
+                Type::Number
+            }
+            Node::Unary(UnaryOperator::Minus | UnaryOperator::Tilde, operand) => {
+                if !self.node(operand).is_assignable_to(&Type::Number) {
+                    self.error("Operand must be a number.");
+                }
+                Type::Number
+            }
+            Node::Unary(UnaryOperator::Bang, operand) => {
+                self.node(operand);
+                Type::Bool
+            }
+            Node::Call(callee, arguments, line_number) => {
+                let callee = self.node(callee);
+                let arguments: Vec<Type> = arguments
+                    .iter()
+                    .map(|argument| self.node(argument))
+                    .collect();
+                self.line_number = *line_number;
+                self.call(callee, &arguments)
+            }
+            Node::Get(object, name, line_number) => {
+                let object = self.node(object);
+                self.line_number = *line_number;
+                match object {
+                    // Fields aren't declared, so only methods have known types.
+                    Type::Instance(class) => class
+                        .find_method(name)
+                        .map_or(Type::Any, |method| Type::Function(Some(method))),
+                    Type::List | Type::Map => {
+                        let method = match object {
+                            Type::List => list_method(name),
+                            _ => map_method(name),
+                        };
+                        match method {
+                            Some(method) => Type::Function(Some(Rc::new(method))),
+                            None => {
+                                self.error(&format!("Undefined property '{}'.", name));
+                                Type::Any
+                            }
+                        }
+                    }
+                    Type::Any => Type::Any,
+                    _ => {
+                        self.error("Only instances have properties.");
+                        Type::Any
+                    }
+                }
+            }
+            Node::Set(object, _, value, line_number) => {
+                let object = self.node(object);
+                self.line_number = *line_number;
+                if !matches!(object, Type::Instance(_) | Type::Any) {
+                    self.error("Only instances have fields.");
+                }
+                self.node(value)
+            }
+            Node::List(elements) => {
+                for element in elements {
+                    self.node(element);
+                }
+                Type::List
+            }
+            Node::Map(entries, line_number) => {
+                for (key, value) in entries {
+                    let key = self.node(key);
+                    self.node(value);
+                    self.line_number = *line_number;
+                    self.key(&key);
+                }
+                Type::Map
+            }
+            // Elements could be anything, so only what is indexed and with what is checked.
+            Node::Index(object, index, line_number) => {
+                let object = self.node(object);
+                let index = self.node(index);
+                self.line_number = *line_number;
+                self.index(&object, &index);
+                Type::Any
+            }
+            Node::SetIndex(object, index, value, line_number) => {
+                let object = self.node(object);
+                let index = self.node(index);
+                self.line_number = *line_number;
+                self.index(&object, &index);
+                self.node(value)
+            }
+            Node::Slice(object, start, end, line_number) => {
+                let object = self.node(object);
+                let bounds: Vec<Type> = start
+                    .iter()
+                    .chain(end)
+                    .map(|bound| self.node(bound))
+                    .collect();
+                self.line_number = *line_number;
+                if !object.is_assignable_to(&Type::List) {
+                    self.error("Only lists can be sliced.");
+                }
+                if !bounds
+                    .iter()
+                    .all(|bound| bound.is_assignable_to(&Type::Number))
+                {
+                    self.error("List index must be an integer.");
+                }
+                Type::List
+            }
+            Node::This(..) => self
+                .class
+                .as_ref()
+                .map_or(Type::Any, |class| Type::Instance(Rc::clone(class))),
+            Node::Super(method, ..) => self
+                .class
+                .as_ref()
+                .and_then(|class| class.superclass.as_ref()?.find_method(method))
+                .map_or(Type::Any, |method| Type::Function(Some(method))),
+            Node::Variable(name, _, line_number) => {
+                self.line_number = *line_number;
+                self.lookup(name)
+            }
+            Node::Primary(literal) => match literal {
+                Literal::Nil => Type::Nil,
+                Literal::True | Literal::False => Type::Bool,
+                Literal::Number(_) => Type::Number,
+                Literal::String(_) => Type::String,
+            },
+        }
+    }
+
+    fn call(&mut self, callee: Type, arguments: &[Type]) -> Type {
+        let (signature, result) = match callee {
+            Type::Function(Some(signature)) => {
+                let returns = signature.returns.clone();
+                (signature, returns)
+            }
+            Type::Class(class) => {
+                let signature = class
+                    .find_method(&Symbol::intern("init"))
+                    .unwrap_or_else(|| {
+                        Rc::new(Signature {
+                            parameters: Vec::new(),
+                            returns: Type::Any,
+                        })
+                    });
+                (signature, Type::Instance(class))
+            }
+            Type::Function(None) | Type::Any => return Type::Any,
+            _ => {
+                self.error("Can only call functions and classes.");
+                return Type::Any;
+            }
+        };
+
+        if arguments.len() != signature.parameters.len() {
+            self.error(&format!(
+                "Expected {} arguments but got {}.",
+                signature.parameters.len(),
+                arguments.len()
+            ));
+        }
+        for (index, (actual, expected)) in arguments.iter().zip(&signature.parameters).enumerate() {
+            if !actual.is_assignable_to(expected) {
+                self.error(&format!(
+                    "Expected {} for argument {} but found {}.",
+                    expected,
+                    index + 1,
+                    actual
+                ));
+            }
+        }
+        result
+    }
+
+    // Anything else could be either a list or a map, so its index is only checked as a key.
+    fn index(&mut self, object: &Type, index: &Type) {
+        match object {
+            Type::List => {
+                if !index.is_assignable_to(&Type::Number) {
+                    self.error("List index must be an integer.");
+                }
+            }
+            Type::Map | Type::Any => self.key(index),
+            _ => self.error("Only lists and maps can be indexed."),
+        }
+    }
+
+    fn key(&mut self, key: &Type) {
+        if !matches!(
+            key,
+            Type::Any | Type::Nil | Type::Bool | Type::Number | Type::String
+        ) {
+            self.error("Map key must be nil, a boolean, a number or a string.");
+        }
+    }
+
+    fn numbers(&mut self, left: &Node, right: &Node) {
+        let left = self.node(left);
+        let right = self.node(right);
+        self.number_operands(&left, &right);
+    }
+
+    fn number_operands(&mut self, left: &Type, right: &Type) {
+        if !left.is_assignable_to(&Type::Number) || !right.is_assignable_to(&Type::Number) {
+            self.error("Operands must be numbers.");
+        }
+    }
+
+    fn plus(&mut self, left: Type, right: Type) -> Type {
+        match (left, right) {
+            (Type::Number | Type::Any, Type::Number) | (Type::Number, Type::Any) => Type::Number,
+            (Type::String | Type::Any, Type::String) | (Type::String, Type::Any) => Type::String,
+            (Type::Any, Type::Any) => Type::Any,
+            _ => {
+                self.error("Operands must be two numbers or two strings.");
+                Type::Any
+            }
+        }
+    }
+
+    // The type an annotation names: a built-in type, or the instances of a class.
+    fn annotation(&mut self, annotation: Option<&Symbol>) -> Type {
+        let Some(name) = annotation else {
+            return Type::Any;
+        };
+        match name.as_str() {
+            "Any" => Type::Any,
+            "Nil" => Type::Nil,
+            "Bool" => Type::Bool,
+            "Number" => Type::Number,
+            "String" => Type::String,
+            "List" => Type::List,
+            "Map" => Type::Map,
+            "Function" => Type::Function(None),
+            _ => match self.lookup(name) {
+                Type::Class(class) => Type::Instance(class),
+                _ => {
+                    self.error(&format!("Unknown type '{}'.", name));
+                    Type::Any
+                }
+            },
+        }
+    }
+
+    fn expect_variable(&mut self, name: &Symbol, actual: &Type, declared: &Type) {
+        if !actual.is_assignable_to(declared) {
+            self.error(&format!(
+                "Expected {} for '{}' but found {}.",
+                declared, name, actual
+            ));
+        }
+    }
+
+    fn define(&mut self, name: &Symbol, declared: Type) {
+        self.scopes
+            .last_mut()
+            .expect("there is always a global scope")
+            .insert(name.clone(), declared);
+    }
+
+    // Names that aren't declared by the program, like natives, could be anything.
+    fn lookup(&self, name: &Symbol) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+            .unwrap_or(Type::Any)
+    }
+
+    fn error(&mut self, message: &str) {
+        self.errors.push(Error::new(self.line_number, message));
+    }
+}
+
+// The signature of the method of lists with the given name, if there is one.
+fn list_method(name: &Symbol) -> Option<Signature> {
+    let (parameters, returns) = match name.as_str() {
+        "push" => (vec![Type::Any], Type::Nil),
+        "pop" => (vec![], Type::Any),
+        "len" => (vec![], Type::Number),
+        "insert" => (vec![Type::Number, Type::Any], Type::Nil),
+        "remove" => (vec![Type::Number], Type::Any),
+        "contains" => (vec![Type::Any], Type::Bool),
+        _ => return None,
+    };
+    Some(Signature {
+        parameters,
+        returns,
+    })
+}
+
+// The signature of the method of maps with the given name, if there is one.
+fn map_method(name: &Symbol) -> Option<Signature> {
+    let (parameters, returns) = match name.as_str() {
+        "keys" | "values" => (vec![], Type::List),
+        "has" | "delete" => (vec![Type::Any], Type::Bool),
+        _ => return None,
+    };
+    Some(Signature {
+        parameters,
+        returns,
+    })
+}
+
+// Whether running the statements always ends in a `return`.
+fn always_returns(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Return(..) => true,
+        Statement::Block(statements) => always_returns(statements),
+        Statement::If(_, then_branch, Some(else_branch)) => {
+            always_returns(std::slice::from_ref(then_branch))
+                && always_returns(std::slice::from_ref(else_branch))
+        }
+        _ => false,
+    })
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub line_number: i32,
+    pub message: String,
+}
+
+impl Error {
+    fn new(line_number: i32, message: &str) -> Error {
+        Error {
+            line_number,
+            message: message.to_string(),
+        }
+    }
+}
+
+// The declaration an export statement exports, or else the statement itself.
+fn unexported(statement: &Statement) -> &Statement {
+    match statement {
+        Statement::Export(declaration) => declaration,
+        statement => statement,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::scanner::Scanner;
+
+    use super::TypeChecker;
+
+    struct TestCase {
+        input: &'static str,
+        expected_output: Vec<(i32, &'static str)>,
+    }
+
+    fn check(source_code: &str) -> Vec<(i32, String)> {
+        let tokens = Scanner::new().scan(source_code).unwrap();
+        let statements = RecursiveDescentParser::new()
+            .parse_program(&tokens)
+            .unwrap();
+        match TypeChecker::new().check(&statements) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .into_iter()
+                .map(|error| (error.line_number, error.message))
+                .collect(),
+        }
+    }
+
+    fn assert_errors(test_cases: Vec<TestCase>) {
+        for test_case in test_cases {
+            let expected_output: Vec<(i32, String)> = test_case
+                .expected_output
+                .into_iter()
+                .map(|(line_number, message)| (line_number, message.to_string()))
+                .collect();
+            assert_eq!(
+                check(test_case.input),
+                expected_output,
+                "{}",
+                test_case.input
+            );
+        }
+    }
+
+    #[test]
+    fn unannotated_code_is_any() {
+        let source_code = "var a = 1; a = \"b\"; fun f(x) { return x + 1; } f(\"c\")(nil).d = -a;";
+
+        assert_eq!(check(source_code), []);
+    }
+
+    #[test]
+    fn literals_and_operators_are_inferred() {
+        let test_cases = vec![
+            TestCase {
+                input: "var a: Number = 1 + 2 * 3;\nvar b: String = \"x\" + \"y\";\nvar c: Bool = !nil and 1 < 2;",
+                expected_output: vec![],
+            },
+            TestCase {
+                input: "var a: String = 1 + 2;\nvar b: Number = -\"c\";\nprint 1 < \"d\";",
+                expected_output: vec![
+                    (1, "Expected String for 'a' but found Number."),
+                    (2, "Operand must be a number."),
+                    (3, "Operands must be numbers."),
+                ],
+            },
+            TestCase {
+                input: "var a;\nvar b: Number = a + 1;\nprint 1 + \"e\";\nprint nil - a;",
+                expected_output: vec![
+                    (3, "Operands must be two numbers or two strings."),
+                    (4, "Operands must be numbers."),
+                ],
+            },
+            TestCase {
+                input: "var a: Number = 1;\na = true;\nvar b: Nil = a == 1 or nil;",
+                expected_output: vec![
+                    (2, "Expected Number for 'a' but found Bool."),
+                ],
+            },
+            TestCase {
+                input: "var a: Number = 2 ** 3 % 2;\nvar b: String = a ? \"c\" : \"d\";\nb += a;\nb++;\na -= a ~/ 2;\nvar c: String = a ? 1 : 2;",
+                expected_output: vec![
+                    (3, "Operands must be two numbers or two strings."),
+                    (4, "Operand must be a number."),
+                    (6, "Expected String for 'c' but found Number."),
+                ],
+            },
+            TestCase {
+                input: "var a: Number = ~1 & 2 | 3 << 4;\nvar b: String = a >> 1;\nprint ~\"c\" ^ nil;",
+                expected_output: vec![
+                    (2, "Expected String for 'b' but found Number."),
+                    (3, "Operand must be a number."),
+                    (3, "Operands must be numbers."),
+                ],
+            },
+        ];
+
+        assert_errors(test_cases);
+    }
+
+    #[test]
+    fn functions_are_checked_against_their_signatures() {
+        let test_cases = vec![
+            TestCase {
+                input: "fun f(a: Number, b): String {\n  return g(a) + b;\n}\nfun g(c: Number): String {\n  if (c > 0) return \"+\"; else return \"-\";\n}",
+                expected_output: vec![],
+            },
+            TestCase {
+                input: "fun f(a: Number): String {\n  return a;\n}\nf(\"b\", 2);\nvar g: Function = f;\nvar h: Number = f(1);",
+                expected_output: vec![
+                    (2, "Expected String to be returned but found Number."),
+                    (4, "Expected 1 arguments but got 2."),
+                    (4, "Expected Number for argument 1 but found String."),
+                    (6, "Expected Number for 'h' but found String."),
+                ],
+            },
+            TestCase {
+                input: "fun f(a: Number): Bool {\n  if (a > 0) return true;\n}\nfun g(): Numbr {}\n1();",
+                expected_output: vec![
+                    (1, "'f' can end without returning Bool."),
+                    (4, "Unknown type 'Numbr'."),
+                    (5, "Can only call functions and classes."),
+                ],
+            },
+        ];
+
+        assert_errors(test_cases);
+    }
+
+    #[test]
+    fn classes_are_types_of_their_instances() {
+        let test_cases = vec![
+            TestCase {
+                input: "class A {\n  init(x: Number) { this.x = x; }\n  m(): A { return this; }\n}\nclass B < A {}\nvar a: A = B(1).m();\nfun f(a: A): Number { return a.x; }",
+                expected_output: vec![],
+            },
+            TestCase {
+                input: "class A { init(x: Number) {} }\nclass B < A {\n  m(): B { return super.m; }\n}\nvar b: B = A(\"x\");\nA.y;\ntrue.z = 2;\n{ class C < b {} }",
+                expected_output: vec![
+                    (5, "Expected Number for argument 1 but found String."),
+                    (5, "Expected B for 'b' but found A."),
+                    (6, "Only instances have properties."),
+                    (7, "Only instances have fields."),
+                    (8, "Superclass must be a class."),
+                ],
+            },
+        ];
+
+        assert_errors(test_cases);
+    }
+
+    #[test]
+    fn lists_are_indexed_by_numbers() {
+        let test_cases = vec![
+            TestCase {
+                input: "var a: List = [1, \"b\"];\nvar c: Number = a[0] + a.len();\na[-1] = a[1:];\na.push(nil);",
+                expected_output: vec![],
+            },
+            TestCase {
+                input: "var a: List = 1;\nvar b = [][\"c\"];\n\"d\"[0] = 1;\nvar e: Bool = [].pop() and [].len();\n[].size();\nprint 1[:\"f\"];",
+                expected_output: vec![
+                    (1, "Expected List for 'a' but found Number."),
+                    (2, "List index must be an integer."),
+                    (3, "Only lists and maps can be indexed."),
+                    (5, "Undefined property 'size'."),
+                    (6, "Only lists can be sliced."),
+                    (6, "List index must be an integer."),
+                ],
+            },
+        ];
+
+        assert_errors(test_cases);
+    }
+
+    #[test]
+    fn maps_are_indexed_by_hashable_keys() {
+        let test_cases = vec![
+            TestCase {
+                input: "var a: Map = {\"b\": 1, nil: [], 2: {}};
+var c: List = a.keys();
+a[true] = a.has(2) and a.delete(nil);
+fun f(x) { return x[[]]; }",
+                expected_output: vec![(4, "Map key must be nil, a boolean, a number or a string.")],
+            },
+            TestCase {
+                input: "var a: Map = [];
+var b: Map = {[]: 1};
+b[{}] = 2;
+var c: Number = b.keys();
+b.size;",
+                expected_output: vec![
+                    (1, "Expected Map for 'a' but found List."),
+                    (2, "Map key must be nil, a boolean, a number or a string."),
+                    (3, "Map key must be nil, a boolean, a number or a string."),
+                    (4, "Expected Number for 'c' but found List."),
+                    (5, "Undefined property 'size'."),
+                ],
+            },
+        ];
+
+        assert_errors(test_cases);
+    }
+}
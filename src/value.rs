@@ -0,0 +1,526 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::Function;
+use crate::environment::Environment;
+use crate::interpreter::Interpreter;
+use crate::symbol::Symbol;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(Rc<str>),
+    NativeFunction(Rc<NativeFunction>),
+    Closure(Rc<Closure>),
+    Class(Rc<Class>),
+    Instance(Rc<Instance>),
+    /// A list, which is shared by every value referring to it.
+    List(Rc<RefCell<Vec<Value>>>),
+    /// A map, which is shared by every value referring to it like a list.
+    Map(Rc<RefCell<Map>>),
+    Module(Rc<Module>),
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "bool",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::NativeFunction(_) | Value::Closure(_) => "function",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Module(_) => "module",
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(left), Value::Bool(right)) => left == right,
+            (Value::Number(left), Value::Number(right)) => left == right,
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::NativeFunction(left), Value::NativeFunction(right)) => Rc::ptr_eq(left, right),
+            (Value::Closure(left), Value::Closure(right)) => Rc::ptr_eq(left, right),
+            (Value::Class(left), Value::Class(right)) => Rc::ptr_eq(left, right),
+            (Value::Instance(left), Value::Instance(right)) => Rc::ptr_eq(left, right),
+            (Value::List(left), Value::List(right)) => Rc::ptr_eq(left, right),
+            (Value::Map(left), Value::Map(right)) => Rc::ptr_eq(left, right),
+            (Value::Module(left), Value::Module(right)) => Rc::ptr_eq(left, right),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, &mut Vec::new())
+    }
+}
+
+impl Value {
+    // Writes the value with each list or map in `containers`, which are being written around it,
+    // shown as `[...]` or `{...}` so that one containing itself doesn't recurse forever.
+    fn write(&self, f: &mut fmt::Formatter, containers: &mut Vec<*const ()>) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(boolean) => write!(f, "{}", boolean),
+            Value::Number(number) => write!(f, "{}", number),
+            Value::String(string) => write!(f, "{}", string),
+            Value::NativeFunction(native_function) => {
+                write!(f, "<native fn {}>", native_function.name)
+            }
+            Value::Closure(closure) => write!(f, "<fn {}>", closure.function.name),
+            Value::Class(class) => write!(f, "{}", class.name),
+            Value::Instance(instance) => write!(f, "{} instance", instance.class.name),
+            Value::List(list) => {
+                let container = Rc::as_ptr(list).cast::<()>();
+                if containers.contains(&container) {
+                    return write!(f, "[...]");
+                }
+                containers.push(container);
+                write!(f, "[")?;
+                for (index, element) in list.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.write(f, containers)?;
+                }
+                containers.pop();
+                write!(f, "]")
+            }
+            Value::Map(map) => {
+                let container = Rc::as_ptr(map).cast::<()>();
+                if containers.contains(&container) {
+                    return write!(f, "{{...}}");
+                }
+                containers.push(container);
+                write!(f, "{{")?;
+                for (index, (key, value)) in map.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", key)?;
+                    value.write(f, containers)?;
+                }
+                containers.pop();
+                write!(f, "}}")
+            }
+            Value::Module(module) => write!(f, "<module {}>", module.name),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(boolean: bool) -> Self {
+        Value::Bool(boolean)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Value::Number(number)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(string: &str) -> Self {
+        Value::String(Rc::from(string))
+    }
+}
+
+impl From<String> for Value {
+    fn from(string: String) -> Self {
+        Value::String(Rc::from(string))
+    }
+}
+
+impl From<Rc<str>> for Value {
+    fn from(string: Rc<str>) -> Self {
+        Value::String(string)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(elements: Vec<Value>) -> Self {
+        Value::List(Rc::new(RefCell::new(elements)))
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::Nil
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(option: Option<T>) -> Self {
+        option.map_or(Value::Nil, Into::into)
+    }
+}
+
+/// The error produced when a Lox value can't be converted into the requested Rust type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl TypeError {
+    fn new(expected: &'static str, found: &Value) -> TypeError {
+        TypeError {
+            expected,
+            found: found.type_name(),
+        }
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {} but got {}", self.expected, self.found)
+    }
+}
+
+impl TryFrom<&Value> for bool {
+    type Error = TypeError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(boolean) => Ok(*boolean),
+            _ => Err(TypeError::new("a bool", value)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for f64 {
+    type Error = TypeError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Number(number) => Ok(*number),
+            _ => Err(TypeError::new("a number", value)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for String {
+    type Error = TypeError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(string) => Ok(string.to_string()),
+            _ => Err(TypeError::new("a string", value)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Rc<str> {
+    type Error = TypeError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(string) => Ok(Rc::clone(string)),
+            _ => Err(TypeError::new("a string", value)),
+        }
+    }
+}
+
+/// The number of arguments a native function accepts. Variadic functions validate their own
+/// argument count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Exact(usize),
+    Variadic,
+}
+
+impl Arity {
+    pub fn accepts(&self, argument_count: usize) -> bool {
+        match self {
+            Arity::Exact(arity) => *arity == argument_count,
+            Arity::Variadic => true,
+        }
+    }
+}
+
+/// The signature of a function implemented in Rust. An `Err` carries the message of the runtime
+/// error to raise at the call site.
+pub type NativeFn = dyn Fn(&mut Interpreter, &[Value]) -> Result<Value, String>;
+
+pub struct NativeFunction {
+    pub name: Symbol,
+    pub arity: Arity,
+    pub function: Box<NativeFn>,
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+/// A function declared in Lox, together with the scope it was declared in.
+pub struct Closure {
+    pub function: Rc<Function>,
+    pub environment: Rc<RefCell<Environment>>,
+    /// Whether this is the `init` method of a class, which always returns the instance.
+    pub is_initializer: bool,
+}
+
+impl Closure {
+    /// The method bound to an instance, in a scope where `this` is the instance.
+    pub fn bind(&self, instance: Rc<Instance>) -> Closure {
+        let mut environment = Environment::new(Some(Rc::clone(&self.environment)));
+        environment.define(Symbol::intern("this"), Value::Instance(instance));
+        Closure {
+            function: Rc::clone(&self.function),
+            environment: Rc::new(RefCell::new(environment)),
+            is_initializer: self.is_initializer,
+        }
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("name", &self.function.name)
+            .finish()
+    }
+}
+
+/// A class declared in Lox. Calling it creates an instance and runs its `init` method.
+pub struct Class {
+    pub name: Symbol,
+    pub superclass: Option<Rc<Class>>,
+    pub methods: HashMap<Symbol, Rc<Closure>>,
+}
+
+impl Class {
+    /// Looks a method up in this class and then in each superclass.
+    pub fn find_method(&self, name: &Symbol) -> Option<Rc<Closure>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self
+                .superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name)),
+        }
+    }
+
+    /// The number of arguments calling the class takes, which is that of its `init` method.
+    pub fn arity(&self) -> usize {
+        self.find_method(&Symbol::intern("init"))
+            .map_or(0, |initializer| initializer.function.parameters.len())
+    }
+}
+
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Class").field("name", &self.name).finish()
+    }
+}
+
+/// An instance of a Lox class. Fields can be added to it at any time.
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: RefCell<HashMap<Symbol, Value>>,
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Instance {
+        Instance {
+            class,
+            fields: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// A field of the instance, or else a method of its class bound to it.
+    pub fn get(self: &Rc<Self>, name: &Symbol) -> Option<Value> {
+        if let Some(value) = self.fields.borrow().get(name) {
+            return Some(value.clone());
+        }
+        self.class
+            .find_method(name)
+            .map(|method| Value::Closure(Rc::new(method.bind(Rc::clone(self)))))
+    }
+
+    pub fn set(&self, name: Symbol, value: Value) {
+        self.fields.borrow_mut().insert(name, value);
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Instance")
+            .field("class", &self.class.name)
+            .finish()
+    }
+}
+
+/// A file that has been imported. Only the globals it exports can be read from outside it, and
+/// they are read as they are now, not as they were when it was imported.
+pub struct Module {
+    /// The path of the file, as it was found.
+    pub name: String,
+    pub globals: Rc<RefCell<Environment>>,
+    pub exports: HashSet<Symbol>,
+}
+
+impl Module {
+    pub fn get(&self, name: &Symbol) -> Option<Value> {
+        if !self.exports.contains(name) {
+            return None;
+        }
+        self.globals.borrow().get(name)
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Module").field("name", &self.name).finish()
+    }
+}
+
+/// A value that can be a key of a map: nil, a boolean, a number or a string. A number is kept as
+/// its bits so that keys can be hashed, with `-0` made `0` so that numbers that are equal are the
+/// same key. NaN isn't equal to itself, so it can't be a key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Nil,
+    Bool(bool),
+    Number(u64),
+    String(Rc<str>),
+}
+
+impl TryFrom<&Value> for Key {
+    type Error = String;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Nil => Ok(Key::Nil),
+            Value::Bool(boolean) => Ok(Key::Bool(*boolean)),
+            Value::Number(number) if number.is_nan() => Err("NaN can't be a map key.".to_string()),
+            Value::Number(number) => Ok(Key::Number((number + 0.0).to_bits())),
+            Value::String(string) => Ok(Key::String(Rc::clone(string))),
+            _ => Err(format!(
+                "Map key must be nil, a boolean, a number or a string but got {}.",
+                value.type_name()
+            )),
+        }
+    }
+}
+
+impl From<&Key> for Value {
+    fn from(key: &Key) -> Self {
+        match key {
+            Key::Nil => Value::Nil,
+            Key::Bool(boolean) => Value::Bool(*boolean),
+            Key::Number(bits) => Value::Number(f64::from_bits(*bits)),
+            Key::String(string) => Value::String(Rc::clone(string)),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Value::from(self))
+    }
+}
+
+/// The entries of a Lox map, which are iterated in the order their keys were first added.
+#[derive(Debug, Default)]
+pub struct Map {
+    keys: Vec<Key>,
+    values: HashMap<Key, Value>,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn insert(&mut self, key: Key, value: Value) {
+        if self.values.insert(key.clone(), value).is_none() {
+            self.keys.push(key);
+        }
+    }
+
+    pub fn remove(&mut self, key: &Key) -> Option<Value> {
+        let value = self.values.remove(key)?;
+        self.keys.retain(|other| other != key);
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+        self.keys.iter().map(|key| (key, &self.values[key]))
+    }
+}
+
+/// A plain Rust function or closure that can be exposed to Lox. Arguments are converted from Lox
+/// values with `TryFrom<&Value>` and the result is converted back with `Into<Value>`.
+///
+/// `Args` is the tuple of argument types and only exists to tell the implementations apart.
+pub trait HostFunction<Args> {
+    fn arity(&self) -> usize;
+    fn call(&self, name: &str, arguments: &[Value]) -> Result<Value, String>;
+}
+
+macro_rules! impl_host_function {
+    ($($argument:ident),*) => {
+        impl<F, R, $($argument),*> HostFunction<($($argument,)*)> for F
+        where
+            F: Fn($($argument),*) -> R,
+            R: Into<Value>,
+            $($argument: for<'a> TryFrom<&'a Value, Error = TypeError>,)*
+        {
+            fn arity(&self) -> usize {
+                let arguments: &[&str] = &[$(stringify!($argument)),*];
+                arguments.len()
+            }
+
+            #[allow(unused_variables, unused_mut)]
+            fn call(&self, name: &str, arguments: &[Value]) -> Result<Value, String> {
+                let mut arguments = arguments.iter();
+                let result = self($(
+                    <$argument>::try_from(arguments.next().unwrap()).map_err(|error| {
+                        format!("{}() expects {} but got {}.", name, error.expected, error.found)
+                    })?
+                ),*);
+                Ok(result.into())
+            }
+        }
+    };
+}
+
+impl_host_function!();
+impl_host_function!(A);
+impl_host_function!(A, B);
+impl_host_function!(A, B, C);
+impl_host_function!(A, B, C, D);
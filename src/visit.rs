@@ -0,0 +1,421 @@
+use crate::ast::{
+    AssignOperator, BitwiseOperator, Catch, Class, ComparisonOperator, EqualityOperator, Fixity,
+    Function, Imports, IncrementOperator, Literal, LogicalOperator, Node, ProductOperator,
+    ShiftOperator, Statement, SumOperator, UnaryOperator,
+};
+use crate::symbol::Symbol;
+
+/// Walks the AST without changing it. Each method is called for one kind of statement or node,
+/// and by default visits its children in source order, so implementations only override the
+/// kinds they care about.
+pub trait Visitor {
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_expression_statement(&mut self, expression: &Node, _line_number: i32) {
+        self.visit_node(expression)
+    }
+
+    fn visit_print(&mut self, expression: &Node, _line_number: i32) {
+        self.visit_node(expression)
+    }
+
+    fn visit_var(
+        &mut self,
+        _name: &Symbol,
+        _annotation: Option<&Symbol>,
+        initializer: Option<&Node>,
+        _line_number: i32,
+    ) {
+        if let Some(initializer) = initializer {
+            self.visit_node(initializer);
+        }
+    }
+
+    fn visit_block(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.visit_statement(statement);
+        }
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Node,
+        then_branch: &Statement,
+        else_branch: Option<&Statement>,
+    ) {
+        self.visit_node(condition);
+        self.visit_statement(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.visit_statement(else_branch);
+        }
+    }
+
+    fn visit_while(&mut self, condition: &Node, body: &Statement) {
+        self.visit_node(condition);
+        self.visit_statement(body);
+    }
+
+    fn visit_for(
+        &mut self,
+        initializer: Option<&Statement>,
+        condition: Option<&Node>,
+        increment: Option<&Node>,
+        body: &Statement,
+    ) {
+        if let Some(initializer) = initializer {
+            self.visit_statement(initializer);
+        }
+        if let Some(condition) = condition {
+            self.visit_node(condition);
+        }
+        if let Some(increment) = increment {
+            self.visit_node(increment);
+        }
+        self.visit_statement(body);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        for statement in &function.body {
+            self.visit_statement(statement);
+        }
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        if let Some(superclass) = &class.superclass {
+            self.visit_node(superclass);
+        }
+        for method in &class.methods {
+            self.visit_method(method);
+        }
+    }
+
+    fn visit_method(&mut self, method: &Function) {
+        for statement in &method.body {
+            self.visit_statement(statement);
+        }
+    }
+
+    fn visit_return(&mut self, value: Option<&Node>, _line_number: i32) {
+        if let Some(value) = value {
+            self.visit_node(value);
+        }
+    }
+
+    fn visit_import(&mut self, _path: &Symbol, _imports: &Imports, _line_number: i32) {}
+
+    fn visit_export(&mut self, declaration: &Statement) {
+        self.visit_statement(declaration)
+    }
+
+    fn visit_try(&mut self, body: &Statement, catch: Option<&Catch>, finally: Option<&Statement>) {
+        self.visit_statement(body);
+        if let Some(catch) = catch {
+            self.visit_statement(&catch.body);
+        }
+        if let Some(finally) = finally {
+            self.visit_statement(finally);
+        }
+    }
+
+    fn visit_throw(&mut self, value: &Node, _line_number: i32) {
+        self.visit_node(value);
+    }
+
+    fn visit_labelled(&mut self, _label: &Symbol, body: &Statement) {
+        self.visit_statement(body);
+    }
+
+    fn visit_break(&mut self, _label: Option<&Symbol>, _line_number: i32) {}
+
+    fn visit_continue(&mut self, _label: Option<&Symbol>, _line_number: i32) {}
+
+    fn visit_node(&mut self, node: &Node) {
+        walk_node(self, node)
+    }
+
+    fn visit_expression(&mut self, expression: &Node) {
+        self.visit_node(expression)
+    }
+
+    fn visit_grouping(&mut self, expression: &Node) {
+        self.visit_node(expression)
+    }
+
+    fn visit_series(&mut self, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+    }
+
+    fn visit_assign(&mut self, _name: &Symbol, value: &Node, _line_number: i32) {
+        self.visit_node(value)
+    }
+
+    fn visit_compound_assign(
+        &mut self,
+        _operator: &AssignOperator,
+        target: &Node,
+        value: &Node,
+        _line_number: i32,
+    ) {
+        self.visit_node(target);
+        self.visit_node(value);
+    }
+
+    fn visit_increment(
+        &mut self,
+        _operator: &IncrementOperator,
+        _fixity: &Fixity,
+        operand: &Node,
+        _line_number: i32,
+    ) {
+        self.visit_node(operand)
+    }
+
+    fn visit_conditional(&mut self, condition: &Node, then_value: &Node, else_value: &Node) {
+        self.visit_node(condition);
+        self.visit_node(then_value);
+        self.visit_node(else_value);
+    }
+
+    fn visit_logical(&mut self, _operator: &LogicalOperator, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+    }
+
+    fn visit_equality(&mut self, _operator: &EqualityOperator, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+    }
+
+    fn visit_bitwise(&mut self, _operator: &BitwiseOperator, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+    }
+
+    fn visit_comparison(&mut self, _operator: &ComparisonOperator, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+    }
+
+    fn visit_shift(&mut self, _operator: &ShiftOperator, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+    }
+
+    fn visit_sum(&mut self, _operator: &SumOperator, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+    }
+
+    fn visit_product(&mut self, _operator: &ProductOperator, left: &Node, right: &Node) {
+        self.visit_node(left);
+        self.visit_node(right);
+    }
+
+    fn visit_power(&mut self, base: &Node, exponent: &Node) {
+        self.visit_node(base);
+        self.visit_node(exponent);
+    }
+
+    fn visit_unary(&mut self, _operator: &UnaryOperator, operand: &Node) {
+        self.visit_node(operand)
+    }
+
+    fn visit_call(&mut self, callee: &Node, arguments: &[Node], _line_number: i32) {
+        self.visit_node(callee);
+        for argument in arguments {
+            self.visit_node(argument);
+        }
+    }
+
+    fn visit_get(&mut self, object: &Node, _name: &Symbol, _line_number: i32) {
+        self.visit_node(object)
+    }
+
+    fn visit_set(&mut self, object: &Node, _name: &Symbol, value: &Node, _line_number: i32) {
+        self.visit_node(object);
+        self.visit_node(value);
+    }
+
+    fn visit_list(&mut self, elements: &[Node]) {
+        for element in elements {
+            self.visit_node(element);
+        }
+    }
+
+    fn visit_map(&mut self, entries: &[(Node, Node)], _line_number: i32) {
+        for (key, value) in entries {
+            self.visit_node(key);
+            self.visit_node(value);
+        }
+    }
+
+    fn visit_index(&mut self, object: &Node, index: &Node, _line_number: i32) {
+        self.visit_node(object);
+        self.visit_node(index);
+    }
+
+    fn visit_set_index(&mut self, object: &Node, index: &Node, value: &Node, _line_number: i32) {
+        self.visit_node(object);
+        self.visit_node(index);
+        self.visit_node(value);
+    }
+
+    fn visit_slice(
+        &mut self,
+        object: &Node,
+        start: Option<&Node>,
+        end: Option<&Node>,
+        _line_number: i32,
+    ) {
+        self.visit_node(object);
+        if let Some(start) = start {
+            self.visit_node(start);
+        }
+        if let Some(end) = end {
+            self.visit_node(end);
+        }
+    }
+
+    fn visit_this(&mut self, _line_number: i32) {}
+
+    fn visit_super(&mut self, _method: &Symbol, _line_number: i32) {}
+
+    fn visit_variable(&mut self, _name: &Symbol, _line_number: i32) {}
+
+    fn visit_literal(&mut self, _literal: &Literal) {}
+}
+
+/// Calls the visitor's method for the kind of the statement.
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Expression(expression, line_number) => {
+            visitor.visit_expression_statement(expression, *line_number)
+        }
+        Statement::Print(expression, line_number) => visitor.visit_print(expression, *line_number),
+        Statement::Var(name, annotation, initializer, line_number) => visitor.visit_var(
+            name,
+            annotation.as_ref(),
+            initializer.as_ref(),
+            *line_number,
+        ),
+        Statement::Block(statements) => visitor.visit_block(statements),
+        Statement::If(condition, then_branch, else_branch) => {
+            visitor.visit_if(condition, then_branch, else_branch.as_deref())
+        }
+        Statement::While(condition, body) => visitor.visit_while(condition, body),
+        Statement::For(initializer, condition, increment, body) => visitor.visit_for(
+            initializer.as_deref(),
+            condition.as_ref(),
+            increment.as_ref(),
+            body,
+        ),
+        Statement::Function(function) => visitor.visit_function(function),
+        Statement::Class(class) => visitor.visit_class(class),
+        Statement::Return(value, line_number) => visitor.visit_return(value.as_ref(), *line_number),
+        Statement::Import(path, imports, line_number) => {
+            visitor.visit_import(path, imports, *line_number)
+        }
+        Statement::Export(declaration) => visitor.visit_export(declaration),
+        Statement::Try(body, catch, finally) => {
+            visitor.visit_try(body, catch.as_ref(), finally.as_deref())
+        }
+        Statement::Throw(value, line_number) => visitor.visit_throw(value, *line_number),
+        Statement::Labelled(label, body) => visitor.visit_labelled(label, body),
+        Statement::Break(label, line_number) => visitor.visit_break(label.as_ref(), *line_number),
+        Statement::Continue(label, line_number) => {
+            visitor.visit_continue(label.as_ref(), *line_number)
+        }
+    }
+}
+
+/// Calls the visitor's method for the kind of the node.
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    match node {
+        Node::Expression(expression) => visitor.visit_expression(expression),
+        Node::Grouping(expression) => visitor.visit_grouping(expression),
+        Node::Series(left, right) => visitor.visit_series(left, right),
+        Node::Assign(name, value, _, line_number) => {
+            visitor.visit_assign(name, value, *line_number)
+        }
+        Node::CompoundAssign(operator, target, value, line_number) => {
+            visitor.visit_compound_assign(operator, target, value, *line_number)
+        }
+        Node::Increment(operator, fixity, operand, line_number) => {
+            visitor.visit_increment(operator, fixity, operand, *line_number)
+        }
+        Node::Conditional(condition, then_value, else_value) => {
+            visitor.visit_conditional(condition, then_value, else_value)
+        }
+        Node::Logical(operator, left, right) => visitor.visit_logical(operator, left, right),
+        Node::Equality(operator, left, right) => visitor.visit_equality(operator, left, right),
+        Node::Bitwise(operator, left, right) => visitor.visit_bitwise(operator, left, right),
+        Node::Comparison(operator, left, right) => visitor.visit_comparison(operator, left, right),
+        Node::Shift(operator, left, right) => visitor.visit_shift(operator, left, right),
+        Node::Sum(operator, left, right) => visitor.visit_sum(operator, left, right),
+        Node::Product(operator, left, right) => visitor.visit_product(operator, left, right),
+        Node::Power(base, exponent) => visitor.visit_power(base, exponent),
+        Node::Unary(operator, operand) => visitor.visit_unary(operator, operand),
+        Node::Call(callee, arguments, line_number) => {
+            visitor.visit_call(callee, arguments, *line_number)
+        }
+        Node::Get(object, name, line_number) => visitor.visit_get(object, name, *line_number),
+        Node::Set(object, name, value, line_number) => {
+            visitor.visit_set(object, name, value, *line_number)
+        }
+        Node::List(elements) => visitor.visit_list(elements),
+        Node::Map(entries, line_number) => visitor.visit_map(entries, *line_number),
+        Node::Index(object, index, line_number) => visitor.visit_index(object, index, *line_number),
+        Node::SetIndex(object, index, value, line_number) => {
+            visitor.visit_set_index(object, index, value, *line_number)
+        }
+        Node::Slice(object, start, end, line_number) => {
+            visitor.visit_slice(object, start.as_deref(), end.as_deref(), *line_number)
+        }
+        Node::This(_, line_number) => visitor.visit_this(*line_number),
+        Node::Super(method, _, line_number) => visitor.visit_super(method, *line_number),
+        Node::Variable(name, _, line_number) => visitor.visit_variable(name, *line_number),
+        Node::Primary(literal) => visitor.visit_literal(literal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::recursive_descent::RecursiveDescentParser;
+    use crate::parser::Parse;
+    use crate::scanner::Scanner;
+    use crate::symbol::Symbol;
+
+    use super::Visitor;
+
+    // Collects the names of the variables read, in order.
+    #[derive(Default)]
+    struct Variables(Vec<String>);
+
+    impl Visitor for Variables {
+        fn visit_variable(&mut self, name: &Symbol, _line_number: i32) {
+            self.0.push(name.as_str().to_string());
+        }
+    }
+
+    #[test]
+    fn visitors_reach_every_node_in_source_order() {
+        let tokens = Scanner::new()
+            .scan("fun f(x) { for (var i = a; i < -b; c = d) if (e) return f(g); else print (h, i); } { var j = k and l; while (m) n; }")
+            .unwrap();
+        let statements = RecursiveDescentParser::new()
+            .parse_program(&tokens)
+            .unwrap();
+        let mut variables = Variables::default();
+        for statement in &statements {
+            variables.visit_statement(statement);
+        }
+
+        assert_eq!(
+            variables.0,
+            ["a", "i", "b", "d", "e", "f", "g", "h", "i", "k", "l", "m", "n"]
+        );
+    }
+}